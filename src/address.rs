@@ -0,0 +1,229 @@
+use std::{error::Error, fmt, str::FromStr};
+
+use bincode::{
+	Decode, Encode, de::Decoder, error::DecodeError, impl_borrow_decode,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+use sha3::{Digest, Sha3_256};
+
+use crate::config::{
+	ADDRESS_VERSION, MULTISIG_ADDRESS_VERSION, REWARD_INPUT_ADDRESS,
+};
+
+const HASH_SIZE: usize = 32;
+const CHECKSUM_SIZE: usize = 4;
+pub const ADDRESS_SIZE: usize = 1 + HASH_SIZE;
+
+/// Destination of coins: a version byte saying what owns the address and
+/// the SHA3 hash of the owner. Written as base58check, version and hash
+/// followed by a checksum, so a mistyped address fails to parse instead of
+/// paying to nobody.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Encode)]
+pub struct Address {
+	version: u8,
+	hash: [u8; HASH_SIZE],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressError {
+	Encoding,
+	Length,
+	Checksum,
+	Version(u8),
+}
+
+impl fmt::Display for AddressError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Encoding => f.write_str("Address is not base58."),
+			Self::Length => f.write_str("Address has the wrong length."),
+			Self::Checksum => f.write_str("Address checksum mismatch."),
+			Self::Version(version) => {
+				write!(f, "Unknown address version {version}.")
+			}
+		}
+	}
+}
+
+impl Error for AddressError {}
+
+impl Address {
+	pub const fn new(version: u8, hash: [u8; HASH_SIZE]) -> Self {
+		Self { version, hash }
+	}
+
+	/// Address of a wallet, from its protobuf encoded public key.
+	pub fn from_public_key(public_key: &[u8]) -> Self {
+		Self::new(ADDRESS_VERSION, Sha3_256::digest(public_key).into())
+	}
+
+	pub const fn version(&self) -> u8 {
+		self.version
+	}
+
+	pub fn to_bytes(&self) -> [u8; ADDRESS_SIZE] {
+		let mut bytes = [0; ADDRESS_SIZE];
+		bytes[0] = self.version;
+		bytes[1..].copy_from_slice(&self.hash);
+		bytes
+	}
+}
+
+impl FromStr for Address {
+	type Err = AddressError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let bytes = bs58::decode(s)
+			.into_vec()
+			.map_err(|_| AddressError::Encoding)?;
+		if bytes.len() != ADDRESS_SIZE + CHECKSUM_SIZE {
+			return Err(AddressError::Length);
+		}
+		let (body, sum) = bytes.split_at(ADDRESS_SIZE);
+		if sum != checksum(body) {
+			return Err(AddressError::Checksum);
+		}
+		let version = body[0];
+		check_version(version)?;
+		Ok(Self::new(version, body[1..].try_into().expect("Hash size.")))
+	}
+}
+
+// Addresses from the network are checked like parsed ones. The reward input
+// address is the only one with a reserved version, it appears in the input
+// of every reward transaction.
+impl<Context> Decode<Context> for Address {
+	fn decode<D: Decoder<Context = Context>>(
+		decoder: &mut D,
+	) -> Result<Self, DecodeError> {
+		let address = Self::new(
+			u8::decode(decoder)?,
+			<[u8; HASH_SIZE]>::decode(decoder)?,
+		);
+		if address != REWARD_INPUT_ADDRESS {
+			check_version(address.version)
+				.map_err(|err| DecodeError::OtherString(err.to_string()))?;
+		}
+		Ok(address)
+	}
+}
+
+impl_borrow_decode!(Address);
+
+impl fmt::Display for Address {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let body = self.to_bytes();
+		let mut bytes = body.to_vec();
+		bytes.extend(checksum(&body));
+		f.write_str(&bs58::encode(bytes).into_string())
+	}
+}
+
+impl Serialize for Address {
+	fn serialize<S: Serializer>(
+		&self,
+		serializer: S,
+	) -> Result<S::Ok, S::Error> {
+		serializer.collect_str(self)
+	}
+}
+
+impl<'de> Deserialize<'de> for Address {
+	fn deserialize<D: Deserializer<'de>>(
+		deserializer: D,
+	) -> Result<Self, D::Error> {
+		String::deserialize(deserializer)?
+			.parse()
+			.map_err(de::Error::custom)
+	}
+}
+
+fn check_version(version: u8) -> Result<(), AddressError> {
+	if version != ADDRESS_VERSION && version != MULTISIG_ADDRESS_VERSION {
+		return Err(AddressError::Version(version));
+	}
+	Ok(())
+}
+
+fn checksum(body: &[u8]) -> [u8; CHECKSUM_SIZE] {
+	let hash = Sha3_256::digest(Sha3_256::digest(body));
+	[hash[0], hash[1], hash[2], hash[3]]
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::config::REWARD_INPUT_ADDRESS;
+	use libp2p::identity::Keypair;
+	use pretty_assertions::assert_eq;
+
+	fn before_each() -> Address {
+		let keypair = Keypair::ed25519_from_bytes([7; 32]).unwrap();
+		Address::from_public_key(&keypair.public().encode_protobuf())
+	}
+
+	#[test]
+	fn round_trips_through_text() {
+		let address = before_each();
+
+		assert_eq!(address.to_string().parse(), Ok(address));
+	}
+
+	#[test]
+	fn rejects_mistyped_addresses() {
+		let text = before_each().to_string();
+		let mut typo = text.clone().into_bytes();
+		typo[10] = if typo[10] == b'2' { b'3' } else { b'2' };
+		let typo = String::from_utf8(typo).unwrap();
+
+		assert_eq!(typo.parse::<Address>(), Err(AddressError::Checksum));
+		assert_eq!(text[1..].parse::<Address>(), Err(AddressError::Length));
+		assert_eq!(
+			text.replace(&text[..1], "0").parse::<Address>(),
+			Err(AddressError::Encoding)
+		);
+	}
+
+	#[test]
+	fn rejects_unknown_versions() {
+		let text = REWARD_INPUT_ADDRESS.to_string();
+
+		assert_eq!(
+			text.parse::<Address>(),
+			Err(AddressError::Version(REWARD_INPUT_ADDRESS.version()))
+		);
+	}
+
+	#[test]
+	fn decode_checks_version() {
+		let config = bincode::config::standard();
+		let decode = |address: Address| {
+			let bytes = bincode::encode_to_vec(address, config).unwrap();
+			bincode::decode_from_slice::<Address, _>(&bytes, config)
+				.map(|(decoded, _)| decoded)
+		};
+		let address = before_each();
+
+		assert_eq!(decode(address).unwrap(), address);
+		assert_eq!(decode(REWARD_INPUT_ADDRESS).unwrap(), REWARD_INPUT_ADDRESS);
+		assert!(decode(Address::new(0x99, [1; HASH_SIZE])).is_err());
+		assert!(decode(Address::new(0, [1; HASH_SIZE])).is_err());
+	}
+
+	#[test]
+	fn rejects_hex_public_keys() {
+		let keypair = Keypair::ed25519_from_bytes([7; 32]).unwrap();
+		let hex_key = hex::encode(keypair.public().encode_protobuf());
+
+		assert!(hex_key.parse::<Address>().is_err());
+	}
+
+	#[test]
+	fn serializes_as_string() {
+		let address = before_each();
+		let json = serde_json::to_string(&address).unwrap();
+
+		assert_eq!(json, format!("\"{address}\""));
+		assert_eq!(serde_json::from_str::<Address>(&json).unwrap(), address);
+	}
+}
@@ -0,0 +1,302 @@
+use std::{
+	collections::HashMap,
+	error::Error,
+	fs,
+	path::{Path, PathBuf},
+	time::Duration,
+};
+
+use libp2p::{Multiaddr, PeerId, multiaddr::Protocol};
+
+use crate::{
+	config::{
+		ADDRESS_BOOK_BACKOFF_BASE, ADDRESS_BOOK_BACKOFF_MAX,
+		ADDRESS_BOOK_MAX_ADDRS, ADDRESS_BOOK_MAX_FAILURES,
+	},
+	wire::{self, MessageType},
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PeerEntry {
+	pub addrs: Vec<Multiaddr>,
+	pub last_seen: i64, // milliseconds
+	pub failures: u32,
+	pub next_dial: i64, // milliseconds
+	pub connections: u32,
+	pub latency: Option<Duration>,
+}
+
+/// Known peers with their addresses and connection state. Addresses, last
+/// seen time and dial failures are written to `path` on every change so the
+/// node can redial its peers after a restart.
+#[derive(Debug, Default)]
+pub struct AddressBook {
+	entries: HashMap<PeerId, PeerEntry>,
+	path: Option<PathBuf>,
+}
+
+// peer id, addresses, last seen, failures
+type StoredEntry = (Vec<u8>, Vec<Vec<u8>>, i64, u32);
+
+impl AddressBook {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Loads peers stored at `path`. A missing file gives an empty book.
+	pub fn load(
+		path: impl AsRef<Path>,
+	) -> Result<Self, Box<dyn Error + Send + Sync>> {
+		let path = path.as_ref().to_path_buf();
+		let mut address_book =
+			Self { path: Some(path.clone()), ..Self::default() };
+		if !path.exists() {
+			return Ok(address_book);
+		}
+
+		let bytes = fs::read(&path)?;
+		let config = bincode::config::standard();
+		let (stored, _): (Vec<StoredEntry>, usize) =
+			bincode::decode_from_slice(
+				wire::open(MessageType::AddressBook, &bytes)?,
+				config,
+			)?;
+		for (peer_bytes, addrs, last_seen, failures) in stored {
+			let peer_id = PeerId::from_bytes(&peer_bytes)?;
+			let addrs = addrs
+				.into_iter()
+				.map(Multiaddr::try_from)
+				.collect::<Result<Vec<Multiaddr>, _>>()?;
+			address_book.entries.insert(
+				peer_id,
+				PeerEntry {
+					addrs,
+					last_seen,
+					failures,
+					..PeerEntry::default()
+				},
+			);
+		}
+		Ok(address_book)
+	}
+
+	pub fn save(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+		let Some(path) = &self.path else {
+			return Ok(());
+		};
+		let stored: Vec<StoredEntry> = self
+			.entries
+			.iter()
+			.map(|(peer_id, entry)| {
+				(
+					peer_id.to_bytes(),
+					entry
+						.addrs
+						.iter()
+						.map(|addr| addr.to_vec())
+						.collect(),
+					entry.last_seen,
+					entry.failures,
+				)
+			})
+			.collect();
+		let config = bincode::config::standard();
+		let payload = bincode::encode_to_vec(stored, config)?;
+		let bytes = wire::seal(MessageType::AddressBook, &payload);
+		if let Some(dir) = path.parent() {
+			fs::create_dir_all(dir)?;
+		}
+		fs::write(path, bytes)?;
+		Ok(())
+	}
+
+	pub fn entries(&self) -> &HashMap<PeerId, PeerEntry> {
+		&self.entries
+	}
+
+	pub fn get(&self, peer_id: &PeerId) -> Option<&PeerEntry> {
+		self.entries.get(peer_id)
+	}
+
+	/// Remembers a dialable address of the peer. The newest address is
+	/// tried first and only `ADDRESS_BOOK_MAX_ADDRS` are kept.
+	pub fn add_address(&mut self, peer_id: &PeerId, mut addr: Multiaddr) {
+		// the peer id is the key already
+		if matches!(addr.iter().last(), Some(Protocol::P2p(_))) {
+			addr.pop();
+		}
+		let entry = self.entries.entry(*peer_id).or_default();
+		if entry.addrs.first() == Some(&addr) {
+			return;
+		}
+		entry.addrs.retain(|known| *known != addr);
+		entry.addrs.insert(0, addr);
+		entry.addrs.truncate(ADDRESS_BOOK_MAX_ADDRS);
+		self.persist();
+	}
+
+	pub fn connection_established(&mut self, peer_id: &PeerId, now: i64) {
+		let entry = self.entries.entry(*peer_id).or_default();
+		entry.connections += 1;
+		entry.last_seen = now;
+		entry.failures = 0;
+		entry.next_dial = 0;
+		self.persist();
+	}
+
+	pub fn connection_closed(&mut self, peer_id: &PeerId) {
+		if let Some(entry) = self.entries.get_mut(peer_id) {
+			entry.connections = entry.connections.saturating_sub(1);
+			if entry.connections == 0 {
+				entry.latency = None;
+			}
+		}
+	}
+
+	pub fn set_latency(&mut self, peer_id: &PeerId, latency: Duration) {
+		if let Some(entry) = self.entries.get_mut(peer_id) {
+			entry.latency = Some(latency);
+		}
+	}
+
+	/// Pushes the next dial of the peer back exponentially. Peers that keep
+	/// failing past `ADDRESS_BOOK_MAX_FAILURES` are forgotten.
+	pub fn record_failure(&mut self, peer_id: &PeerId, now: i64) {
+		let Some(entry) = self.entries.get_mut(peer_id) else {
+			return;
+		};
+		if entry.connections > 0 {
+			return;
+		}
+		entry.failures += 1;
+		if entry.failures > ADDRESS_BOOK_MAX_FAILURES {
+			self.entries.remove(peer_id);
+		} else {
+			entry.next_dial = now.saturating_add(backoff(entry.failures));
+		}
+		self.persist();
+	}
+
+	/// Disconnected peers whose backoff ran out. Their next dial is pushed
+	/// back right away so a dial in flight is not repeated.
+	pub fn take_due(&mut self, now: i64) -> Vec<(PeerId, Vec<Multiaddr>)> {
+		let mut due: Vec<(PeerId, Vec<Multiaddr>)> = Vec::new();
+		for (peer_id, entry) in self.entries.iter_mut() {
+			if entry.connections > 0
+				|| entry.addrs.is_empty()
+				|| entry.next_dial > now
+			{
+				continue;
+			}
+			entry.next_dial = now.saturating_add(backoff(entry.failures + 1));
+			due.push((*peer_id, entry.addrs.clone()));
+		}
+		due
+	}
+
+	fn persist(&self) {
+		if let Err(err) = self.save() {
+			eprintln!("Failed to save address book: {}", err);
+		}
+	}
+}
+
+/// Delay before the next dial after `failures` failed attempts.
+pub fn backoff(failures: u32) -> i64 {
+	let exponent = failures.saturating_sub(1).min(32);
+	ADDRESS_BOOK_BACKOFF_BASE
+		.saturating_mul(1 << exponent)
+		.min(ADDRESS_BOOK_BACKOFF_MAX)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use pretty_assertions::assert_eq;
+
+	fn before_each() -> (AddressBook, PeerId, Multiaddr) {
+		let mut address_book = AddressBook::new();
+		let peer_id = PeerId::random();
+		let addr: Multiaddr = "/ip4/127.0.0.1/tcp/4001".parse().unwrap();
+		address_book.add_address(&peer_id, addr.clone());
+		(address_book, peer_id, addr)
+	}
+
+	#[test]
+	fn backoff_doubles_up_to_max() {
+		assert_eq!(backoff(1), ADDRESS_BOOK_BACKOFF_BASE);
+		assert_eq!(backoff(2), ADDRESS_BOOK_BACKOFF_BASE * 2);
+		assert_eq!(backoff(3), ADDRESS_BOOK_BACKOFF_BASE * 4);
+		assert_eq!(backoff(u32::MAX), ADDRESS_BOOK_BACKOFF_MAX);
+	}
+
+	#[test]
+	fn strips_peer_id_from_address() {
+		let (mut address_book, peer_id, addr) = before_each();
+		let with_peer_id = addr.clone().with(Protocol::P2p(peer_id));
+		address_book.add_address(&peer_id, with_peer_id);
+
+		assert_eq!(address_book.get(&peer_id).unwrap().addrs, vec![addr]);
+	}
+
+	#[test]
+	fn new_peers_are_due() {
+		let (mut address_book, peer_id, addr) = before_each();
+
+		assert_eq!(address_book.take_due(0), vec![(peer_id, vec![addr])]);
+		// dial in flight
+		assert_eq!(address_book.take_due(0), vec![]);
+	}
+
+	#[test]
+	fn failures_back_off() {
+		let (mut address_book, peer_id, _) = before_each();
+		address_book.record_failure(&peer_id, 0);
+		address_book.record_failure(&peer_id, 0);
+
+		let next_dial = backoff(2);
+		assert_eq!(address_book.take_due(next_dial - 1).len(), 0);
+		assert_eq!(address_book.take_due(next_dial).len(), 1);
+	}
+
+	#[test]
+	fn forgets_failing_peers() {
+		let (mut address_book, peer_id, _) = before_each();
+		for _ in 0..=ADDRESS_BOOK_MAX_FAILURES {
+			address_book.record_failure(&peer_id, 0);
+		}
+
+		assert_eq!(address_book.get(&peer_id), None);
+	}
+
+	#[test]
+	fn connected_peers_are_not_due() {
+		let (mut address_book, peer_id, _) = before_each();
+		address_book.record_failure(&peer_id, 0);
+		address_book.connection_established(&peer_id, 10);
+
+		let entry = address_book.get(&peer_id).unwrap();
+		assert_eq!(entry.failures, 0);
+		assert_eq!(entry.last_seen, 10);
+		assert_eq!(address_book.take_due(i64::MAX).len(), 0);
+	}
+
+	#[test]
+	fn peers_persist_across_loads() {
+		let path = std::env::temp_dir()
+			.join(format!("cryptochain-peers-{}.bin", PeerId::random()));
+		let peer_id = PeerId::random();
+		let addr: Multiaddr = "/ip4/127.0.0.1/tcp/4001".parse().unwrap();
+
+		let mut address_book = AddressBook::load(&path).unwrap();
+		address_book.add_address(&peer_id, addr.clone());
+		address_book.connection_established(&peer_id, 10);
+		let loaded = AddressBook::load(&path).unwrap();
+		fs::remove_file(&path).unwrap();
+
+		let entry = loaded.get(&peer_id).unwrap();
+		assert_eq!(entry.addrs, vec![addr]);
+		assert_eq!(entry.last_seen, 10);
+		assert_eq!(entry.connections, 0);
+	}
+}
@@ -0,0 +1,280 @@
+use std::{error::Error, fmt, str::FromStr};
+
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+
+use crate::config::AMOUNT_DECIMALS;
+
+/// Coin amount in base units, `COIN` units make one coin. Arithmetic is
+/// checked, amounts never wrap. Text and JSON carry amounts as decimal
+/// strings in coins, e.g. `"1.5"`, so clients never round through floats.
+#[derive(
+	Debug,
+	Clone,
+	Copy,
+	Default,
+	PartialEq,
+	Eq,
+	PartialOrd,
+	Ord,
+	Hash,
+	Encode,
+	Decode,
+)]
+pub struct Amount(u64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmountError {
+	Overflow,
+	Underflow,
+	Invalid,
+	TooPrecise,
+}
+
+impl fmt::Display for AmountError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Overflow => f.write_str("Amount overflow."),
+			Self::Underflow => f.write_str("Amount underflow."),
+			Self::Invalid => f.write_str("Amount is not a decimal number."),
+			Self::TooPrecise => write!(
+				f,
+				"Amount has more than {AMOUNT_DECIMALS} decimal places."
+			),
+		}
+	}
+}
+
+impl Error for AmountError {}
+
+impl Amount {
+	pub const ZERO: Self = Self(0);
+	pub const MAX: Self = Self(u64::MAX);
+	pub const COIN: Self = Self(10u64.pow(AMOUNT_DECIMALS));
+
+	pub const fn new(units: u64) -> Self {
+		Self(units)
+	}
+
+	/// Whole coins. Meant for constants, where an overflow fails the build.
+	pub const fn from_coins(coins: u64) -> Self {
+		Self(coins * Self::COIN.0)
+	}
+
+	pub const fn units(self) -> u64 {
+		self.0
+	}
+
+	pub const fn is_zero(self) -> bool {
+		self.0 == 0
+	}
+
+	pub fn checked_add(self, other: Self) -> Result<Self, AmountError> {
+		self.0
+			.checked_add(other.0)
+			.map(Self)
+			.ok_or(AmountError::Overflow)
+	}
+
+	pub fn checked_sub(self, other: Self) -> Result<Self, AmountError> {
+		self.0
+			.checked_sub(other.0)
+			.map(Self)
+			.ok_or(AmountError::Underflow)
+	}
+
+	pub fn saturating_add(self, other: Self) -> Self {
+		Self(self.0.saturating_add(other.0))
+	}
+
+	pub fn saturating_sub(self, other: Self) -> Self {
+		Self(self.0.saturating_sub(other.0))
+	}
+
+	/// Sum of `amounts`, failing instead of wrapping.
+	pub fn checked_sum(
+		amounts: impl IntoIterator<Item = Self>,
+	) -> Result<Self, AmountError> {
+		amounts
+			.into_iter()
+			.try_fold(Self::ZERO, Self::checked_add)
+	}
+}
+
+impl From<u64> for Amount {
+	fn from(units: u64) -> Self {
+		Self(units)
+	}
+}
+
+/// Parses a decimal coin amount. Digits below the smallest unit are
+/// rejected rather than rounded away.
+impl FromStr for Amount {
+	type Err = AmountError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (whole, fraction) = match s.split_once('.') {
+			Some((_, "")) => return Err(AmountError::Invalid),
+			Some(parts) => parts,
+			None => (s, ""),
+		};
+		let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+		if whole.is_empty() || !is_digits(whole) || !is_digits(fraction) {
+			return Err(AmountError::Invalid);
+		}
+		let fraction = fraction.trim_end_matches('0');
+		if fraction.len() > AMOUNT_DECIMALS as usize {
+			return Err(AmountError::TooPrecise);
+		}
+
+		// only digits are left, parsing can only fail by overflowing
+		let coins: u64 = whole.parse().map_err(|_| AmountError::Overflow)?;
+		let units: u64 =
+			format!("{fraction:0<width$}", width = AMOUNT_DECIMALS as usize)
+				.parse()
+				.map_err(|_| AmountError::Overflow)?;
+		coins
+			.checked_mul(Self::COIN.0)
+			.map(Self)
+			.ok_or(AmountError::Overflow)?
+			.checked_add(Self(units))
+	}
+}
+
+/// Decimal coin amount without trailing zeros, `"0.00000001"` for one unit.
+impl fmt::Display for Amount {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let coins = self.0 / Self::COIN.0;
+		let units = self.0 % Self::COIN.0;
+		if units == 0 {
+			return write!(f, "{coins}");
+		}
+		let fraction =
+			format!("{units:0width$}", width = AMOUNT_DECIMALS as usize);
+		write!(f, "{coins}.{}", fraction.trim_end_matches('0'))
+	}
+}
+
+impl Serialize for Amount {
+	fn serialize<S: Serializer>(
+		&self,
+		serializer: S,
+	) -> Result<S::Ok, S::Error> {
+		serializer.collect_str(self)
+	}
+}
+
+impl<'de> Deserialize<'de> for Amount {
+	fn deserialize<D: Deserializer<'de>>(
+		deserializer: D,
+	) -> Result<Self, D::Error> {
+		String::deserialize(deserializer)?
+			.parse()
+			.map_err(de::Error::custom)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use pretty_assertions::assert_eq;
+
+	#[test]
+	fn adds_and_subtracts() {
+		let amount = Amount::new(50);
+
+		assert_eq!(amount.checked_add(Amount::new(25)), Ok(Amount::new(75)));
+		assert_eq!(amount.checked_sub(Amount::new(50)), Ok(Amount::ZERO));
+	}
+
+	#[test]
+	fn rejects_overflow_and_underflow() {
+		assert_eq!(
+			Amount::MAX.checked_add(Amount::new(1)),
+			Err(AmountError::Overflow)
+		);
+		assert_eq!(
+			Amount::new(1).checked_sub(Amount::new(2)),
+			Err(AmountError::Underflow)
+		);
+		assert_eq!(
+			Amount::checked_sum([Amount::MAX, Amount::new(1)]),
+			Err(AmountError::Overflow)
+		);
+	}
+
+	#[test]
+	fn formats_as_decimal_coins() {
+		assert_eq!(Amount::ZERO.to_string(), "0");
+		assert_eq!(Amount::new(1).to_string(), "0.00000001");
+		assert_eq!(Amount::new(150_000_000).to_string(), "1.5");
+		assert_eq!(Amount::from_coins(42).to_string(), "42");
+		assert_eq!(Amount::MAX.to_string(), "184467440737.09551615");
+	}
+
+	#[test]
+	fn parses_decimal_coins() {
+		assert_eq!("0".parse(), Ok(Amount::ZERO));
+		assert_eq!("1.5".parse(), Ok(Amount::new(150_000_000)));
+		assert_eq!("0.00000001".parse(), Ok(Amount::new(1)));
+		assert_eq!("007.10".parse(), Ok(Amount::new(710_000_000)));
+		assert_eq!("184467440737.09551615".parse(), Ok(Amount::MAX));
+	}
+
+	#[test]
+	fn rejects_digits_below_smallest_unit() {
+		assert_eq!(
+			"0.000000015".parse::<Amount>(),
+			Err(AmountError::TooPrecise)
+		);
+		assert_eq!(
+			"0.000000009".parse::<Amount>(),
+			Err(AmountError::TooPrecise)
+		);
+		// zeros past the last place change nothing
+		assert_eq!("0.0000000100".parse(), Ok(Amount::new(1)));
+	}
+
+	#[test]
+	fn rejects_amounts_above_max() {
+		assert_eq!(
+			"184467440737.09551616".parse::<Amount>(),
+			Err(AmountError::Overflow)
+		);
+		assert_eq!(
+			"184467440738".parse::<Amount>(),
+			Err(AmountError::Overflow)
+		);
+		assert_eq!(
+			"99999999999999999999999".parse::<Amount>(),
+			Err(AmountError::Overflow)
+		);
+	}
+
+	#[test]
+	fn rejects_malformed_amounts() {
+		for input in ["", ".5", "1.", "-1", "+1", "1e8", "1.2.3", " 1", "0x10"]
+		{
+			assert_eq!(input.parse::<Amount>(), Err(AmountError::Invalid));
+		}
+	}
+
+	#[test]
+	fn round_trips_through_text() {
+		for units in [0, 1, 10, 99_999_999, 100_000_000, 123_456_789, u64::MAX]
+		{
+			let amount = Amount::new(units);
+
+			assert_eq!(amount.to_string().parse(), Ok(amount));
+		}
+	}
+
+	#[test]
+	fn serializes_as_decimal_string() {
+		let amount = Amount::new(250_000_000);
+
+		assert_eq!(serde_json::to_string(&amount).unwrap(), "\"2.5\"");
+		assert_eq!(serde_json::from_str::<Amount>("\"2.5\"").unwrap(), amount);
+		assert!(serde_json::from_str::<Amount>("2.5").is_err());
+	}
+}
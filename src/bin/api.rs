@@ -1,5 +0,0 @@
-// To run: cargo run --bin api
-
-fn main() {
-	println!("This is API.")
-}
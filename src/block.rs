@@ -1,14 +1,17 @@
 use bincode::{Decode, Encode};
-use std::usize;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
 
 use crate::{
 	config::{
 		GENESIS_DATA, GENESIS_DIFFICULTY, GENESIS_HASH, GENESIS_LAST_HASH,
 		GENESIS_NONCE, GENESIS_TS, MINE_RATE, MINE_RATE_DELTA,
 	},
+	transaction::Transaction,
 	utils::cryptohash,
 };
 use chrono::Utc;
+use tokio_util::sync::CancellationToken;
 
 pub trait BlockTr<T> {
 	fn adjust_difficulty(last_block: &T, ms_time: i64) -> u32;
@@ -17,10 +20,15 @@ pub trait BlockTr<T> {
 	fn is_valid_bit_hash(hash: &[u8], difficulty: u32) -> bool;
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+#[serde_as]
+#[derive(
+	Debug, PartialEq, Eq, Clone, Encode, Decode, Serialize, Deserialize,
+)]
 pub struct Block {
 	pub timestamp: i64,
+	#[serde_as(as = "serde_with::hex::Hex")]
 	pub last_hash: Vec<u8>,
+	#[serde_as(as = "serde_with::hex::Hex")]
 	pub hash: Vec<u8>,
 	pub data: Vec<String>,
 	pub nonce: u32,
@@ -38,34 +46,39 @@ impl Block {
 	) -> Self {
 		Self { timestamp, last_hash, hash, data, nonce, difficulty }
 	}
-}
 
-impl BlockTr<Block> for Block {
-	fn genesis() -> Self {
-		let data = GENESIS_DATA
+	/// Decodes every transaction entry stored in block data. Other data
+	/// entries are skipped.
+	pub fn transactions(
+		&self,
+	) -> Result<Vec<Transaction>, Box<dyn std::error::Error + Send + Sync>> {
+		self.data
 			.iter()
-			.map(|item| item.to_string())
-			.collect();
-		Self::new(
-			GENESIS_TS,
-			GENESIS_LAST_HASH.to_vec(),
-			GENESIS_HASH.to_vec(),
-			data,
-			GENESIS_NONCE,
-			GENESIS_DIFFICULTY,
-		)
+			.filter_map(|item| Transaction::from_block_data(item))
+			.collect()
 	}
 
-	fn mine_block(data: Vec<String>, last_block: &Block) -> Block {
-		let mut ms_time = Utc::now().timestamp_millis();
+	/// Same as `mine_block`, but gives up with `None` once `cancel` is
+	/// cancelled, so a stopping node does not wait for the proof of work.
+	pub fn mine_block_cancellable(
+		data: Vec<String>,
+		last_block: &Block,
+		cancel: &CancellationToken,
+	) -> Option<Block> {
+		// timestamps strictly increase along the chain
+		let min_time = last_block.timestamp.saturating_add(1);
+		let mut ms_time = Utc::now().timestamp_millis().max(min_time);
 		let last_hash = hex::encode(&last_block.hash);
 		let difficulty: u32 = Self::adjust_difficulty(last_block, ms_time);
 		let mut nonce: u32 = 0;
 		let mut new_hash: Vec<u8>;
 
 		loop {
+			if cancel.is_cancelled() {
+				return None;
+			}
 			nonce += 1;
-			ms_time = Utc::now().timestamp_millis();
+			ms_time = Utc::now().timestamp_millis().max(min_time);
 			new_hash =
 				cryptohash(&data, &last_hash, ms_time, nonce, difficulty);
 			// let sector = new_hash.get(0..difficulty).unwrap();
@@ -75,19 +88,45 @@ impl BlockTr<Block> for Block {
 				break;
 			}
 		}
-		Self::new(
+		Some(Self::new(
 			ms_time,
 			last_block.hash.clone(),
 			new_hash,
 			data,
 			nonce,
 			difficulty,
+		))
+	}
+}
+
+impl BlockTr<Block> for Block {
+	fn genesis() -> Self {
+		let data = GENESIS_DATA
+			.iter()
+			.map(|item| item.to_string())
+			.collect();
+		Self::new(
+			GENESIS_TS,
+			GENESIS_LAST_HASH.to_vec(),
+			GENESIS_HASH.to_vec(),
+			data,
+			GENESIS_NONCE,
+			GENESIS_DIFFICULTY,
 		)
 	}
 
+	fn mine_block(data: Vec<String>, last_block: &Block) -> Block {
+		Self::mine_block_cancellable(
+			data,
+			last_block,
+			&CancellationToken::new(),
+		)
+		.expect("Mining is never cancelled.")
+	}
+
 	fn adjust_difficulty(last_block: &Block, ms_time: i64) -> u32 {
-		let diff: u32 =
-			(last_block.timestamp as isize - ms_time as isize).abs() as u32;
+		let diff: u32 = (last_block.timestamp as isize - ms_time as isize)
+			.unsigned_abs() as u32;
 		let mut new_difficulty: u32;
 		if diff > MINE_RATE + MINE_RATE_DELTA {
 			// decrease difficulty
@@ -118,12 +157,12 @@ impl BlockTr<Block> for Block {
 			return false;
 		}
 
-		if bits > 0 {
-			if let Some(&byte) = hash.get(full_bytes as usize) {
-				let mask = 0xFFu8 << (8 - bits);
-				if byte & mask != 0 {
-					return false;
-				}
+		if bits > 0
+			&& let Some(&byte) = hash.get(full_bytes as usize)
+		{
+			let mask = 0xFFu8 << (8 - bits);
+			if byte & mask != 0 {
+				return false;
 			}
 		}
 
@@ -211,9 +250,23 @@ mod tests {
 		assert_eq!(is_valid, true);
 	}
 
+	#[test]
+	fn cancelled_mining_gives_up() {
+		let cancel = CancellationToken::new();
+		cancel.cancel();
+
+		let mined_block = Block::mine_block_cancellable(
+			vec![String::from("Mined Data")],
+			&Block::genesis(),
+			&cancel,
+		);
+
+		assert_eq!(mined_block, None);
+	}
+
 	#[test]
 	fn test_black_data_sorting() {
-		let mut data = vec!["bcd", "cdf", "abc"];
+		let mut data = ["bcd", "cdf", "abc"];
 		data.sort();
 		let data: Vec<String> =
 			data.iter().map(|item| item.to_string()).collect();
@@ -287,7 +340,7 @@ mod tests {
 		let (genesis_block, _) = init_mined_block();
 
 		let last_hash_hex = hex::encode(&genesis_block.hash);
-		let timestamp = Utc::now().timestamp_millis() as i64;
+		let timestamp = Utc::now().timestamp_millis();
 		let data: Vec<String> = vec![String::from("test")];
 		let nonce = 0;
 		let difficulty = 1;
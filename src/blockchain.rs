@@ -1,18 +1,33 @@
+use std::{
+	collections::{HashMap, HashSet},
+	error::Error,
+	fs,
+	path::Path,
+};
+
+use chrono::Utc;
+
+use uuid::Uuid;
+
 use crate::{
+	address::Address,
+	amount::{Amount, AmountError},
 	block::{Block, BlockTr},
-	utils::cryptohash,
+	config::{MAX_BLOCK_BYTES, MAX_CLOCK_DRIFT, block_subsidy},
+	transaction::Transaction,
+	utils::{cryptohash, write_file_atomic},
+	wire::{self, MessageType},
 };
 
-// Also nbeed to load chain from file system if it exists.
 pub trait BlockchainTr {
 	fn add_block(&mut self, data: Vec<String>);
 	fn replace_chain(&mut self, new_chain: Vec<Block>);
 	fn to_bytes(
-		chain: &Vec<Block>,
-	) -> Result<Vec<u8>, bincode::error::EncodeError>;
+		chain: &[Block],
+	) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>>;
 	fn from_bytes(
 		bytes: &[u8],
-	) -> Result<Vec<Block>, bincode::error::DecodeError>;
+	) -> Result<Vec<Block>, Box<dyn Error + Send + Sync>>;
 }
 
 #[derive(Debug, Clone)]
@@ -25,47 +40,124 @@ impl Blockchain {
 		Self { chain: vec![Block::genesis()] }
 	}
 
-	pub fn is_valid_chain(chain: &Vec<Block>) -> bool {
-		let first_block = chain.first().unwrap();
+	/// Loads the chain stored at `path`. A missing file gives a new chain
+	/// and a stored chain has to pass `is_valid_chain`.
+	pub fn load(
+		path: impl AsRef<Path>,
+	) -> Result<Self, Box<dyn Error + Send + Sync>> {
+		let path = path.as_ref();
+		if !path.exists() {
+			return Ok(Self::new());
+		}
+		let chain = <Self as BlockchainTr>::from_bytes(&fs::read(path)?)?;
+		if !Self::is_valid_chain(&chain) {
+			return Err(format!("Invalid chain in {}.", path.display()).into());
+		}
+		Ok(Self { chain })
+	}
+
+	pub fn save(
+		&self,
+		path: impl AsRef<Path>,
+	) -> Result<(), Box<dyn Error + Send + Sync>> {
+		let bytes = <Self as BlockchainTr>::to_bytes(&self.chain)?;
+		write_file_atomic(path.as_ref(), &bytes)?;
+		Ok(())
+	}
+
+	pub fn is_valid_chain(chain: &[Block]) -> bool {
+		let Some(first_block) = chain.first() else {
+			return false;
+		};
 		let genesis = Block::genesis();
 
 		if *first_block != genesis {
 			return false;
 		}
 
-		for idx in 0..chain.len() {
-			if idx == 0 {
-				continue;
-			}
-			let block = chain.get(idx).unwrap();
-			let (timestamp, last_hash, hash, data, nonce, difficulty) = (
-				block.timestamp,
-				&block.last_hash,
-				&block.hash,
-				&block.data,
-				block.nonce,
-				block.difficulty,
-			);
+		if !chain
+			.windows(2)
+			.all(|pair| Self::is_valid_next_block(&pair[0], &pair[1]))
+		{
+			return false;
+		}
+		Self::is_valid_transaction_data(chain)
+	}
 
-			let last_block = chain.get(idx - 1).unwrap();
-			let actual_last_hash = &last_block.hash;
+	/// Checks that `block` links to `last_block`, keeps the difficulty
+	/// within one step of it and carries a matching hash. Its timestamp has
+	/// to follow the last block and may not run more than `MAX_CLOCK_DRIFT`
+	/// ahead of the local clock, so miners can not move time locks.
+	pub fn is_valid_next_block(last_block: &Block, block: &Block) -> bool {
+		let (timestamp, last_hash, hash, data, nonce, difficulty) = (
+			block.timestamp,
+			&block.last_hash,
+			&block.hash,
+			&block.data,
+			block.nonce,
+			block.difficulty,
+		);
 
-			let difficulty_delta = last_block.difficulty.abs_diff(difficulty);
+		let actual_last_hash = &last_block.hash;
 
-			if actual_last_hash != last_hash || difficulty_delta > 1 {
-				return false;
-			}
+		let difficulty_delta = last_block.difficulty.abs_diff(difficulty);
 
-			let last_hash = hex::encode(last_hash);
-			let validated_hash =
-				cryptohash(data, &last_hash, timestamp, nonce, difficulty);
+		if actual_last_hash != last_hash || difficulty_delta > 1 {
+			return false;
+		}
+
+		if difficulty < 1 || !Block::is_valid_bit_hash(hash, difficulty) {
+			return false;
+		}
 
-			if *hash != validated_hash {
+		let max_timestamp = Utc::now()
+			.timestamp_millis()
+			.saturating_add(MAX_CLOCK_DRIFT);
+		if timestamp <= last_block.timestamp || timestamp > max_timestamp {
+			return false;
+		}
+
+		let last_hash = hex::encode(last_hash);
+		let validated_hash =
+			cryptohash(data, &last_hash, timestamp, nonce, difficulty);
+
+		*hash == validated_hash
+	}
+
+	pub fn is_valid_transaction_data(chain: &[Block]) -> bool {
+		let mut chain_index = ChainIndex::default();
+		for (height, block) in chain.iter().enumerate().skip(1) {
+			if !chain_index.is_valid_block_data(block, height as u64) {
 				return false;
 			}
+			chain_index.add_block(block);
 		}
 		true
 	}
+
+	/// Fees paid by the non-reward transactions of a block.
+	pub fn block_fees(
+		transactions: &[Transaction],
+	) -> Result<Amount, AmountError> {
+		Amount::checked_sum(
+			transactions
+				.iter()
+				.filter(|txn| !txn.is_reward())
+				.map(|txn| txn.fee),
+		)
+	}
+
+	/// Coins minted by block rewards so far. Fees are moved between
+	/// addresses, so only the subsidy part of each reward adds to the supply.
+	pub fn circulating_supply(chain: &[Block]) -> Amount {
+		ChainIndex::new(chain).circulating_supply()
+	}
+}
+
+impl Default for Blockchain {
+	fn default() -> Self {
+		Self::new()
+	}
 }
 
 impl BlockchainTr for Blockchain {
@@ -93,21 +185,164 @@ impl BlockchainTr for Blockchain {
 	}
 
 	fn to_bytes(
-		chain: &Vec<Block>,
-	) -> Result<Vec<u8>, bincode::error::EncodeError> {
+		chain: &[Block],
+	) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
 		let config = bincode::config::standard();
-		bincode::encode_to_vec(chain, config)
+		let payload = bincode::encode_to_vec(chain, config)?;
+		Ok(wire::seal(MessageType::Chain, &payload))
 	}
 
 	fn from_bytes(
 		bytes: &[u8],
-	) -> Result<Vec<Block>, bincode::error::DecodeError> {
+	) -> Result<Vec<Block>, Box<dyn Error + Send + Sync>> {
 		let config = bincode::config::standard();
-		let (chain, _bytes_read) = bincode::decode_from_slice(bytes, config)?;
+		let payload = wire::open(MessageType::Chain, bytes)?;
+		let (chain, _bytes_read) = bincode::decode_from_slice(payload, config)?;
 		Ok(chain)
 	}
 }
 
+/// Mined transaction ids, address balances and circulating supply of a
+/// chain, gathered in one pass so admitting transactions or the next block
+/// does not decode the chain again for each of them. Balances match
+/// `Wallet::calculate_balance`.
+#[derive(Debug, Default)]
+pub struct ChainIndex {
+	mined: HashSet<Uuid>,
+	balances: HashMap<Address, Amount>,
+	circulating_supply: Amount,
+}
+
+impl ChainIndex {
+	pub fn new(chain: &[Block]) -> Self {
+		let mut index = Self::default();
+		for block in chain {
+			index.add_block(block);
+		}
+		index
+	}
+
+	/// Adds the transactions of the block appended after the indexed chain.
+	pub fn add_block(&mut self, block: &Block) {
+		let Ok(transactions) = block.transactions() else {
+			return;
+		};
+		// a sender spends its whole balance, only the outputs from the
+		// block of its latest transaction on count
+		for transaction in &transactions {
+			self.balances
+				.insert(transaction.input.sender_address, Amount::ZERO);
+		}
+		let fees = Blockchain::block_fees(&transactions).unwrap_or(Amount::MAX);
+		if let Some(reward) = transactions.iter().find(|txn| txn.is_reward()) {
+			self.circulating_supply = self
+				.circulating_supply
+				.saturating_add(reward.amount.saturating_sub(fees));
+		}
+		for transaction in transactions {
+			for (address, amount) in &transaction.output_map {
+				let balance = self.balances.entry(*address).or_default();
+				*balance = balance.saturating_add(*amount);
+			}
+			self.mined.insert(transaction.id);
+		}
+	}
+
+	/// Checks the transactions of `block` at `height`, the block appended
+	/// after the indexed chain.
+	pub fn is_valid_block_data(&self, block: &Block, height: u64) -> bool {
+		let transactions = match block.transactions() {
+			Ok(transactions) => transactions,
+			Err(e) => {
+				eprintln!("Block contains malformed transaction: {e}");
+				return false;
+			}
+		};
+
+		let block_size: usize = transactions.iter().map(|txn| txn.size()).sum();
+		if block_size > MAX_BLOCK_BYTES {
+			eprintln!("Block exceeds size limit: {block_size} bytes.");
+			return false;
+		}
+
+		let Ok(fees) = Blockchain::block_fees(&transactions) else {
+			eprintln!("Block fees overflow.");
+			return false;
+		};
+		let subsidy = block_subsidy(height, self.circulating_supply);
+		let mut reward_count = 0;
+		let mut senders = HashSet::new();
+		let mut ids = HashSet::new();
+
+		for transaction in &transactions {
+			if self.is_mined(&transaction.id) || !ids.insert(transaction.id) {
+				eprintln!("Duplicate transaction: {}", transaction.id);
+				return false;
+			}
+
+			if transaction.is_reward() {
+				reward_count += 1;
+				if reward_count > 1 {
+					eprintln!("Block has more than one miner reward.");
+					return false;
+				}
+				let Ok(reward) = subsidy.checked_add(fees) else {
+					eprintln!("Miner reward overflows.");
+					return false;
+				};
+				if !transaction.is_valid_reward(reward) {
+					eprintln!("Miner reward amount is invalid.");
+					return false;
+				}
+				continue;
+			}
+
+			if !transaction.is_valid() {
+				return false;
+			}
+
+			if !transaction.is_lock_expired(height, block.timestamp) {
+				eprintln!(
+					"Transaction {} is still time locked.",
+					transaction.id
+				);
+				return false;
+			}
+
+			let sender = &transaction.input.sender_address;
+			if !senders.insert(*sender) {
+				eprintln!("Block spends from the same sender twice.");
+				return false;
+			}
+
+			let true_balance = self.balance(sender);
+			if transaction.input.amount != true_balance {
+				eprintln!(
+					"Transaction input {} does not match chain balance {}.",
+					transaction.input.amount, true_balance
+				);
+				return false;
+			}
+		}
+		true
+	}
+
+	pub fn circulating_supply(&self) -> Amount {
+		self.circulating_supply
+	}
+
+	pub fn is_mined(&self, id: &Uuid) -> bool {
+		self.mined.contains(id)
+	}
+
+	pub fn balance(&self, address: &Address) -> Amount {
+		self.balances
+			.get(address)
+			.copied()
+			.unwrap_or_default()
+	}
+}
+
 #[cfg(test)]
 mod test_blockchain {
 	use super::*;
@@ -116,7 +351,7 @@ mod test_blockchain {
 	#[test]
 	fn contains_chain_vec() {
 		let blockchain = Blockchain::new();
-		assert!(blockchain.chain.len() > 0);
+		assert!(!blockchain.chain.is_empty());
 	}
 
 	#[test]
@@ -132,4 +367,36 @@ mod test_blockchain {
 		blockchain.add_block(new_data.clone());
 		assert_eq!(blockchain.chain.last().unwrap().data, new_data);
 	}
+
+	fn chain_path() -> std::path::PathBuf {
+		std::env::temp_dir()
+			.join(format!("cryptochain-chain-{}.bin", uuid::Uuid::new_v4()))
+	}
+
+	#[test]
+	fn chain_persists_across_loads() {
+		let path = chain_path();
+		let mut blockchain = Blockchain::load(&path).unwrap();
+		blockchain.add_block(vec![String::from("foo")]);
+
+		blockchain.save(&path).unwrap();
+		let loaded = Blockchain::load(&path).unwrap();
+		fs::remove_file(&path).unwrap();
+
+		assert_eq!(loaded.chain, blockchain.chain);
+	}
+
+	#[test]
+	fn rejects_invalid_stored_chain() {
+		let path = chain_path();
+		let mut blockchain = Blockchain::new();
+		blockchain.add_block(vec![String::from("foo")]);
+		blockchain.chain[1].data = vec![String::from("tampered")];
+
+		blockchain.save(&path).unwrap();
+		let loaded = Blockchain::load(&path);
+		fs::remove_file(&path).unwrap();
+
+		assert!(loaded.is_err());
+	}
 }
@@ -0,0 +1,184 @@
+use chrono::Utc;
+use libp2p::gossipsub::MessageAcceptance;
+
+use crate::{
+	block::Block,
+	blockchain::{Blockchain, BlockchainTr, ChainIndex},
+	config::MAX_CLOCK_DRIFT,
+	transaction_pool::TransactionPool,
+};
+
+/// Replaces the local chain with a chain received over gossip.
+///
+/// Invalid chains are rejected. Chains that are valid but not longer than
+/// ours are ignored, they are most likely just behind. So are chains whose
+/// tip is ahead of our clock, the peer may only have a clock running fast.
+pub fn admit_chain(
+	blockchain: &mut Blockchain,
+	transaction_pool: &mut TransactionPool,
+	new_chain: Vec<Block>,
+) -> MessageAcceptance {
+	match check_chain(blockchain.chain.len(), &new_chain) {
+		MessageAcceptance::Accept => {
+			apply_chain(blockchain, transaction_pool, new_chain)
+		}
+		acceptance => acceptance,
+	}
+}
+
+/// Validates a received chain against the length of the local chain. Needs
+/// no lock, so the costly validation does not stall the rest of the node.
+pub fn check_chain(chain_len: usize, new_chain: &[Block]) -> MessageAcceptance {
+	if new_chain.len() <= chain_len {
+		return MessageAcceptance::Ignore;
+	}
+	let max_timestamp = Utc::now()
+		.timestamp_millis()
+		.saturating_add(MAX_CLOCK_DRIFT);
+	if new_chain
+		.last()
+		.is_some_and(|block| block.timestamp > max_timestamp)
+	{
+		return MessageAcceptance::Ignore;
+	}
+	if !Blockchain::is_valid_chain(new_chain) {
+		return MessageAcceptance::Reject;
+	}
+	MessageAcceptance::Accept
+}
+
+/// Replaces the local chain with a chain that passed `check_chain`. The
+/// chain is ignored if the local one grew past it in the meantime.
+pub fn apply_chain(
+	blockchain: &mut Blockchain,
+	transaction_pool: &mut TransactionPool,
+	new_chain: Vec<Block>,
+) -> MessageAcceptance {
+	if new_chain.len() <= blockchain.chain.len() {
+		return MessageAcceptance::Ignore;
+	}
+	blockchain.replace_chain(new_chain);
+	transaction_pool
+		.remove_stale_transactions(&ChainIndex::new(&blockchain.chain));
+	MessageAcceptance::Accept
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{block::BlockTr, utils::cryptohash};
+	use pretty_assertions::assert_eq;
+
+	fn before_each() -> (Blockchain, TransactionPool, Blockchain) {
+		let mut blockchain = Blockchain::new();
+		blockchain.add_block(vec![String::from("Alpha")]);
+		let mut longer_blockchain = blockchain.clone();
+		longer_blockchain.add_block(vec![String::from("Bravo")]);
+		(blockchain, TransactionPool::new(), longer_blockchain)
+	}
+
+	#[test]
+	fn accepts_longer_valid_chain() {
+		let (mut blockchain, mut transaction_pool, longer_blockchain) =
+			before_each();
+
+		let acceptance = admit_chain(
+			&mut blockchain,
+			&mut transaction_pool,
+			longer_blockchain.chain.clone(),
+		);
+
+		assert!(matches!(acceptance, MessageAcceptance::Accept));
+		assert_eq!(blockchain.chain, longer_blockchain.chain);
+	}
+
+	#[test]
+	fn rejects_invalid_chain() {
+		let (mut blockchain, mut transaction_pool, mut longer_blockchain) =
+			before_each();
+		longer_blockchain.chain[2].data = vec![String::from("Tampered")];
+
+		let acceptance = admit_chain(
+			&mut blockchain,
+			&mut transaction_pool,
+			longer_blockchain.chain,
+		);
+
+		assert!(matches!(acceptance, MessageAcceptance::Reject));
+		assert_eq!(blockchain.chain.len(), 2);
+	}
+
+	#[test]
+	fn ignores_chain_with_tip_in_future() {
+		let (mut blockchain, mut transaction_pool, mut longer_blockchain) =
+			before_each();
+		let tip = longer_blockchain.chain.pop().unwrap();
+		let last_block = longer_blockchain.chain.last().unwrap();
+		let timestamp =
+			Utc::now().timestamp_millis() + MAX_CLOCK_DRIFT + 60 * 1000;
+		let last_hash = hex::encode(&last_block.hash);
+		let (nonce, hash) = (0..)
+			.map(|nonce| {
+				let hash = cryptohash(
+					&tip.data,
+					&last_hash,
+					timestamp,
+					nonce,
+					tip.difficulty,
+				);
+				(nonce, hash)
+			})
+			.find(|(_, hash)| Block::is_valid_bit_hash(hash, tip.difficulty))
+			.unwrap();
+		longer_blockchain.chain.push(Block::new(
+			timestamp,
+			tip.last_hash,
+			hash,
+			tip.data,
+			nonce,
+			tip.difficulty,
+		));
+
+		let acceptance = admit_chain(
+			&mut blockchain,
+			&mut transaction_pool,
+			longer_blockchain.chain,
+		);
+
+		assert!(matches!(acceptance, MessageAcceptance::Ignore));
+		assert_eq!(blockchain.chain.len(), 2);
+	}
+
+	#[test]
+	fn ignores_shorter_chain() {
+		let (mut blockchain, mut transaction_pool, _) = before_each();
+
+		let acceptance = admit_chain(
+			&mut blockchain,
+			&mut transaction_pool,
+			Blockchain::new().chain,
+		);
+
+		assert!(matches!(acceptance, MessageAcceptance::Ignore));
+		assert_eq!(blockchain.chain.len(), 2);
+	}
+
+	#[test]
+	fn ignores_checked_chain_once_local_chain_caught_up() {
+		let (mut blockchain, mut transaction_pool, longer_blockchain) =
+			before_each();
+		let acceptance =
+			check_chain(blockchain.chain.len(), &longer_blockchain.chain);
+		assert!(matches!(acceptance, MessageAcceptance::Accept));
+		blockchain.add_block(vec![String::from("Charlie")]);
+
+		let acceptance = apply_chain(
+			&mut blockchain,
+			&mut transaction_pool,
+			longer_blockchain.chain,
+		);
+
+		assert!(matches!(acceptance, MessageAcceptance::Ignore));
+		assert_eq!(blockchain.chain[2].data, vec![String::from("Charlie")]);
+	}
+}
@@ -0,0 +1,252 @@
+use std::{
+	error::Error,
+	io::{self, Read, Write},
+};
+
+use serde::Serialize;
+use sha3::{Digest, Sha3_256};
+
+use crate::{
+	block::{Block, BlockTr},
+	blockchain::Blockchain,
+	config::{
+		CHAIN_ARCHIVE_MAGIC, CHAIN_ARCHIVE_VERSION, CHAIN_ID, MAX_BLOCK_BYTES,
+	},
+};
+
+// transactions are stored hex encoded in the block data, with room for
+// the block header
+const MAX_BLOCK_RECORD_BYTES: usize = 4 * MAX_BLOCK_BYTES;
+
+#[derive(Serialize)]
+struct JsonArchive<'a> {
+	chain_id: &'a str,
+	blocks: &'a [Block],
+}
+
+/// Writes `chain` as a portable file for seeding nodes and offline
+/// archives. Integers are little endian:
+///
+/// ```text
+/// magic "CCHA" | version u16 | chain id (u32 length + utf8) | block count u64
+/// | count x (u32 length + bincode block) | sha3-256 of everything before
+/// ```
+pub fn export(
+	chain: &[Block],
+	writer: impl Write,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+	let mut writer = HashingWriter { inner: writer, hasher: Sha3_256::new() };
+	writer.write_all(&CHAIN_ARCHIVE_MAGIC)?;
+	writer.write_all(&CHAIN_ARCHIVE_VERSION.to_le_bytes())?;
+	writer.write_all(&(CHAIN_ID.len() as u32).to_le_bytes())?;
+	writer.write_all(CHAIN_ID.as_bytes())?;
+	writer.write_all(&(chain.len() as u64).to_le_bytes())?;
+	let config = bincode::config::standard();
+	for block in chain {
+		let bytes = bincode::encode_to_vec(block, config)?;
+		writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+		writer.write_all(&bytes)?;
+	}
+	let checksum = writer.hasher.finalize();
+	writer.inner.write_all(&checksum)?;
+	writer.inner.flush()?;
+	Ok(())
+}
+
+/// Writes `chain` as JSON for analysis tools. JSON files cannot be
+/// imported.
+pub fn export_json(
+	chain: &[Block],
+	writer: impl Write,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+	let archive = JsonArchive { chain_id: CHAIN_ID, blocks: chain };
+	serde_json::to_writer_pretty(writer, &archive)?;
+	Ok(())
+}
+
+/// Reads an archive block by block. Every block is checked against the one
+/// before it as it arrives and the complete chain has to pass
+/// `Blockchain::is_valid_chain`.
+pub fn import(
+	reader: impl Read,
+) -> Result<Vec<Block>, Box<dyn Error + Send + Sync>> {
+	let mut reader = HashingReader { inner: reader, hasher: Sha3_256::new() };
+
+	if read_array::<4>(&mut reader)? != CHAIN_ARCHIVE_MAGIC {
+		return Err("Not a chain archive.".into());
+	}
+	let version = u16::from_le_bytes(read_array(&mut reader)?);
+	if version != CHAIN_ARCHIVE_VERSION {
+		return Err(
+			format!("Unsupported chain archive version {version}.").into()
+		);
+	}
+	let chain_id_len = u32::from_le_bytes(read_array(&mut reader)?) as usize;
+	if chain_id_len != CHAIN_ID.len() {
+		return Err("Archive belongs to another chain.".into());
+	}
+	let mut chain_id = vec![0; chain_id_len];
+	reader.read_exact(&mut chain_id)?;
+	if chain_id != CHAIN_ID.as_bytes() {
+		return Err(format!(
+			"Archive belongs to chain {}.",
+			String::from_utf8_lossy(&chain_id)
+		)
+		.into());
+	}
+	let count = u64::from_le_bytes(read_array(&mut reader)?);
+
+	let config = bincode::config::standard();
+	let mut chain: Vec<Block> = Vec::new();
+	for height in 0..count {
+		let len = u32::from_le_bytes(read_array(&mut reader)?) as usize;
+		if len > MAX_BLOCK_RECORD_BYTES {
+			return Err(format!("Block {height} is too large.").into());
+		}
+		let mut bytes = vec![0; len];
+		reader.read_exact(&mut bytes)?;
+		let (block, _): (Block, usize) =
+			bincode::decode_from_slice(&bytes, config)?;
+		let valid = match chain.last() {
+			Some(last_block) => {
+				Blockchain::is_valid_next_block(last_block, &block)
+			}
+			None => block == Block::genesis(),
+		};
+		if !valid {
+			return Err(format!("Block {height} is invalid.").into());
+		}
+		chain.push(block);
+	}
+
+	let checksum = reader.hasher.finalize();
+	if read_array::<32>(&mut reader.inner)?[..] != checksum[..] {
+		return Err("Chain archive checksum mismatch.".into());
+	}
+	if reader.inner.read(&mut [0])? != 0 {
+		return Err("Trailing data after the chain archive.".into());
+	}
+	if !Blockchain::is_valid_chain(&chain) {
+		return Err("Archived chain is invalid.".into());
+	}
+	Ok(chain)
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+	let mut bytes = [0; N];
+	reader.read_exact(&mut bytes)?;
+	Ok(bytes)
+}
+
+struct HashingWriter<W> {
+	inner: W,
+	hasher: Sha3_256,
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		let written = self.inner.write(buf)?;
+		self.hasher.update(&buf[..written]);
+		Ok(written)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		self.inner.flush()
+	}
+}
+
+struct HashingReader<R> {
+	inner: R,
+	hasher: Sha3_256,
+}
+
+impl<R: Read> Read for HashingReader<R> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		let read = self.inner.read(buf)?;
+		self.hasher.update(&buf[..read]);
+		Ok(read)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		transaction_miner::mine_transactions,
+		transaction_pool::TransactionPool, wallet::Wallet,
+	};
+	use libp2p::identity::Keypair;
+	use pretty_assertions::assert_eq;
+
+	fn before_each() -> (Vec<Block>, Vec<u8>) {
+		let wallet = Wallet::new(&Keypair::generate_ed25519());
+		let mut blockchain = Blockchain::new();
+		let mut transaction_pool = TransactionPool::new();
+		for _ in 0..2 {
+			mine_transactions(
+				&mut blockchain,
+				&mut transaction_pool,
+				&wallet.address(),
+			)
+			.unwrap();
+		}
+		let mut bytes = Vec::new();
+		export(&blockchain.chain, &mut bytes).unwrap();
+		(blockchain.chain, bytes)
+	}
+
+	#[test]
+	fn round_trips_a_chain() {
+		let (chain, bytes) = before_each();
+
+		assert_eq!(import(bytes.as_slice()).unwrap(), chain);
+	}
+
+	#[test]
+	fn starts_with_magic_and_version() {
+		let (_, bytes) = before_each();
+
+		assert_eq!(&bytes[..4], b"CCHA");
+		assert_eq!(&bytes[4..6], &CHAIN_ARCHIVE_VERSION.to_le_bytes());
+	}
+
+	#[test]
+	fn rejects_corrupted_archives() {
+		let (_, bytes) = before_each();
+
+		let mut flipped = bytes.clone();
+		let last = flipped.len() - 40;
+		flipped[last] ^= 1;
+		let mut version = bytes.clone();
+		version[4] = 9;
+
+		assert!(import(flipped.as_slice()).is_err());
+		assert!(import(version.as_slice()).is_err());
+		assert!(import(&bytes[..bytes.len() - 1]).is_err());
+		assert!(import([bytes.as_slice(), &[0]].concat().as_slice()).is_err());
+	}
+
+	#[test]
+	fn rejects_invalid_blocks_with_valid_checksum() {
+		let (mut chain, _) = before_each();
+		chain[2].data = vec![String::from("forged")];
+		let mut bytes = Vec::new();
+		export(&chain, &mut bytes).unwrap();
+
+		let err = import(bytes.as_slice()).unwrap_err();
+
+		assert_eq!(err.to_string(), "Block 2 is invalid.");
+	}
+
+	#[test]
+	fn exports_json() {
+		let (chain, _) = before_each();
+		let mut bytes = Vec::new();
+
+		export_json(&chain, &mut bytes).unwrap();
+		let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+
+		assert_eq!(json["chain_id"], CHAIN_ID);
+		assert_eq!(json["blocks"].as_array().unwrap().len(), 3);
+	}
+}
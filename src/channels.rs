@@ -1,3 +1,4 @@
+use libp2p::Multiaddr;
 use tokio::sync::mpsc;
 use uuid::Uuid;
 
@@ -17,6 +18,8 @@ impl AppMessage {
 pub enum AppEvent {
 	BroadcastMessage(AppMessage),
 	SyncBlockchain,
+	SyncBans,
+	Dial(Multiaddr),
 }
 
 pub fn create_unbounded_channel()
@@ -0,0 +1,164 @@
+use std::{
+	error::Error,
+	fs::File,
+	io::{BufReader, BufWriter},
+	path::{Path, PathBuf},
+};
+
+use clap::{Args, Subcommand};
+
+use crate::{
+	block::Block,
+	blockchain::Blockchain,
+	chain_archive,
+	cli::{Storage, Target, load_blockchain},
+	config::CHAIN_FILE,
+	http_server::chain::ChainInfo,
+};
+
+#[derive(Debug, Args)]
+pub struct ChainArgs {
+	#[command(subcommand)]
+	command: ChainCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum ChainCommand {
+	/// Prints height, tip and supply of the chain.
+	Info {
+		#[command(flatten)]
+		target: Target,
+	},
+	/// Writes the chain to an archive file.
+	Export {
+		file: PathBuf,
+		/// Writes JSON for analysis tools instead, it cannot be imported.
+		#[arg(long)]
+		json: bool,
+		#[command(flatten)]
+		target: Target,
+	},
+	/// Replaces the stored chain with a longer valid chain from an archive.
+	Import {
+		file: PathBuf,
+		#[command(flatten)]
+		storage: Storage,
+	},
+	/// Checks the stored chain, or an archive, against the consensus rules.
+	Verify {
+		file: Option<PathBuf>,
+		#[command(flatten)]
+		storage: Storage,
+	},
+}
+
+pub async fn run(args: ChainArgs) -> Result<(), Box<dyn Error + Send + Sync>> {
+	match args.command {
+		ChainCommand::Info { target } => {
+			let info = ChainInfo::new(&target.chain().await?);
+			println!("{}", serde_json::to_string_pretty(&info)?);
+		}
+		ChainCommand::Export { file, json, target } => {
+			let chain = target.chain().await?;
+			let writer = BufWriter::new(File::create(&file)?);
+			if json {
+				chain_archive::export_json(&chain, writer)?;
+			} else {
+				chain_archive::export(&chain, writer)?;
+			}
+			println!("Exported {} blocks to {}", chain.len(), file.display());
+		}
+		ChainCommand::Import { file, storage } => {
+			let chain = read_archive(&file)?;
+			let blockchain = load_blockchain(&storage)?;
+			if chain.len() <= blockchain.chain.len() {
+				return Err(format!(
+					"Imported chain has {} blocks, the stored one {}.",
+					chain.len(),
+					blockchain.chain.len()
+				)
+				.into());
+			}
+			let len = chain.len();
+			Blockchain { chain }.save(storage.data_dir()?.join(CHAIN_FILE))?;
+			println!("Imported {len} blocks");
+		}
+		ChainCommand::Verify { file, storage } => {
+			let chain = match file {
+				Some(file) => read_archive(&file)?,
+				None => load_blockchain(&storage)?.chain,
+			};
+			if !Blockchain::is_valid_chain(&chain) {
+				return Err("Chain is invalid.".into());
+			}
+			println!("Chain of {} blocks is valid", chain.len());
+		}
+	}
+	Ok(())
+}
+
+fn read_archive(
+	file: &Path,
+) -> Result<Vec<Block>, Box<dyn Error + Send + Sync>> {
+	chain_archive::import(BufReader::new(File::open(file)?))
+		.map_err(|err| format!("{}: {err}", file.display()).into())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::cli::tests::{data_dir, run_args};
+	use pretty_assertions::assert_eq;
+
+	#[tokio::test]
+	async fn import_takes_a_longer_exported_chain() {
+		let source = data_dir();
+		let destination = data_dir();
+		let file = source.join("export.bin");
+		let (src, dst) =
+			(source.to_str().unwrap(), destination.to_str().unwrap());
+
+		run_args(&["wallet", "new", "--data-dir", src])
+			.await
+			.unwrap();
+		run_args(&["mine", "--data-dir", src])
+			.await
+			.unwrap();
+		run_args(&[
+			"chain",
+			"export",
+			file.to_str().unwrap(),
+			"--data-dir",
+			src,
+		])
+		.await
+		.unwrap();
+		let imported = run_args(&[
+			"chain",
+			"import",
+			file.to_str().unwrap(),
+			"--data-dir",
+			dst,
+		])
+		.await;
+		let again = run_args(&[
+			"chain",
+			"import",
+			file.to_str().unwrap(),
+			"--data-dir",
+			dst,
+		])
+		.await;
+		let verified = run_args(&["chain", "verify", "--data-dir", dst]).await;
+		let chain = Blockchain::load(destination.join(CHAIN_FILE))
+			.unwrap()
+			.chain;
+		std::fs::remove_dir_all(&source).unwrap();
+		std::fs::remove_dir_all(&destination).unwrap();
+
+		assert!(imported.is_ok(), "{imported:?}");
+		assert!(again.is_err());
+		assert!(verified.is_ok());
+		assert_eq!(chain.len(), 2);
+	}
+}
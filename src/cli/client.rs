@@ -0,0 +1,88 @@
+use std::error::Error;
+
+use reqwest::{Client, Response};
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::http_server::error::ErrorBody;
+
+/// Client of the HTTP API of a running node.
+#[derive(Debug, Clone)]
+pub struct NodeClient {
+	base_url: String,
+	http: Client,
+}
+
+impl NodeClient {
+	/// `url` is the address of the node, `/api` is added to it.
+	pub fn new(url: &str) -> Self {
+		let url = url.trim_end_matches('/');
+		let base_url = if url.contains("://") {
+			format!("{url}/api")
+		} else {
+			format!("http://{url}/api")
+		};
+		Self { base_url, http: Client::new() }
+	}
+
+	pub async fn get<T: DeserializeOwned>(
+		&self,
+		path: &str,
+	) -> Result<T, Box<dyn Error + Send + Sync>> {
+		let response = self
+			.http
+			.get(format!("{}{path}", self.base_url))
+			.send()
+			.await?;
+		Ok(Self::check(response).await?.json().await?)
+	}
+
+	pub async fn post<B: Serialize, T: DeserializeOwned>(
+		&self,
+		path: &str,
+		body: Option<&B>,
+	) -> Result<T, Box<dyn Error + Send + Sync>> {
+		let mut request = self.http.post(format!("{}{path}", self.base_url));
+		if let Some(body) = body {
+			request = request.json(body);
+		}
+		let response = Self::check(request.send().await?).await?;
+		// some routes answer with a bare status code
+		let bytes = response.bytes().await?;
+		let body = if bytes.is_empty() { b"null".as_slice() } else { &bytes };
+		Ok(serde_json::from_slice(body)?)
+	}
+
+	// turns error statuses into errors carrying the message of the node
+	async fn check(
+		response: Response,
+	) -> Result<Response, Box<dyn Error + Send + Sync>> {
+		let status = response.status();
+		if status.is_success() {
+			return Ok(response);
+		}
+		let text = response.text().await.unwrap_or_default();
+		let message = match serde_json::from_str::<ErrorBody>(&text) {
+			Ok(body) => format!("{} ({})", body.message, body.code),
+			Err(_) => text,
+		};
+		Err(format!("Node answered {status}: {message}").into())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use pretty_assertions::assert_eq;
+
+	#[test]
+	fn adds_scheme_and_api_prefix() {
+		assert_eq!(
+			NodeClient::new("127.0.0.1:3005").base_url,
+			"http://127.0.0.1:3005/api"
+		);
+		assert_eq!(
+			NodeClient::new("http://node.example:3005/").base_url,
+			"http://node.example:3005/api"
+		);
+	}
+}
@@ -0,0 +1,302 @@
+pub mod chain;
+pub mod client;
+pub mod peers;
+pub mod wallet;
+
+use std::{error::Error, path::PathBuf};
+
+use clap::{Args, Parser, Subcommand};
+use libp2p::PeerId;
+
+use crate::{
+	block::Block,
+	blockchain::Blockchain,
+	config::{CHAIN_FILE, TRANSACTION_POOL_FILE, WALLET_KEY_FILE},
+	node,
+	node_config::{NodeArgs, NodeConfig},
+	node_key,
+	transaction_miner::mine_transactions,
+	transaction_pool::TransactionPool,
+	txn_admission::load_transaction_pool,
+	wallet::Wallet,
+};
+
+pub use client::NodeClient;
+
+type CliResult = Result<(), Box<dyn Error + Send + Sync>>;
+
+#[derive(Debug, Parser)]
+#[command(
+	name = "cryptochain",
+	version,
+	about = "Runs and operates cryptochain nodes."
+)]
+pub struct Cli {
+	#[command(subcommand)]
+	pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+	/// Runs a node or manages its network identity.
+	#[command(subcommand)]
+	Node(NodeCommand),
+	/// Manages the wallet key in the data directory and pays from it.
+	Wallet(wallet::WalletArgs),
+	/// Inspects, exports and imports the chain.
+	Chain(chain::ChainArgs),
+	/// Lists and connects peers.
+	Peers(peers::PeersArgs),
+	/// Mines the transaction pool into a new block.
+	Mine(Target),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum NodeCommand {
+	/// Runs a node until Ctrl-C or SIGTERM.
+	Run(NodeArgs),
+	/// Manages the libp2p identity stored in the data directory.
+	Key(NodeKeyArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct NodeKeyArgs {
+	#[command(flatten)]
+	storage: Storage,
+	#[command(subcommand)]
+	command: NodeKeyCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum NodeKeyCommand {
+	/// Prints the peer id of the node, generating its key if missing.
+	PeerId,
+	/// Replaces the node key, the node gets a new peer id on restart.
+	Rotate,
+}
+
+/// Data directory of a node, taken from `--data-dir` or the config file.
+#[derive(Debug, Default, Args)]
+pub struct Storage {
+	/// TOML node config, only its data directory and HTTP address are used.
+	#[arg(long, env = "CRYPTOCHAIN_CONFIG")]
+	config: Option<PathBuf>,
+	#[arg(long, env = "CRYPTOCHAIN_DATA_DIR")]
+	data_dir: Option<PathBuf>,
+}
+
+impl Storage {
+	fn node_config(&self) -> Result<NodeConfig, Box<dyn Error + Send + Sync>> {
+		NodeConfig::from_args(NodeArgs {
+			config: self.config.clone(),
+			data_dir: self.data_dir.clone(),
+			..NodeArgs::default()
+		})
+	}
+
+	fn data_dir(&self) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+		Ok(self.node_config()?.data_dir)
+	}
+}
+
+/// Where a command reads and writes chain state: a running node when
+/// `--node` is given, the data directory otherwise. Local state must not be
+/// changed while a node uses the same data directory, the node overwrites
+/// it on shutdown.
+#[derive(Debug, Default, Args)]
+pub struct Target {
+	#[command(flatten)]
+	storage: Storage,
+	/// HTTP address of a running node, e.g. 127.0.0.1:3005.
+	#[arg(long, env = "CRYPTOCHAIN_NODE")]
+	node: Option<String>,
+}
+
+impl Target {
+	fn remote(&self) -> Option<NodeClient> {
+		self.node.as_deref().map(NodeClient::new)
+	}
+
+	/// Client of `--node`, or of the HTTP address in the node config.
+	fn node_client(&self) -> Result<NodeClient, Box<dyn Error + Send + Sync>> {
+		match self.remote() {
+			Some(client) => Ok(client),
+			None => Ok(NodeClient::new(
+				&self.storage.node_config()?.http_addr.to_string(),
+			)),
+		}
+	}
+
+	async fn chain(&self) -> Result<Vec<Block>, Box<dyn Error + Send + Sync>> {
+		match self.remote() {
+			Some(client) => client.get("/blocks").await,
+			None => Ok(load_blockchain(&self.storage)?.chain),
+		}
+	}
+}
+
+fn load_blockchain(
+	storage: &Storage,
+) -> Result<Blockchain, Box<dyn Error + Send + Sync>> {
+	Blockchain::load(storage.data_dir()?.join(CHAIN_FILE))
+}
+
+fn load_pool(
+	storage: &Storage,
+	chain: &[Block],
+) -> Result<TransactionPool, Box<dyn Error + Send + Sync>> {
+	load_transaction_pool(
+		storage.data_dir()?.join(TRANSACTION_POOL_FILE),
+		chain,
+	)
+}
+
+fn load_wallet(
+	storage: &Storage,
+) -> Result<Wallet, Box<dyn Error + Send + Sync>> {
+	let path = storage.data_dir()?.join(WALLET_KEY_FILE);
+	if !path.exists() {
+		return Err(format!(
+			"No wallet at {}, create one with `cryptochain wallet new`.",
+			path.display()
+		)
+		.into());
+	}
+	Wallet::load(path)
+}
+
+pub async fn run(cli: Cli) -> CliResult {
+	match cli.command {
+		Command::Node(NodeCommand::Run(args)) => {
+			node::run(NodeConfig::from_args(args)?).await
+		}
+		Command::Node(NodeCommand::Key(args)) => {
+			let path = args.storage.node_config()?.node_key_path();
+			let keypair = match args.command {
+				NodeKeyCommand::PeerId => node_key::load_or_generate(&path)?,
+				NodeKeyCommand::Rotate => node_key::rotate(&path)?,
+			};
+			println!("{}", PeerId::from(keypair.public()));
+			Ok(())
+		}
+		Command::Wallet(args) => wallet::run(args).await,
+		Command::Chain(args) => chain::run(args).await,
+		Command::Peers(args) => peers::run(args).await,
+		Command::Mine(target) => mine(target).await,
+	}
+}
+
+async fn mine(target: Target) -> CliResult {
+	let block: Block = match target.remote() {
+		Some(client) => {
+			client
+				.post::<(), _>("/mine-transactions", None)
+				.await?
+		}
+		None => {
+			let data_dir = target.storage.data_dir()?;
+			let wallet = load_wallet(&target.storage)?;
+			let mut blockchain = load_blockchain(&target.storage)?;
+			let mut transaction_pool =
+				load_pool(&target.storage, &blockchain.chain)?;
+			let block = mine_transactions(
+				&mut blockchain,
+				&mut transaction_pool,
+				&wallet.address(),
+			)?;
+			blockchain.save(data_dir.join(CHAIN_FILE))?;
+			transaction_pool.save(data_dir.join(TRANSACTION_POOL_FILE))?;
+			block
+		}
+	};
+	println!(
+		"Mined block {} with {} transactions",
+		hex::encode(&block.hash),
+		block.transactions()?.len()
+	);
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use pretty_assertions::assert_eq;
+
+	pub(super) fn data_dir() -> PathBuf {
+		std::env::temp_dir()
+			.join(format!("cryptochain-cli-{}", PeerId::random()))
+	}
+
+	pub(super) async fn run_args(args: &[&str]) -> CliResult {
+		run(Cli::try_parse_from(
+			std::iter::once("cryptochain").chain(args.iter().copied()),
+		)?)
+		.await
+	}
+
+	#[test]
+	fn parses_node_run_args() {
+		let cli = Cli::try_parse_from([
+			"cryptochain",
+			"node",
+			"run",
+			"4000",
+			"--no-mdns",
+		])
+		.unwrap();
+
+		let Command::Node(NodeCommand::Run(args)) = cli.command else {
+			panic!("Expected node run.");
+		};
+		assert_eq!(args.port, Some(4000));
+		assert_eq!(args.no_mdns, true);
+	}
+
+	#[test]
+	fn parses_remote_target() {
+		let cli = Cli::try_parse_from([
+			"cryptochain",
+			"mine",
+			"--node",
+			"127.0.0.1:3005",
+		])
+		.unwrap();
+
+		let Command::Mine(target) = cli.command else {
+			panic!("Expected mine.");
+		};
+		assert_eq!(target.node.as_deref(), Some("127.0.0.1:3005"));
+	}
+
+	#[tokio::test]
+	async fn local_mining_needs_a_wallet() {
+		let data_dir = data_dir();
+
+		let result =
+			run_args(&["mine", "--data-dir", data_dir.to_str().unwrap()]).await;
+
+		assert!(result.is_err());
+	}
+
+	#[tokio::test]
+	async fn local_mining_rewards_the_wallet() {
+		let data_dir = data_dir();
+		let dir = data_dir.to_str().unwrap();
+
+		run_args(&["wallet", "new", "--data-dir", dir])
+			.await
+			.unwrap();
+		run_args(&["mine", "--data-dir", dir])
+			.await
+			.unwrap();
+		let wallet = Wallet::load(data_dir.join(WALLET_KEY_FILE)).unwrap();
+		let blockchain = Blockchain::load(data_dir.join(CHAIN_FILE)).unwrap();
+		std::fs::remove_dir_all(&data_dir).unwrap();
+
+		assert_eq!(blockchain.chain.len(), 2);
+		assert!(
+			!Wallet::calculate_balance(&blockchain.chain, &wallet.address())
+				.is_zero()
+		);
+	}
+}
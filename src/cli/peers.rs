@@ -0,0 +1,54 @@
+use std::error::Error;
+
+use clap::{Args, Subcommand};
+
+use crate::{
+	address_book::AddressBook,
+	cli::Target,
+	config::ADDRESS_BOOK_FILE,
+	http_server::peers::{ConnectDto, PeerResponse, peer_list},
+};
+
+#[derive(Debug, Args)]
+pub struct PeersArgs {
+	#[command(subcommand)]
+	command: PeersCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum PeersCommand {
+	/// Prints the known peers, as JSON.
+	List {
+		#[command(flatten)]
+		target: Target,
+	},
+	/// Asks a running node to dial a multiaddr.
+	Connect {
+		address: String,
+		#[command(flatten)]
+		target: Target,
+	},
+}
+
+pub async fn run(args: PeersArgs) -> Result<(), Box<dyn Error + Send + Sync>> {
+	match args.command {
+		PeersCommand::List { target } => {
+			let peers: Vec<PeerResponse> = match target.remote() {
+				Some(client) => client.get("/peers").await?,
+				None => peer_list(&AddressBook::load(
+					target.storage.data_dir()?.join(ADDRESS_BOOK_FILE),
+				)?),
+			};
+			println!("{}", serde_json::to_string_pretty(&peers)?);
+		}
+		PeersCommand::Connect { address, target } => {
+			let payload = ConnectDto { address: Some(address) };
+			target
+				.node_client()?
+				.post::<_, ()>("/peers/connect", Some(&payload))
+				.await?;
+			println!("Dialing");
+		}
+	}
+	Ok(())
+}
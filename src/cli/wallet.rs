@@ -0,0 +1,236 @@
+use std::error::Error;
+
+use clap::{Args, Subcommand};
+use libp2p::gossipsub::MessageAcceptance;
+use libp2p::identity::Keypair;
+use serde::de::IgnoredAny;
+
+use crate::{
+	address::Address,
+	amount::Amount,
+	blockchain::ChainIndex,
+	cli::{Storage, Target, load_blockchain, load_pool, load_wallet},
+	config::{TRANSACTION_POOL_FILE, WALLET_KEY_FILE},
+	http_server::{chain::BalanceResponse, transact::SignedTransactionDto},
+	traits::BinarySerializable,
+	txn_admission::admit_transaction,
+	wallet::Wallet,
+};
+
+#[derive(Debug, Args)]
+pub struct WalletArgs {
+	#[command(subcommand)]
+	command: WalletCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum WalletCommand {
+	/// Generates a wallet key in the data directory.
+	New {
+		#[command(flatten)]
+		storage: Storage,
+		/// Replaces an existing wallet, its funds are lost without a backup.
+		#[arg(long)]
+		force: bool,
+	},
+	/// Stores a key printed by `wallet export` in the data directory.
+	Import {
+		/// Hex of the protobuf encoded key.
+		key: String,
+		#[command(flatten)]
+		storage: Storage,
+		#[arg(long)]
+		force: bool,
+	},
+	/// Prints the wallet key as hex.
+	Export {
+		#[command(flatten)]
+		storage: Storage,
+	},
+	/// Prints the balance of the wallet or of an address.
+	Balance {
+		address: Option<Address>,
+		#[command(flatten)]
+		target: Target,
+	},
+	/// Signs a payment from the wallet and submits it to the pool.
+	Send {
+		/// Address of the recipient.
+		recipient: Address,
+		amount: Amount,
+		#[arg(long, default_value_t = Amount::ZERO)]
+		fee: Amount,
+		#[command(flatten)]
+		target: Target,
+	},
+}
+
+pub async fn run(args: WalletArgs) -> Result<(), Box<dyn Error + Send + Sync>> {
+	match args.command {
+		WalletCommand::New { storage, force } => {
+			let wallet = Wallet::new(&Keypair::generate_ed25519());
+			store(&storage, &wallet, force)?;
+			println!("{}", wallet.address());
+		}
+		WalletCommand::Import { key, storage, force } => {
+			let keypair = Keypair::from_protobuf_encoding(&hex::decode(key)?)?;
+			let wallet = Wallet::new(&keypair);
+			store(&storage, &wallet, force)?;
+			println!("{}", wallet.address());
+		}
+		WalletCommand::Export { storage } => {
+			println!("{}", Wallet::export_pk(&load_wallet(&storage)?.keypair));
+		}
+		WalletCommand::Balance { address, target } => {
+			let address = match address {
+				Some(address) => address,
+				None => load_wallet(&target.storage)?.address(),
+			};
+			println!("{}", balance(&target, &address).await?);
+		}
+		WalletCommand::Send { recipient, amount, fee, target } => {
+			let mut wallet = load_wallet(&target.storage)?;
+			wallet.balance = balance(&target, &wallet.address()).await?;
+			let transaction =
+				wallet.create_transaction(amount, fee, &recipient)?;
+
+			match target.remote() {
+				Some(client) => {
+					let payload = SignedTransactionDto {
+						transaction: Some(hex::encode(transaction.to_bytes()?)),
+					};
+					client
+						.post::<_, IgnoredAny>("/transactions", Some(&payload))
+						.await?;
+				}
+				None => {
+					let chain = load_blockchain(&target.storage)?.chain;
+					let mut transaction_pool =
+						load_pool(&target.storage, &chain)?;
+					let acceptance = admit_transaction(
+						&mut transaction_pool,
+						&ChainIndex::new(&chain),
+						transaction.clone(),
+					);
+					if !matches!(acceptance, MessageAcceptance::Accept) {
+						return Err(
+							"Transaction does not fit the pool, mine the pending one first."
+								.into(),
+						);
+					}
+					transaction_pool.save(
+						target
+							.storage
+							.data_dir()?
+							.join(TRANSACTION_POOL_FILE),
+					)?;
+				}
+			}
+			println!("{}", transaction.id);
+		}
+	}
+	Ok(())
+}
+
+fn store(
+	storage: &Storage,
+	wallet: &Wallet,
+	force: bool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+	let path = storage.data_dir()?.join(WALLET_KEY_FILE);
+	if path.exists() && !force {
+		return Err(format!(
+			"A wallet already exists at {}, pass --force to replace it.",
+			path.display()
+		)
+		.into());
+	}
+	wallet.save(path)
+}
+
+async fn balance(
+	target: &Target,
+	address: &Address,
+) -> Result<Amount, Box<dyn Error + Send + Sync>> {
+	match target.remote() {
+		Some(client) => {
+			let response: BalanceResponse = client
+				.get(&format!("/balances/{address}"))
+				.await?;
+			Ok(response.balance)
+		}
+		None => {
+			let chain = load_blockchain(&target.storage)?.chain;
+			Ok(Wallet::calculate_balance(&chain, address))
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		cli::tests::{data_dir, run_args},
+		wallet::Wallet,
+	};
+	use libp2p::identity::Keypair;
+
+	fn recipient() -> String {
+		Wallet::new(&Keypair::generate_ed25519())
+			.address()
+			.to_string()
+	}
+
+	#[tokio::test]
+	async fn new_keeps_an_existing_wallet() {
+		let data_dir = data_dir();
+		let dir = data_dir.to_str().unwrap();
+
+		run_args(&["wallet", "new", "--data-dir", dir])
+			.await
+			.unwrap();
+		let second = run_args(&["wallet", "new", "--data-dir", dir]).await;
+		let forced =
+			run_args(&["wallet", "new", "--force", "--data-dir", dir]).await;
+		std::fs::remove_dir_all(&data_dir).unwrap();
+
+		assert!(second.is_err());
+		assert!(forced.is_ok());
+	}
+
+	#[tokio::test]
+	async fn send_adds_to_local_pool() {
+		let data_dir = data_dir();
+		let dir = data_dir.to_str().unwrap();
+
+		run_args(&["wallet", "new", "--data-dir", dir])
+			.await
+			.unwrap();
+		run_args(&["mine", "--data-dir", dir])
+			.await
+			.unwrap();
+		let recipient = recipient();
+		let sent =
+			run_args(&["wallet", "send", &recipient, "10", "--data-dir", dir])
+				.await;
+		let pool = crate::transaction_pool::TransactionPool::load(
+			data_dir.join(crate::config::TRANSACTION_POOL_FILE),
+		)
+		.unwrap();
+		std::fs::remove_dir_all(&data_dir).unwrap();
+
+		assert!(sent.is_ok(), "{sent:?}");
+		assert_eq!(pool.transaction_map().len(), 1);
+	}
+
+	#[tokio::test]
+	async fn send_rejects_mistyped_recipient() {
+		let mut typo = recipient();
+		let last = if typo.ends_with('2') { '3' } else { '2' };
+		typo.pop();
+		typo.push(last);
+
+		let sent = run_args(&["wallet", "send", &typo, "10"]).await;
+
+		assert!(sent.is_err());
+	}
+}
@@ -17,13 +17,12 @@ impl Debouncer {
 	}
 
 	pub fn check(&mut self) -> bool {
-		if self.pending_execution {
-			if let Some(last_event) = self.last_event {
-				if last_event.elapsed() >= self.delay {
-					self.pending_execution = false;
-					return true;
-				}
-			}
+		if self.pending_execution
+			&& let Some(last_event) = self.last_event
+			&& last_event.elapsed() >= self.delay
+		{
+			self.pending_execution = false;
+			return true;
 		}
 		false
 	}
@@ -1,3 +1,7 @@
+use crate::{address::Address, amount::Amount};
+
+pub const CHAIN_ID: &str = "cryptochain-local";
+pub const SIGNING_PAYLOAD_VERSION: u8 = 5;
 pub const MINE_RATE: u32 = 1000; // milliseconds
 pub const MINE_RATE_DELTA: u32 = 50;
 
@@ -7,4 +11,126 @@ pub const GENESIS_LAST_HASH: [u8; 4] = [1, 2, 3, 4];
 pub const GENESIS_DATA: [&str; 1] = ["genesis_data"];
 pub const GENESIS_DIFFICULTY: u32 = 5;
 pub const GENESIS_NONCE: u32 = 0;
-pub const STARTING_BALANCE: u32 = 1000;
+pub const ADDRESS_VERSION: u8 = 0x1c;
+pub const MULTISIG_ADDRESS_VERSION: u8 = 0x32;
+// reserved for the input of reward transactions, never parsed from text
+pub const REWARD_ADDRESS_VERSION: u8 = 0;
+pub const REWARD_INPUT_ADDRESS: Address =
+	Address::new(REWARD_ADDRESS_VERSION, [0; 32]);
+// places below a coin, `Amount::COIN` base units make one coin
+pub const AMOUNT_DECIMALS: u32 = 8;
+pub const INITIAL_BLOCK_SUBSIDY: Amount = Amount::from_coins(50);
+pub const HALVING_INTERVAL: u64 = 1000; // blocks
+pub const MAX_SUPPLY: Amount = Amount::from_coins(100_000);
+pub const MAX_BLOCK_BYTES: usize = 64 * 1024;
+pub const FEE_ESTIMATE_BLOCKS: usize = 10;
+pub const MAX_MULTISIG_KEYS: usize = 16;
+pub const POOL_MAX_TRANSACTIONS: usize = 5000;
+pub const POOL_MAX_BYTES: usize = 8 * 1024 * 1024;
+pub const POOL_MAX_PER_SENDER: usize = 4;
+// chains and pools travel as single gossip messages, this fits 256 full
+// blocks or a full pool, each with room for encoding overhead. Larger
+// messages are refused by `NetworkHandle::publish` and logged, so a chain
+// past the limit is no longer gossiped in full
+pub const GOSSIP_MAX_TRANSMIT_SIZE: usize = 256 * MAX_BLOCK_BYTES + 1024 * 1024;
+const _: () = assert!(GOSSIP_MAX_TRANSMIT_SIZE >= POOL_MAX_BYTES + 1024 * 1024);
+pub const HTTP_ADDR: &str = "127.0.0.1:3005";
+pub const DATA_DIR: &str = "data";
+pub const NODE_KEY_FILE: &str = "node_key.bin";
+pub const WALLET_KEY_FILE: &str = "wallet_key.bin";
+pub const PEER_BANS_FILE: &str = "peer_bans.bin";
+pub const ADDRESS_BOOK_FILE: &str = "address_book.bin";
+pub const CHAIN_FILE: &str = "chain.bin";
+pub const TRANSACTION_POOL_FILE: &str = "transaction_pool.bin";
+pub const CHAIN_ARCHIVE_MAGIC: [u8; 4] = *b"CCHA";
+pub const CHAIN_ARCHIVE_VERSION: u16 = 1;
+pub const WIRE_MAGIC: [u8; 4] = *b"CCWF";
+pub const SHUTDOWN_TIMEOUT: u64 = 10; // seconds
+pub const ADDRESS_BOOK_MAX_ADDRS: usize = 8;
+pub const ADDRESS_BOOK_MAX_FAILURES: u32 = 10;
+pub const ADDRESS_BOOK_BACKOFF_BASE: i64 = 5_000; // milliseconds
+pub const ADDRESS_BOOK_BACKOFF_MAX: i64 = 10 * 60 * 1000; // milliseconds
+pub const REDIAL_CHECK_INTERVAL: u64 = 5; // seconds
+pub const PEER_BAN_SCORE: f64 = -100.0;
+pub const PEER_BAN_DURATION: i64 = 24 * 60 * 60 * 1000; // milliseconds
+pub const MISBEHAVIOR_BAN_SCORE: u32 = 100;
+// misbehavior scores drop by one point per interval, a score just below the
+// ban threshold fades within an hour
+pub const MISBEHAVIOR_DECAY_INTERVAL: i64 = 36_000; // milliseconds
+// multiaddrs ending in /p2p/<peer id>, dialed at start to join the DHT
+pub const BOOTSTRAP_PEERS: &[&str] = &[];
+pub const KAD_RANDOM_WALK_INTERVAL: u64 = 60; // seconds
+pub const PEER_SCORE_CHECK_INTERVAL: u64 = 5; // seconds
+pub const POOL_TRANSACTION_EXPIRY: i64 = 3 * 60 * 60 * 1000; // milliseconds
+// how far block and transaction timestamps may run ahead of the local clock
+pub const MAX_CLOCK_DRIFT: i64 = 2 * 60 * 1000; // milliseconds
+
+/// Coins minted by the block at `height`. The subsidy halves every
+/// `HALVING_INTERVAL` blocks and never pushes the supply above `MAX_SUPPLY`.
+pub fn block_subsidy(height: u64, circulating_supply: Amount) -> Amount {
+	let halvings = height.saturating_sub(1) / HALVING_INTERVAL;
+	let scheduled = if halvings >= u64::BITS as u64 {
+		Amount::ZERO
+	} else {
+		Amount::new(INITIAL_BLOCK_SUBSIDY.units() >> halvings)
+	};
+	scheduled.min(MAX_SUPPLY.saturating_sub(circulating_supply))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use pretty_assertions::assert_eq;
+
+	#[test]
+	fn first_block_gets_initial_subsidy() {
+		assert_eq!(block_subsidy(1, Amount::ZERO), INITIAL_BLOCK_SUBSIDY);
+	}
+
+	#[test]
+	fn subsidy_halves_every_interval() {
+		assert_eq!(
+			block_subsidy(HALVING_INTERVAL, Amount::ZERO),
+			INITIAL_BLOCK_SUBSIDY
+		);
+		assert_eq!(
+			block_subsidy(HALVING_INTERVAL + 1, Amount::ZERO),
+			Amount::from_coins(25)
+		);
+		assert_eq!(
+			block_subsidy(HALVING_INTERVAL * 2 + 1, Amount::ZERO),
+			Amount::new(1_250_000_000)
+		);
+	}
+
+	#[test]
+	fn subsidy_runs_out() {
+		assert_eq!(
+			block_subsidy(HALVING_INTERVAL * 64, Amount::ZERO),
+			Amount::ZERO
+		);
+	}
+
+	#[test]
+	fn subsidy_is_capped_by_max_supply() {
+		let almost_max = MAX_SUPPLY.checked_sub(Amount::new(10)).unwrap();
+
+		assert_eq!(block_subsidy(1, almost_max), Amount::new(10));
+		assert_eq!(block_subsidy(1, MAX_SUPPLY), Amount::ZERO);
+	}
+
+	#[test]
+	fn total_schedule_stays_under_max_supply() {
+		let mut supply = Amount::ZERO;
+		let mut height: u64 = 1;
+		loop {
+			let subsidy = block_subsidy(height, supply);
+			if subsidy.is_zero() {
+				break;
+			}
+			supply = supply.checked_add(subsidy).unwrap();
+			height += 1;
+		}
+		assert!(supply <= MAX_SUPPLY);
+	}
+}
@@ -0,0 +1,257 @@
+use std::{fmt::Write, str::FromStr};
+
+use tokio::{
+	io::{self, AsyncBufReadExt},
+	task::JoinHandle,
+};
+
+use crate::{
+	address::Address,
+	amount::Amount,
+	http_server::{AppState, mine::mine_block, transact::send_transaction},
+};
+
+const HELP: &str = "Commands:
+  peers                    known peers and their connections
+  chain tip                height and hash of the last block
+  mine                     mines the transaction pool into a block
+  send <address> <amount>  pays <amount> coins to <address>
+  help                     this help
+  exit                     stops the node";
+
+/// Operator command typed into the console of a node started with
+/// `--console`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsoleCommand {
+	Peers,
+	ChainTip,
+	Mine,
+	Send { address: Address, amount: Amount },
+	Help,
+	Exit,
+}
+
+impl FromStr for ConsoleCommand {
+	type Err = String;
+
+	fn from_str(line: &str) -> Result<Self, Self::Err> {
+		let words: Vec<&str> = line.split_whitespace().collect();
+		match words.as_slice() {
+			["peers"] => Ok(Self::Peers),
+			["chain", "tip"] => Ok(Self::ChainTip),
+			["mine"] => Ok(Self::Mine),
+			["send", address, amount] => {
+				let address = address.parse().map_err(|err| {
+					format!("Invalid address {address}: {err}")
+				})?;
+				let amount = amount
+					.parse()
+					.map_err(|_| format!("Invalid amount: {amount}"))?;
+				Ok(Self::Send { address, amount })
+			}
+			["help"] => Ok(Self::Help),
+			["exit"] | ["quit"] => Ok(Self::Exit),
+			_ => Err(format!("Unknown command: {line}. Type help.")),
+		}
+	}
+}
+
+/// Reads console commands from stdin until the node stops. The commands go
+/// through the same functions as the HTTP routes.
+pub fn start_console_task(state: AppState) -> JoinHandle<()> {
+	tokio::spawn(async move {
+		println!("{HELP}");
+		let mut stdin = io::BufReader::new(io::stdin()).lines();
+		loop {
+			let line = tokio::select! {
+				_ = state.shutdown.cancelled() => break,
+				line = stdin.next_line() => line,
+			};
+			let Ok(Some(line)) = line else {
+				// stdin closed, the node keeps running
+				break;
+			};
+			if line.trim().is_empty() {
+				continue;
+			}
+			match line.parse() {
+				Ok(command) => match execute(&state, command).await {
+					Ok(output) => println!("{output}"),
+					Err(err) => eprintln!("{err}"),
+				},
+				Err(err) => eprintln!("{err}"),
+			}
+		}
+	})
+}
+
+pub async fn execute(
+	state: &AppState,
+	command: ConsoleCommand,
+) -> Result<String, String> {
+	match command {
+		ConsoleCommand::Peers => {
+			let address_book = state.address_book.read().await;
+			let mut output = String::new();
+			for (peer_id, entry) in address_book.entries() {
+				let status = match entry.latency {
+					Some(latency) if entry.connections > 0 => {
+						format!("connected, {} ms", latency.as_millis())
+					}
+					_ if entry.connections > 0 => String::from("connected"),
+					_ => format!("{} failed dials", entry.failures),
+				};
+				let _ = writeln!(output, "{peer_id} ({status})");
+				for addr in &entry.addrs {
+					let _ = writeln!(output, "  {addr}");
+				}
+			}
+			if output.is_empty() {
+				return Ok(String::from("No known peers."));
+			}
+			output.pop();
+			Ok(output)
+		}
+		ConsoleCommand::ChainTip => {
+			let blockchain = state.blockchain.read().await;
+			let tip = blockchain
+				.chain
+				.last()
+				.ok_or("Blockchain is empty.")?;
+			Ok(format!(
+				"height {}, hash {}, difficulty {}, timestamp {}",
+				blockchain.chain.len() - 1,
+				hex::encode(&tip.hash),
+				tip.difficulty,
+				tip.timestamp
+			))
+		}
+		ConsoleCommand::Mine => {
+			let block = mine_block(state)
+				.await
+				.map_err(|err| err.to_string())?;
+			Ok(format!("Mined block {}", hex::encode(&block.hash)))
+		}
+		ConsoleCommand::Send { address, amount } => {
+			let transaction =
+				send_transaction(state, &address, amount, None, None)
+					.await
+					.map_err(|err| err.to_string())?;
+			Ok(format!("Sent transaction {}", transaction.id))
+		}
+		ConsoleCommand::Help => Ok(HELP.to_string()),
+		ConsoleCommand::Exit => {
+			state.shutdown.cancel();
+			Ok(String::from("Stopping the node..."))
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		address_book::AddressBook, blockchain::Blockchain,
+		channels::create_unbounded_channel, peer_bans::BanList,
+		transaction_pool::TransactionPool, wallet::Wallet,
+	};
+	use libp2p::identity::Keypair;
+	use pretty_assertions::assert_eq;
+	use std::sync::Arc;
+	use tokio::sync::RwLock;
+	use tokio_util::sync::CancellationToken;
+
+	fn before_each() -> AppState {
+		let (event_tx, _) = create_unbounded_channel();
+		AppState::new(
+			Arc::new(RwLock::new(Blockchain::new())),
+			Arc::new(RwLock::new(Wallet::new(&Keypair::generate_ed25519()))),
+			Arc::new(RwLock::new(TransactionPool::new())),
+			Arc::new(RwLock::new(BanList::new())),
+			Arc::new(RwLock::new(AddressBook::new())),
+			event_tx,
+			CancellationToken::new(),
+		)
+	}
+
+	#[test]
+	fn parses_commands() {
+		let address = Wallet::new(&Keypair::generate_ed25519()).address();
+
+		assert_eq!("peers".parse(), Ok(ConsoleCommand::Peers));
+		assert_eq!(" chain  tip ".parse(), Ok(ConsoleCommand::ChainTip));
+		assert_eq!(
+			format!("send {address} 0.25").parse(),
+			Ok(ConsoleCommand::Send {
+				address,
+				amount: Amount::new(25_000_000)
+			})
+		);
+	}
+
+	#[test]
+	fn rejects_invalid_commands() {
+		let address = Wallet::new(&Keypair::generate_ed25519()).address();
+
+		assert!("chain".parse::<ConsoleCommand>().is_err());
+		assert!("send 0a0b 25".parse::<ConsoleCommand>().is_err());
+		assert!(
+			format!("send {address} -1")
+				.parse::<ConsoleCommand>()
+				.is_err()
+		);
+	}
+
+	#[tokio::test]
+	async fn mine_extends_chain_tip() {
+		let state = before_each();
+
+		execute(&state, ConsoleCommand::Mine)
+			.await
+			.unwrap();
+		let tip = execute(&state, ConsoleCommand::ChainTip)
+			.await
+			.unwrap();
+
+		assert!(tip.starts_with("height 1,"), "{tip}");
+	}
+
+	#[tokio::test]
+	async fn send_pays_from_node_wallet() {
+		let state = before_each();
+		execute(&state, ConsoleCommand::Mine)
+			.await
+			.unwrap();
+
+		execute(
+			&state,
+			ConsoleCommand::Send {
+				address: Wallet::new(&Keypair::generate_ed25519()).address(),
+				amount: Amount::new(10),
+			},
+		)
+		.await
+		.unwrap();
+
+		assert_eq!(
+			state
+				.transaction_pool
+				.read()
+				.await
+				.transaction_map()
+				.len(),
+			1
+		);
+	}
+
+	#[tokio::test]
+	async fn exit_stops_the_node() {
+		let state = before_each();
+
+		execute(&state, ConsoleCommand::Exit)
+			.await
+			.unwrap();
+
+		assert!(state.shutdown.is_cancelled());
+	}
+}
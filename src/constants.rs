@@ -1,4 +1,10 @@
 // MESSAGES
 pub const BROADCAST_TXN_POOL: &str = "broadcast_txn_pool";
+pub const TXN_DATA_PREFIX: &str = "txn:";
 pub const UUID_SIZE: usize = 16;
 pub const U32_SIZE: usize = 4;
+pub const U64_SIZE: usize = 8;
+
+// PROTOCOLS
+pub const KAD_PROTOCOL: &str = "/cryptochain/kad/1.0.0";
+pub const IDENTIFY_PROTOCOL: &str = "/cryptochain/id/1.0.0";
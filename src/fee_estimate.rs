@@ -0,0 +1,103 @@
+use serde::Serialize;
+
+use crate::block::Block;
+
+/// Fee per byte percentiles paid by transactions in recent blocks.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FeeEstimate {
+	pub blocks: usize,
+	pub samples: usize,
+	pub low: f64,
+	pub medium: f64,
+	pub high: f64,
+}
+
+impl FeeEstimate {
+	pub fn from_chain(chain: &[Block], block_count: usize) -> Self {
+		let recent: Vec<&Block> = chain
+			.iter()
+			.skip(1)
+			.rev()
+			.take(block_count)
+			.collect();
+
+		let mut rates: Vec<f64> = recent
+			.iter()
+			.filter_map(|block| block.transactions().ok())
+			.flatten()
+			.filter(|txn| !txn.is_reward())
+			.map(|txn| txn.fee.units() as f64 / txn.size() as f64)
+			.collect();
+		rates.sort_by(|a, b| a.total_cmp(b));
+
+		let percentile = |pct: usize| -> f64 {
+			if rates.is_empty() {
+				return 0.0;
+			}
+			rates[(rates.len() - 1) * pct / 100]
+		};
+
+		Self {
+			blocks: recent.len(),
+			samples: rates.len(),
+			low: percentile(25),
+			medium: percentile(50),
+			high: percentile(75),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		amount::Amount,
+		blockchain::{Blockchain, BlockchainTr},
+		transaction::Transaction,
+		wallet::Wallet,
+	};
+	use libp2p::identity::Keypair;
+	use pretty_assertions::assert_eq;
+
+	#[test]
+	fn empty_chain_estimates_zero() {
+		let blockchain = Blockchain::new();
+		let estimate = FeeEstimate::from_chain(&blockchain.chain, 10);
+
+		assert_eq!(estimate.blocks, 0);
+		assert_eq!(estimate.samples, 0);
+		assert_eq!(estimate.medium, 0.0);
+	}
+
+	#[test]
+	fn ignores_rewards_and_orders_percentiles() {
+		let mut blockchain = Blockchain::new();
+		let recipient = Wallet::new(&Keypair::generate_ed25519());
+		let mut data: Vec<String> = Vec::new();
+		for fee in [1, 10, 100, 500] {
+			let mut sender = Wallet::new(&Keypair::generate_ed25519());
+			sender.balance = Amount::new(1000);
+			let txn = Transaction::with_fee(
+				&sender,
+				&recipient.address(),
+				Amount::new(5),
+				Amount::new(fee),
+			)
+			.unwrap();
+			data.push(txn.to_block_data().unwrap());
+		}
+		let reward = Transaction::reward_transaction(
+			&recipient.address(),
+			Amount::new(50),
+		);
+		data.push(reward.to_block_data().unwrap());
+		blockchain.add_block(data);
+
+		let estimate = FeeEstimate::from_chain(&blockchain.chain, 10);
+
+		assert_eq!(estimate.blocks, 1);
+		assert_eq!(estimate.samples, 4);
+		assert!(estimate.low < estimate.medium);
+		assert!(estimate.medium < estimate.high);
+	}
+}
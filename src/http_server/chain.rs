@@ -0,0 +1,74 @@
+use axum::{
+	Json, Router,
+	extract::{Path, State},
+	routing::get,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	address::Address,
+	amount::Amount,
+	block::Block,
+	blockchain::Blockchain,
+	http_server::{
+		AppState,
+		error::{ApiError, ApiResult},
+	},
+	wallet::Wallet,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainInfo {
+	pub height: u64,
+	pub hash: String,
+	pub difficulty: u32,
+	pub timestamp: i64,
+	pub circulating_supply: Amount,
+}
+
+impl ChainInfo {
+	/// Summary of `chain`, which starts with the genesis block.
+	pub fn new(chain: &[Block]) -> Self {
+		let tip = chain.last().expect("Chain has a genesis block.");
+		Self {
+			height: chain.len() as u64 - 1,
+			hash: hex::encode(&tip.hash),
+			difficulty: tip.difficulty,
+			timestamp: tip.timestamp,
+			circulating_supply: Blockchain::circulating_supply(chain),
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceResponse {
+	pub address: Address,
+	pub balance: Amount,
+}
+
+pub fn routes() -> Router<AppState> {
+	Router::new()
+		.route("/chain", get(get_chain_info))
+		.route("/blocks", get(get_blocks))
+		.route("/balances/{address}", get(get_balance))
+}
+
+async fn get_chain_info(State(state): State<AppState>) -> Json<ChainInfo> {
+	Json(ChainInfo::new(&state.blockchain.read().await.chain))
+}
+
+async fn get_blocks(State(state): State<AppState>) -> Json<Vec<Block>> {
+	Json(state.blockchain.read().await.chain.clone())
+}
+
+async fn get_balance(
+	State(state): State<AppState>,
+	Path(address): Path<String>,
+) -> ApiResult<BalanceResponse> {
+	let address: Address = address.parse().map_err(ApiError::from)?;
+	let balance = Wallet::calculate_balance(
+		&state.blockchain.read().await.chain,
+		&address,
+	);
+	Ok(Json(BalanceResponse { address, balance }))
+}
@@ -0,0 +1,206 @@
+use std::{error::Error, fmt};
+
+use axum::{
+	Json,
+	extract::{FromRequest, Request, rejection::JsonRejection},
+	http::StatusCode,
+	response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use validator::{Validate, ValidationErrors};
+
+use crate::address::AddressError;
+
+/// Error answered by the API routes. The body is an `ErrorBody`; clients
+/// match on its `code`, which stays the same when messages change.
+#[derive(Debug)]
+pub enum ApiError {
+	/// Body is not JSON or does not have the shape of the DTO.
+	InvalidJson(JsonRejection),
+	/// DTO broke one of its `validate()` rules.
+	Validation(ValidationErrors),
+	InvalidAddress(AddressError),
+	/// Any other malformed value in the request.
+	BadRequest(String),
+	/// The transaction can not be built, signed or admitted.
+	InvalidTransaction(String),
+	NotFound(String),
+	Conflict(String),
+	/// The node can not serve the request right now, e.g. it is stopping.
+	Unavailable(String),
+	Internal(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorBody {
+	pub code: String,
+	pub message: String,
+}
+
+pub type ApiResult<T> = Result<Json<T>, ApiError>;
+
+impl ApiError {
+	pub fn bad_request(err: impl fmt::Display) -> Self {
+		Self::BadRequest(err.to_string())
+	}
+
+	pub fn invalid_transaction(err: impl fmt::Display) -> Self {
+		Self::InvalidTransaction(err.to_string())
+	}
+
+	pub fn code(&self) -> &'static str {
+		match self {
+			Self::InvalidJson(_) => "invalid_json",
+			Self::Validation(_) => "validation_failed",
+			Self::InvalidAddress(_) => "invalid_address",
+			Self::BadRequest(_) => "bad_request",
+			Self::InvalidTransaction(_) => "invalid_transaction",
+			Self::NotFound(_) => "not_found",
+			Self::Conflict(_) => "conflict",
+			Self::Unavailable(_) => "unavailable",
+			Self::Internal(_) => "internal",
+		}
+	}
+
+	pub fn status(&self) -> StatusCode {
+		match self {
+			Self::InvalidJson(rejection) => rejection.status(),
+			Self::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+			Self::InvalidAddress(_)
+			| Self::BadRequest(_)
+			| Self::InvalidTransaction(_) => StatusCode::BAD_REQUEST,
+			Self::NotFound(_) => StatusCode::NOT_FOUND,
+			Self::Conflict(_) => StatusCode::CONFLICT,
+			Self::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+			Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+		}
+	}
+
+	pub fn body(&self) -> ErrorBody {
+		ErrorBody { code: self.code().to_string(), message: self.to_string() }
+	}
+}
+
+impl fmt::Display for ApiError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::InvalidJson(rejection) => {
+				write!(f, "Invalid JSON body: {}", rejection.body_text())
+			}
+			Self::Validation(err) => write!(f, "Invalid request: {err}"),
+			Self::InvalidAddress(err) => write!(f, "Invalid address: {err}"),
+			Self::InvalidTransaction(err) => {
+				write!(f, "Invalid transaction: {err}")
+			}
+			Self::BadRequest(message)
+			| Self::NotFound(message)
+			| Self::Conflict(message)
+			| Self::Unavailable(message)
+			| Self::Internal(message) => f.write_str(message),
+		}
+	}
+}
+
+impl Error for ApiError {}
+
+impl IntoResponse for ApiError {
+	fn into_response(self) -> Response {
+		(self.status(), Json(self.body())).into_response()
+	}
+}
+
+impl From<JsonRejection> for ApiError {
+	fn from(rejection: JsonRejection) -> Self {
+		Self::InvalidJson(rejection)
+	}
+}
+
+impl From<ValidationErrors> for ApiError {
+	fn from(err: ValidationErrors) -> Self {
+		Self::Validation(err)
+	}
+}
+
+impl From<AddressError> for ApiError {
+	fn from(err: AddressError) -> Self {
+		Self::InvalidAddress(err)
+	}
+}
+
+/// JSON body that passed the `validate()` rules of its DTO. Routes take
+/// their DTOs through it, so none can skip validation.
+#[derive(Debug)]
+pub struct ValidJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidJson<T>
+where
+	T: DeserializeOwned + Validate,
+	S: Send + Sync,
+{
+	type Rejection = ApiError;
+
+	async fn from_request(req: Request, state: &S) -> Result<Self, ApiError> {
+		let Json(payload) = Json::<T>::from_request(req, state).await?;
+		payload.validate()?;
+		Ok(Self(payload))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use axum::{body::Body, http::header::CONTENT_TYPE};
+	use pretty_assertions::assert_eq;
+
+	#[derive(Debug, Deserialize, Validate)]
+	struct Dto {
+		#[validate(required)]
+		value: Option<u32>,
+	}
+
+	async fn extract(body: &str) -> Result<ValidJson<Dto>, ApiError> {
+		let request = Request::builder()
+			.header(CONTENT_TYPE, "application/json")
+			.body(Body::from(body.to_string()))
+			.unwrap();
+		ValidJson::<Dto>::from_request(request, &()).await
+	}
+
+	#[tokio::test]
+	async fn accepts_valid_dto() {
+		let ValidJson(dto) = extract(r#"{"value": 1}"#).await.unwrap();
+
+		assert_eq!(dto.value, Some(1));
+	}
+
+	#[tokio::test]
+	async fn rejects_malformed_json() {
+		let err = extract("{").await.unwrap_err();
+
+		assert_eq!(err.code(), "invalid_json");
+		assert_eq!(err.status(), StatusCode::BAD_REQUEST);
+	}
+
+	#[tokio::test]
+	async fn runs_validation() {
+		let err = extract("{}").await.unwrap_err();
+
+		assert_eq!(err.code(), "validation_failed");
+		assert_eq!(err.status(), StatusCode::UNPROCESSABLE_ENTITY);
+	}
+
+	#[test]
+	fn body_has_code_and_message() {
+		let err = ApiError::from(AddressError::Checksum);
+
+		assert_eq!(
+			err.body(),
+			ErrorBody {
+				code: String::from("invalid_address"),
+				message: String::from(
+					"Invalid address: Address checksum mismatch."
+				),
+			}
+		);
+	}
+}
@@ -0,0 +1,15 @@
+use axum::{Json, Router, extract::State, routing::get};
+
+use crate::{
+	config::FEE_ESTIMATE_BLOCKS, fee_estimate::FeeEstimate,
+	http_server::AppState,
+};
+
+pub fn routes() -> Router<AppState> {
+	Router::new().route("/fees/estimate", get(estimate_fees))
+}
+
+async fn estimate_fees(State(state): State<AppState>) -> Json<FeeEstimate> {
+	let blockchain = state.blockchain.read().await;
+	Json(FeeEstimate::from_chain(&blockchain.chain, FEE_ESTIMATE_BLOCKS))
+}
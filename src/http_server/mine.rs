@@ -0,0 +1,55 @@
+use axum::{Json, Router, extract::State, routing::post};
+
+use crate::{
+	block::Block,
+	channels::AppEvent,
+	http_server::{
+		AppState,
+		error::{ApiError, ApiResult},
+	},
+	transaction_miner::{append_block, block_template},
+};
+
+pub fn routes() -> Router<AppState> {
+	Router::new().route("/mine-transactions", post(mine))
+}
+
+async fn mine(State(state): State<AppState>) -> ApiResult<Block> {
+	mine_block(&state).await.map(Json)
+}
+
+/// Mines the pool into a block rewarding the node wallet and broadcasts
+/// the chain. Shared by the route and the console.
+///
+/// The proof of work runs on a blocking thread without holding any lock,
+/// the chain and pool are only locked to take the template and to append.
+pub(crate) async fn mine_block(state: &AppState) -> Result<Block, ApiError> {
+	let miner_address = state.wallet.read().await.address();
+	let template = {
+		let blockchain = state.blockchain.read().await;
+		let transaction_pool = state.transaction_pool.read().await;
+		block_template(&blockchain, &transaction_pool, &miner_address).map_err(
+			|err| ApiError::Internal(format!("Mining failed: {}", err)),
+		)?
+	};
+
+	let shutdown = state.shutdown.clone();
+	let block = tokio::task::spawn_blocking(move || template.mine(&shutdown))
+		.await
+		.map_err(|err| ApiError::Internal(format!("Mining failed: {}", err)))?
+		.ok_or_else(|| {
+			ApiError::Unavailable(String::from("Mining cancelled."))
+		})?;
+
+	{
+		let mut blockchain = state.blockchain.write().await;
+		let mut transaction_pool = state.transaction_pool.write().await;
+		append_block(&mut blockchain, &mut transaction_pool, block.clone())
+			.map_err(|err| {
+				ApiError::Conflict(format!("Mining failed: {}", err))
+			})?;
+	}
+
+	let _ = state.event_tx.send(AppEvent::SyncBlockchain);
+	Ok(block)
+}
@@ -1,55 +1,119 @@
+pub mod chain;
+pub mod error;
+pub mod fees;
+pub mod mine;
+pub mod multisig;
+pub mod peers;
+pub mod supply;
 pub mod transact;
 pub mod transaction_pool;
 
-use std::sync::Arc;
+use std::{net::SocketAddr, sync::Arc};
 
 use axum::{Router, routing::get};
 use tokio::{
 	sync::{RwLock, mpsc},
 	task::JoinHandle,
 };
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
 
 use crate::{
-	channels::AppEvent, transaction_pool::TransactionPool, wallet::Wallet,
+	address_book::AddressBook,
+	blockchain::Blockchain,
+	channels::{AppEvent, AppMessage},
+	constants,
+	peer_bans::BanList,
+	transaction_pool::{MultisigPool, TransactionPool},
+	wallet::Wallet,
 };
 
 #[derive(Clone)]
 pub struct AppState {
+	pub blockchain: Arc<RwLock<Blockchain>>,
 	pub wallet: Arc<RwLock<Wallet>>,
 	pub transaction_pool: Arc<RwLock<TransactionPool>>,
+	// multisig transactions waiting for cosigner signatures
+	pub multisig_pool: Arc<RwLock<MultisigPool>>,
+	pub ban_list: Arc<RwLock<BanList>>,
+	pub address_book: Arc<RwLock<AddressBook>>,
 	pub event_tx: mpsc::UnboundedSender<AppEvent>,
+	// cancelled when the node stops, aborts mining in progress
+	pub shutdown: CancellationToken,
+}
+
+impl AppState {
+	pub fn new(
+		blockchain: Arc<RwLock<Blockchain>>,
+		wallet: Arc<RwLock<Wallet>>,
+		transaction_pool: Arc<RwLock<TransactionPool>>,
+		ban_list: Arc<RwLock<BanList>>,
+		address_book: Arc<RwLock<AddressBook>>,
+		event_tx: mpsc::UnboundedSender<AppEvent>,
+		shutdown: CancellationToken,
+	) -> Self {
+		Self {
+			blockchain,
+			wallet,
+			transaction_pool,
+			multisig_pool: Arc::new(RwLock::new(MultisigPool::default())),
+			ban_list,
+			address_book,
+			event_tx,
+			shutdown,
+		}
+	}
+}
+
+pub(crate) fn broadcast_txn(state: &AppState, uuid: &Uuid) {
+	let _ = state
+		.event_tx
+		.send(AppEvent::BroadcastMessage(AppMessage::new(
+			constants::BROADCAST_TXN_POOL.to_string(),
+			*uuid,
+		)));
 }
 
 pub fn start_http_server_task(
-	port: u32,
-	wallet: Arc<RwLock<Wallet>>,
-	transaction_pool: Arc<RwLock<TransactionPool>>,
-	event_tx: mpsc::UnboundedSender<AppEvent>,
+	addr: SocketAddr,
+	state: AppState,
 ) -> JoinHandle<()> {
 	tokio::spawn(async move {
-		let state = AppState { wallet, transaction_pool, event_tx };
-
-		let app: Router = Router::new()
-			.nest(
-				"/api",
-				Router::new()
-					.merge(transact::routes())
-					.merge(transaction_pool::routes())
-					.route("/", get(hello_world)),
-			)
-			.with_state(state);
-
-		let listener =
-			tokio::net::TcpListener::bind(format!("localhost:{port}"))
-				.await
-				.expect(&format!("Failed to bind to port {port}"));
+		let shutdown = state.shutdown.clone();
+		let app = router(state);
 
+		let listener = tokio::net::TcpListener::bind(addr)
+			.await
+			.unwrap_or_else(|_| panic!("Failed to bind to {addr}"));
+		println!("* HTTP server listening on: {}", addr);
+
+		// stops accepting on shutdown and lets running requests finish
 		axum::serve(listener, app)
+			.with_graceful_shutdown(shutdown.cancelled_owned())
 			.await
 			.expect("HTTP server failed.");
 	})
 }
 
+/// All API routes under `/api`, served by `start_http_server_task`.
+pub fn router(state: AppState) -> Router {
+	Router::new()
+		.nest(
+			"/api",
+			Router::new()
+				.merge(chain::routes())
+				.merge(fees::routes())
+				.merge(mine::routes())
+				.merge(multisig::routes())
+				.merge(peers::routes())
+				.merge(supply::routes())
+				.merge(transact::routes())
+				.merge(transaction_pool::routes())
+				.route("/", get(hello_world)),
+		)
+		.with_state(state)
+}
+
 async fn hello_world() -> &'static str {
 	"Hello, rust World!"
 }
@@ -0,0 +1,321 @@
+use axum::{
+	Json, Router,
+	extract::{Path, State},
+	routing::{get, post},
+};
+use chrono::Utc;
+use libp2p::gossipsub::MessageAcceptance;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+	address::Address,
+	amount::Amount,
+	blockchain::ChainIndex,
+	http_server::{
+		AppState, broadcast_txn,
+		error::{ApiError, ApiResult, ValidJson},
+	},
+	multisig::MultisigInput,
+	transaction::Transaction,
+	txn_admission::admit_transaction,
+	wallet::Wallet,
+};
+
+#[derive(Debug, Deserialize, Validate)]
+struct MultisigAddressDto {
+	#[validate(required)]
+	threshold: Option<u32>,
+	#[validate(required, length(min = 1))]
+	public_keys: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+struct MultisigTransactionDto {
+	#[validate(required)]
+	threshold: Option<u32>,
+	#[validate(required, length(min = 1))]
+	public_keys: Option<Vec<String>>,
+	#[validate(required)]
+	recipient: Option<String>,
+	#[validate(required)]
+	amount: Option<Amount>,
+	fee: Option<Amount>,
+}
+
+/// Signature made by a cosigner over `signing_payload`. When both fields are
+/// left out the node wallet signs.
+#[derive(Debug, Deserialize, Validate)]
+struct PartialSignatureDto {
+	public_key: Option<String>,
+	signature: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct MultisigAddressResponse {
+	address: Address,
+	threshold: u32,
+	public_keys: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct PendingMultisigResponse {
+	transaction: Transaction,
+	signing_payload: String,
+	valid_signers: usize,
+	threshold: u32,
+	complete: bool,
+}
+
+pub fn routes() -> Router<AppState> {
+	Router::new()
+		.route("/multisig/address", post(create_address))
+		.route("/multisig/transactions", post(create_transaction))
+		.route("/multisig/transactions/{id}", get(get_transaction))
+		.route("/multisig/transactions/{id}/signatures", post(add_signature))
+}
+
+fn decode_keys(public_keys: &[String]) -> Result<Vec<Vec<u8>>, ApiError> {
+	public_keys
+		.iter()
+		.map(|key| hex::decode(key).map_err(ApiError::bad_request))
+		.collect()
+}
+
+fn parse_id(id: &str) -> Result<Uuid, ApiError> {
+	id.parse()
+		.map_err(|_| ApiError::bad_request("Invalid transaction id."))
+}
+
+fn not_found(id: &Uuid) -> ApiError {
+	ApiError::NotFound(format!("Pending multisig transaction {id} not found."))
+}
+
+fn pending_response(transaction: Transaction) -> PendingMultisigResponse {
+	let signing_payload = transaction.signing_payload();
+	let (valid_signers, threshold) = match &transaction.input.multisig {
+		Some(multisig) => {
+			(multisig.valid_signers(&signing_payload), multisig.threshold)
+		}
+		None => (0, 0),
+	};
+	PendingMultisigResponse {
+		complete: transaction.is_valid(),
+		transaction,
+		signing_payload: hex::encode(signing_payload),
+		valid_signers,
+		threshold,
+	}
+}
+
+async fn create_address(
+	ValidJson(payload): ValidJson<MultisigAddressDto>,
+) -> ApiResult<MultisigAddressResponse> {
+	let public_keys = decode_keys(&payload.public_keys.unwrap_or_default())?;
+	let multisig =
+		MultisigInput::new(payload.threshold.unwrap_or_default(), &public_keys)
+			.map_err(ApiError::bad_request)?;
+
+	Ok(Json(MultisigAddressResponse {
+		address: multisig.address(),
+		threshold: multisig.threshold,
+		public_keys: multisig
+			.public_keys
+			.iter()
+			.map(hex::encode)
+			.collect(),
+	}))
+}
+
+async fn create_transaction(
+	State(state): State<AppState>,
+	ValidJson(payload): ValidJson<MultisigTransactionDto>,
+) -> ApiResult<PendingMultisigResponse> {
+	let public_keys = decode_keys(&payload.public_keys.unwrap_or_default())?;
+	let multisig =
+		MultisigInput::new(payload.threshold.unwrap_or_default(), &public_keys)
+			.map_err(ApiError::bad_request)?;
+	let recipient: Address = payload
+		.recipient
+		.unwrap_or_default()
+		.parse()
+		.map_err(ApiError::from)?;
+
+	let balance = Wallet::calculate_balance(
+		&state.blockchain.read().await.chain,
+		&multisig.address(),
+	);
+	let transaction = Transaction::new_multisig(
+		multisig,
+		balance,
+		&recipient,
+		payload.amount.unwrap_or_default(),
+		payload.fee.unwrap_or_default(),
+	)
+	.map_err(ApiError::invalid_transaction)?;
+
+	state
+		.multisig_pool
+		.write()
+		.await
+		.insert(transaction.clone(), Utc::now().timestamp_millis())
+		.map_err(|err| ApiError::Unavailable(err.to_string()))?;
+	Ok(Json(pending_response(transaction)))
+}
+
+async fn get_transaction(
+	State(state): State<AppState>,
+	Path(id): Path<String>,
+) -> ApiResult<PendingMultisigResponse> {
+	let id = parse_id(&id)?;
+	let now = Utc::now().timestamp_millis();
+	let transaction = state
+		.multisig_pool
+		.write()
+		.await
+		.get_mut(&id, now)
+		.cloned()
+		.ok_or_else(|| not_found(&id))?;
+	Ok(Json(pending_response(transaction)))
+}
+
+async fn add_signature(
+	State(state): State<AppState>,
+	Path(id): Path<String>,
+	ValidJson(payload): ValidJson<PartialSignatureDto>,
+) -> ApiResult<PendingMultisigResponse> {
+	let id = parse_id(&id)?;
+	let now = Utc::now().timestamp_millis();
+	let mut multisig_pool = state.multisig_pool.write().await;
+	let transaction = multisig_pool
+		.get_mut(&id, now)
+		.ok_or_else(|| not_found(&id))?;
+
+	match (payload.public_key, payload.signature) {
+		(Some(public_key), Some(signature)) => {
+			let public_key =
+				hex::decode(public_key).map_err(ApiError::bad_request)?;
+			let signature =
+				hex::decode(signature).map_err(ApiError::bad_request)?;
+			transaction
+				.add_partial_signature(&public_key, signature)
+				.map_err(ApiError::bad_request)?;
+		}
+		(None, None) => {
+			let wallet = state.wallet.read().await;
+			transaction
+				.sign_multisig(&wallet)
+				.map_err(ApiError::bad_request)?;
+		}
+		_ => {
+			return Err(ApiError::bad_request(
+				"Both public_key and signature are required.",
+			));
+		}
+	}
+
+	let transaction = transaction.clone();
+	if transaction.is_valid() {
+		// admitted like a transaction submitted by a client, it stays
+		// pending here when the node does not take it
+		let acceptance = {
+			let blockchain = state.blockchain.read().await;
+			let chain_index = ChainIndex::new(&blockchain.chain);
+			let mut transaction_pool = state.transaction_pool.write().await;
+			admit_transaction(
+				&mut transaction_pool,
+				&chain_index,
+				transaction.clone(),
+			)
+		};
+		match acceptance {
+			MessageAcceptance::Accept => {}
+			MessageAcceptance::Reject => {
+				return Err(ApiError::invalid_transaction(
+					"Rejected by the node.",
+				));
+			}
+			MessageAcceptance::Ignore => {
+				return Err(ApiError::Conflict(String::from(
+					"Transaction is known, mined or does not fit the pool.",
+				)));
+			}
+		}
+		multisig_pool.remove(&id);
+		broadcast_txn(&state, &transaction.id);
+	}
+	Ok(Json(pending_response(transaction)))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		address_book::AddressBook, blockchain::Blockchain, peer_bans::BanList,
+		transaction_pool::TransactionPool,
+	};
+	use libp2p::identity::Keypair;
+	use pretty_assertions::assert_eq;
+	use std::sync::Arc;
+	use tokio::sync::{RwLock, mpsc};
+	use tokio_util::sync::CancellationToken;
+
+	fn before_each() -> AppState {
+		let (event_tx, _) = mpsc::unbounded_channel();
+		AppState::new(
+			Arc::new(RwLock::new(Blockchain::new())),
+			Arc::new(RwLock::new(Wallet::new(&Keypair::generate_ed25519()))),
+			Arc::new(RwLock::new(TransactionPool::new())),
+			Arc::new(RwLock::new(BanList::new())),
+			Arc::new(RwLock::new(AddressBook::new())),
+			event_tx,
+			CancellationToken::new(),
+		)
+	}
+
+	#[tokio::test]
+	async fn signed_transaction_is_admitted_against_chain() {
+		let state = before_each();
+		let public_key = state.wallet.read().await.public_key.clone();
+		let multisig = MultisigInput::new(1, &[public_key]).unwrap();
+		let recipient = Wallet::new(&Keypair::generate_ed25519()).address();
+		// the multisig address holds nothing on chain
+		let transaction = Transaction::new_multisig(
+			multisig,
+			Amount::new(1000),
+			&recipient,
+			Amount::new(20),
+			Amount::ZERO,
+		)
+		.unwrap();
+		let id = transaction.id;
+		state
+			.multisig_pool
+			.write()
+			.await
+			.insert(transaction, Utc::now().timestamp_millis())
+			.unwrap();
+
+		let result = add_signature(
+			State(state.clone()),
+			Path(id.to_string()),
+			ValidJson(PartialSignatureDto {
+				public_key: None,
+				signature: None,
+			}),
+		)
+		.await;
+
+		assert_eq!(result.unwrap_err().code(), "conflict");
+		assert_eq!(
+			state
+				.transaction_pool
+				.read()
+				.await
+				.transaction_map()
+				.is_empty(),
+			true
+		);
+	}
+}
@@ -0,0 +1,166 @@
+use axum::{
+	Json, Router,
+	extract::{Path, State},
+	http::StatusCode,
+	routing::{delete, get, post},
+};
+use chrono::Utc;
+use libp2p::{Multiaddr, PeerId};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::{
+	address_book::AddressBook,
+	channels::AppEvent,
+	config::PEER_BAN_DURATION,
+	http_server::{
+		AppState,
+		error::{ApiError, ApiResult, ValidJson},
+	},
+	peer_bans::Ban,
+};
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct ConnectDto {
+	#[validate(required)]
+	pub address: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerResponse {
+	pub peer_id: String,
+	pub addresses: Vec<String>,
+	pub connections: u32,
+	pub latency_ms: Option<u128>,
+	pub last_seen: i64,
+	pub failures: u32,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+struct BanDto {
+	#[validate(required)]
+	peer_id: Option<String>,
+	// defaults to PEER_BAN_DURATION
+	#[validate(range(min = 1))]
+	duration_secs: Option<u64>,
+	#[validate(length(max = 256))]
+	reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct BanResponse {
+	peer_id: String,
+	#[serde(flatten)]
+	ban: Ban,
+}
+
+pub fn routes() -> Router<AppState> {
+	Router::new()
+		.route("/peers", get(get_peers))
+		.route("/peers/connect", post(connect))
+		.route("/peers/bans", get(get_bans).post(create_ban))
+		.route("/peers/bans/{peer_id}", delete(delete_ban))
+}
+
+async fn get_peers(State(state): State<AppState>) -> Json<Vec<PeerResponse>> {
+	Json(peer_list(&*state.address_book.read().await))
+}
+
+/// Known peers as served by `GET /peers`.
+pub fn peer_list(address_book: &AddressBook) -> Vec<PeerResponse> {
+	address_book
+		.entries()
+		.iter()
+		.map(|(peer_id, entry)| PeerResponse {
+			peer_id: peer_id.to_string(),
+			addresses: entry
+				.addrs
+				.iter()
+				.map(|addr| addr.to_string())
+				.collect(),
+			connections: entry.connections,
+			latency_ms: entry.latency.map(|latency| latency.as_millis()),
+			last_seen: entry.last_seen,
+			failures: entry.failures,
+		})
+		.collect()
+}
+
+async fn connect(
+	State(state): State<AppState>,
+	ValidJson(payload): ValidJson<ConnectDto>,
+) -> Result<StatusCode, ApiError> {
+	let addr: Multiaddr = payload
+		.address
+		.unwrap_or_default()
+		.parse()
+		.map_err(|_| ApiError::bad_request("Invalid multiaddr."))?;
+	state
+		.event_tx
+		.send(AppEvent::Dial(addr))
+		.map_err(|_| {
+			ApiError::Unavailable(String::from("P2P task is not running."))
+		})?;
+	Ok(StatusCode::ACCEPTED)
+}
+
+async fn get_bans(State(state): State<AppState>) -> Json<Vec<BanResponse>> {
+	let now = Utc::now().timestamp_millis();
+	let bans = state
+		.ban_list
+		.read()
+		.await
+		.active_bans(now)
+		.into_iter()
+		.map(|(peer_id, ban)| BanResponse { peer_id: peer_id.to_string(), ban })
+		.collect();
+	Json(bans)
+}
+
+async fn create_ban(
+	State(state): State<AppState>,
+	ValidJson(payload): ValidJson<BanDto>,
+) -> ApiResult<BanResponse> {
+	let peer_id: PeerId = payload
+		.peer_id
+		.unwrap_or_default()
+		.parse()
+		.map_err(|_| ApiError::bad_request("Invalid peer id."))?;
+	let duration = match payload.duration_secs {
+		Some(secs) => i64::try_from(secs)
+			.ok()
+			.and_then(|secs| secs.checked_mul(1000))
+			.ok_or_else(|| {
+				ApiError::bad_request("Ban duration is too long.")
+			})?,
+		None => PEER_BAN_DURATION,
+	};
+	let reason = payload
+		.reason
+		.unwrap_or_else(|| String::from("Banned by operator."));
+
+	let now = Utc::now().timestamp_millis();
+	let ban = {
+		let mut ban_list = state.ban_list.write().await;
+		ban_list.ban(&peer_id, duration, &reason, now);
+		ban_list.active_bans(now).remove(&peer_id)
+	}
+	.ok_or_else(|| ApiError::bad_request("Ban already expired."))?;
+	let _ = state.event_tx.send(AppEvent::SyncBans);
+
+	Ok(Json(BanResponse { peer_id: peer_id.to_string(), ban }))
+}
+
+async fn delete_ban(
+	State(state): State<AppState>,
+	Path(peer_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+	let peer_id: PeerId = peer_id
+		.parse()
+		.map_err(|_| ApiError::bad_request("Invalid peer id."))?;
+	if !state.ban_list.write().await.unban(&peer_id) {
+		return Err(ApiError::NotFound(String::from("Peer is not banned.")));
+	}
+	let _ = state.event_tx.send(AppEvent::SyncBans);
+	Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,34 @@
+use axum::{Json, Router, extract::State, routing::get};
+use serde::Serialize;
+
+use crate::{
+	amount::Amount,
+	blockchain::Blockchain,
+	config::{MAX_SUPPLY, block_subsidy},
+	http_server::AppState,
+};
+
+#[derive(Debug, Serialize)]
+struct SupplyDto {
+	height: u64,
+	circulating_supply: Amount,
+	max_supply: Amount,
+	next_block_subsidy: Amount,
+}
+
+pub fn routes() -> Router<AppState> {
+	Router::new().route("/supply", get(get_supply))
+}
+
+async fn get_supply(State(state): State<AppState>) -> Json<SupplyDto> {
+	let blockchain = state.blockchain.read().await;
+	let height = blockchain.chain.len() as u64 - 1;
+	let circulating_supply = Blockchain::circulating_supply(&blockchain.chain);
+
+	Json(SupplyDto {
+		height,
+		circulating_supply,
+		max_supply: MAX_SUPPLY,
+		next_block_subsidy: block_subsidy(height + 1, circulating_supply),
+	})
+}
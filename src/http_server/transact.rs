@@ -1,84 +1,163 @@
-use axum::{Json, Router, extract::State, http::StatusCode, routing::post};
-use serde::Deserialize;
-use uuid::Uuid;
+use axum::{Json, Router, extract::State, routing::post};
+use libp2p::gossipsub::MessageAcceptance;
+use serde::{Deserialize, Serialize};
 use validator::Validate;
 
 use crate::{
-	channels::{AppEvent, AppMessage},
-	constants,
-	http_server::AppState,
+	address::Address,
+	amount::Amount,
+	blockchain::ChainIndex,
+	http_server::{
+		AppState, broadcast_txn,
+		error::{ApiError, ApiResult, ValidJson},
+	},
+	lock_time::LockTime,
+	traits::BinarySerializable,
 	transaction::Transaction,
+	txn_admission::admit_transaction,
+	wallet::Wallet,
 };
 
 #[derive(Debug, Deserialize, Validate)]
 struct TransactionDto {
 	#[validate(required)]
-	amount: Option<u32>,
+	amount: Option<Amount>,
 	#[validate(required)]
 	recipient: Option<String>,
+	fee: Option<Amount>,
+	lock_time: Option<LockTime>,
+}
+
+/// Transaction signed by the client, hex of its binary encoding.
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct SignedTransactionDto {
+	#[validate(required)]
+	pub transaction: Option<String>,
 }
 
 pub fn routes() -> Router<AppState> {
-	Router::new().route("/transact", post(transact))
+	Router::new()
+		.route("/transact", post(transact))
+		.route("/transactions", post(submit_transaction))
 }
 
-fn broadcast_txn(state: &AppState, uuid: &Uuid) {
-	if let Ok(_) =
-		state
-			.event_tx
-			.send(AppEvent::BroadcastMessage(AppMessage::new(
-				constants::BROADCAST_TXN_POOL.to_string(),
-				uuid.clone(),
-			))) {};
+/// Admits a transaction signed outside the node, same as one received over
+/// gossip, and broadcasts it.
+async fn submit_transaction(
+	State(state): State<AppState>,
+	ValidJson(payload): ValidJson<SignedTransactionDto>,
+) -> ApiResult<Transaction> {
+	let bytes = hex::decode(payload.transaction.unwrap_or_default())
+		.map_err(ApiError::bad_request)?;
+	let transaction =
+		Transaction::from_bytes(&bytes).map_err(ApiError::bad_request)?;
+
+	let acceptance = {
+		let blockchain = state.blockchain.read().await;
+		let chain_index = ChainIndex::new(&blockchain.chain);
+		let mut transaction_pool = state.transaction_pool.write().await;
+		admit_transaction(
+			&mut transaction_pool,
+			&chain_index,
+			transaction.clone(),
+		)
+	};
+	match acceptance {
+		MessageAcceptance::Accept => {
+			broadcast_txn(&state, &transaction.id);
+			Ok(Json(transaction))
+		}
+		MessageAcceptance::Reject => {
+			Err(ApiError::invalid_transaction("Rejected by the node."))
+		}
+		MessageAcceptance::Ignore => Err(ApiError::Conflict(String::from(
+			"Transaction is known, mined or does not fit the pool.",
+		))),
+	}
 }
 
 async fn transact(
 	State(state): State<AppState>,
-	Json(payload): Json<TransactionDto>,
-) -> Result<Json<Transaction>, (StatusCode, String)> {
+	ValidJson(payload): ValidJson<TransactionDto>,
+) -> ApiResult<Transaction> {
+	let recipient: Address = payload
+		.recipient
+		.unwrap_or_default()
+		.parse()
+		.map_err(ApiError::from)?;
+
+	send_transaction(
+		&state,
+		&recipient,
+		payload.amount.unwrap_or_default(),
+		payload.fee,
+		payload.lock_time,
+	)
+	.await
+	.map(Json)
+}
+
+/// Pays `amount` from the node wallet to `recipient`, amending the pending
+/// transaction of the wallet if there is one, and broadcasts it. `fee` and
+/// `lock_time` only apply to a new transaction. Shared by the route and the
+/// console.
+pub(crate) async fn send_transaction(
+	state: &AppState,
+	recipient: &Address,
+	amount: Amount,
+	fee: Option<Amount>,
+	lock_time: Option<LockTime>,
+) -> Result<Transaction, ApiError> {
 	// Transaction signing has to happen on client when system becomes operational.
 	// Transactions should be only submitted via API. But will mod it later.
-	let wallet = state.wallet.read().await;
-	let amount = payload.amount.expect("Unable to load amount.");
-	let recipient_hex_address = payload
-		.recipient
-		.expect("Unable to load recipient address.");
-	let recipient_vec_address = hex::decode(recipient_hex_address.clone())
-		.expect("Unable to decode recipient hex address.");
+	let mut wallet = state.wallet.write().await;
+	wallet.balance = Wallet::calculate_balance(
+		&state.blockchain.read().await.chain,
+		&wallet.address(),
+	);
 
 	let mut transaction_pool = state.transaction_pool.write().await;
 
-	let existing_transaction =
-		transaction_pool.existing_transaction_mut(&wallet.public_key);
+	let existing_transaction = transaction_pool
+		.existing_transaction(&wallet.address())
+		.cloned();
 
 	match existing_transaction {
-		Some(transaction) => {
-			let update_result =
-				transaction.update(&wallet, &recipient_vec_address, amount);
-			match update_result {
-				Ok(()) => {
-					broadcast_txn(&state, &transaction.id);
-					Ok(Json(transaction.clone()))
-				}
-				Err(err) => Err((
-					StatusCode::BAD_REQUEST,
-					format!("Invalid transaction: {}", err),
-				)),
-			}
+		Some(_) if fee.is_some() || lock_time.is_some() => {
+			Err(ApiError::invalid_transaction(
+				"Fee and lock time can not change on the pending transaction of the wallet.",
+			))
+		}
+		Some(mut transaction) => {
+			// re-admitted so the amended transaction counts against the pool
+			// limits like a new one, the pending one stays if it is rejected
+			transaction
+				.update(&wallet, recipient, amount)
+				.map_err(ApiError::invalid_transaction)?;
+			transaction_pool
+				.set_transaction(transaction.clone())
+				.map_err(ApiError::invalid_transaction)?;
+			broadcast_txn(state, &transaction.id);
+			Ok(transaction)
 		}
 		None => {
-			let txn_result =
-				wallet.create_transaction(amount, &recipient_vec_address);
+			let fee = fee.unwrap_or_default();
+			let txn_result = wallet.create_transaction(amount, fee, recipient);
 			match txn_result {
 				Ok(transaction) => {
-					transaction_pool.set_transaction(transaction.clone());
-					broadcast_txn(&state, &transaction.id);
-					Ok(Json(transaction))
+					let transaction = match lock_time {
+						Some(lock_time) => {
+							transaction.with_lock_time(&wallet, lock_time)
+						}
+						None => transaction,
+					};
+					transaction_pool
+						.set_transaction(transaction.clone())
+						.map_err(ApiError::invalid_transaction)?;
+					broadcast_txn(state, &transaction.id);
+					Ok(transaction)
 				}
-				Err(err) => Err((
-					StatusCode::BAD_REQUEST,
-					format!("Invalid transaction: {}", err),
-				)),
+				Err(err) => Err(ApiError::invalid_transaction(err)),
 			}
 		}
 	}
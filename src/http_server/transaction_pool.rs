@@ -1,17 +1,44 @@
 use std::collections::HashMap;
 
 use axum::{Json, Router, extract::State, routing::get};
+use serde::Serialize;
 use uuid::Uuid;
 
-use crate::{http_server::AppState, transaction::Transaction};
+use crate::{
+	http_server::AppState,
+	transaction::Transaction,
+	transaction_pool::{PoolLimits, PoolMetrics},
+};
+
+#[derive(Debug, Serialize)]
+struct PoolStatsResponse {
+	transactions: usize,
+	bytes: usize,
+	limits: PoolLimits,
+	metrics: PoolMetrics,
+}
 
 pub fn routes() -> Router<AppState> {
-	Router::new().route("/transaction-pool-map", get(get_transaction_pool))
+	Router::new()
+		.route("/transaction-pool-map", get(get_transaction_pool))
+		.route("/transaction-pool-stats", get(get_transaction_pool_stats))
 }
 
 async fn get_transaction_pool(
 	State(state): State<AppState>,
 ) -> Json<HashMap<Uuid, Transaction>> {
 	let transaction_pool = state.transaction_pool.read().await;
-	Json(transaction_pool.transaction_map.to_owned())
+	Json(transaction_pool.transaction_map().to_owned())
+}
+
+async fn get_transaction_pool_stats(
+	State(state): State<AppState>,
+) -> Json<PoolStatsResponse> {
+	let transaction_pool = state.transaction_pool.read().await;
+	Json(PoolStatsResponse {
+		transactions: transaction_pool.transaction_map().len(),
+		bytes: transaction_pool.total_bytes(),
+		limits: transaction_pool.limits,
+		metrics: transaction_pool.metrics.clone(),
+	})
 }
@@ -1,16 +1,34 @@
+pub mod address;
+pub mod address_book;
+pub mod amount;
 pub mod block;
 pub mod blockchain;
+pub mod chain_admission;
+pub mod chain_archive;
 pub mod channels;
+pub mod cli;
 pub mod comms_debounce;
 pub mod config;
+pub mod console;
 pub mod constants;
+pub mod fee_estimate;
 pub mod http_server;
+pub mod lock_time;
+pub mod multisig;
+pub mod network_service;
+pub mod node;
+pub mod node_config;
+pub mod node_key;
 pub mod p2p_mdns_bc_coms;
-pub mod p2p_mdns_singleton;
 pub mod p2p_task;
+pub mod peer_bans;
+pub mod shutdown;
 pub mod traits;
 pub mod transaction;
+pub mod transaction_miner;
 pub mod transaction_pool;
+pub mod txn_admission;
 pub mod txn_input;
 pub mod utils;
 pub mod wallet;
+pub mod wire;
@@ -0,0 +1,57 @@
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+/// Earliest point at which a transaction may be included in a block.
+#[derive(
+	Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Encode, Decode,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum LockTime {
+	Height(u64),
+	Timestamp(i64), // milliseconds
+}
+
+impl LockTime {
+	pub fn is_expired(&self, block_height: u64, block_timestamp: i64) -> bool {
+		match self {
+			LockTime::Height(height) => block_height >= *height,
+			LockTime::Timestamp(timestamp) => block_timestamp >= *timestamp,
+		}
+	}
+
+	pub fn to_payload_bytes(lock_time: &Option<LockTime>) -> Vec<u8> {
+		match lock_time {
+			None => vec![0],
+			Some(LockTime::Height(height)) => {
+				let mut bytes = vec![1];
+				bytes.extend(height.to_le_bytes());
+				bytes
+			}
+			Some(LockTime::Timestamp(timestamp)) => {
+				let mut bytes = vec![2];
+				bytes.extend(timestamp.to_le_bytes());
+				bytes
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use pretty_assertions::assert_eq;
+
+	#[test]
+	fn height_lock() {
+		let lock_time = LockTime::Height(10);
+		assert_eq!(lock_time.is_expired(9, i64::MAX), false);
+		assert_eq!(lock_time.is_expired(10, 0), true);
+	}
+
+	#[test]
+	fn timestamp_lock() {
+		let lock_time = LockTime::Timestamp(1_000);
+		assert_eq!(lock_time.is_expired(u64::MAX, 999), false);
+		assert_eq!(lock_time.is_expired(0, 1_000), true);
+	}
+}
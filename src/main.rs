@@ -1,53 +1,13 @@
-use cryptochain::channels::create_unbounded_channel;
-use cryptochain::transaction_pool::TransactionPool;
-use cryptochain::wallet::Wallet;
-use libp2p::identity::Keypair;
-use std::env;
-/**
- * Testing libp2p communicator singleton class with terminal chat.
- */
-use std::sync::Arc;
-use tokio::sync::RwLock;
-
-use cryptochain::blockchain::Blockchain;
-use cryptochain::http_server::start_http_server_task;
-use cryptochain::p2p_task::start_p2p_task;
-
-// Should have initialization script, and continuous event loop.
+use clap::Parser;
+use cryptochain::cli::{self, Cli};
 
 #[tokio::main]
 async fn main() {
-	let args: Vec<String> = env::args().collect();
-
-	let mut port: u32 = 3005;
-
-	if args.len() > 1 {
-		port = args[1].parse().expect("Port must be a number.");
+	if let Err(err) = cli::run(Cli::parse()).await {
+		eprintln!("Error: {err}");
+		std::process::exit(1);
 	}
-
-	let (event_tx, event_rx) = create_unbounded_channel();
-	let blockchain = Arc::new(RwLock::new(Blockchain::new()));
-	let wallet =
-		Arc::new(RwLock::new(Wallet::new(&Keypair::generate_ed25519())));
-	let transaction_pool = Arc::new(RwLock::new(TransactionPool::new()));
-
-	let p2p_handle =
-		start_p2p_task(blockchain.clone(), transaction_pool.clone(), event_rx);
-	let http_server_handle = start_http_server_task(
-		port,
-		wallet.clone(),
-		transaction_pool.clone(),
-		event_tx,
-	);
-
-	tokio::select! {
-		_ = p2p_handle => {},
-		_ = http_server_handle => {},
-		_ = tokio::signal::ctrl_c() => {
-			println!("Shutting down...");
-			std::process::exit(0);
-		}
-	}
-
-	// std::future::pending::<()>().await;
+	// the console reads stdin on a blocking thread, which would keep the
+	// runtime from shutting down once main returns
+	std::process::exit(0);
 }
@@ -0,0 +1,274 @@
+use std::collections::HashSet;
+
+use bincode::{Decode, Encode};
+use libp2p::identity::PublicKey;
+use serde::Serialize;
+use serde_with::serde_as;
+use sha3::{Digest, Sha3_256};
+
+use crate::{
+	address::Address,
+	config::{MAX_MULTISIG_KEYS, MULTISIG_ADDRESS_VERSION},
+	utils::extend_len_prefixed,
+	wallet::Wallet,
+};
+
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Decode, Encode)]
+pub struct PartialSignature {
+	#[serde_as(as = "serde_with::hex::Hex")]
+	pub public_key: Vec<u8>,
+	#[serde_as(as = "serde_with::hex::Hex")]
+	pub signature: Vec<u8>,
+}
+
+/// M-of-N spending condition carried by transactions sent from a multisig
+/// address, together with the signatures collected so far.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Decode, Encode)]
+pub struct MultisigInput {
+	pub threshold: u32,
+	#[serde_as(as = "Vec<serde_with::hex::Hex>")]
+	pub public_keys: Vec<Vec<u8>>,
+	pub signatures: Vec<PartialSignature>,
+}
+
+impl MultisigInput {
+	pub fn new(
+		threshold: u32,
+		public_keys: &[Vec<u8>],
+	) -> Result<Self, &'static str> {
+		if public_keys.is_empty() || public_keys.len() > MAX_MULTISIG_KEYS {
+			return Err("Invalid number of multisig public keys.");
+		}
+		if threshold == 0 || threshold as usize > public_keys.len() {
+			return Err("Multisig threshold out of range.");
+		}
+		if public_keys
+			.iter()
+			.any(|key| PublicKey::try_decode_protobuf(key).is_err())
+		{
+			return Err("Invalid multisig public key.");
+		}
+
+		let mut public_keys = public_keys.to_vec();
+		public_keys.sort();
+		public_keys.dedup();
+
+		let input = Self { threshold, public_keys, signatures: Vec::new() };
+		input.check()?;
+		Ok(input)
+	}
+
+	/// Checks the invariants `new` establishes, which inputs decoded from
+	/// the network do not have to hold: threshold within the key count,
+	/// sorted distinct keys and at most one signature per key.
+	pub fn check(&self) -> Result<(), &'static str> {
+		let key_count = self.public_keys.len();
+		if key_count == 0 || key_count > MAX_MULTISIG_KEYS {
+			return Err("Invalid number of multisig public keys.");
+		}
+		if self.threshold == 0 || self.threshold as usize > key_count {
+			return Err("Multisig threshold out of range.");
+		}
+		if !self
+			.public_keys
+			.windows(2)
+			.all(|pair| pair[0] < pair[1])
+		{
+			return Err("Multisig public keys are not sorted and distinct.");
+		}
+		if self.signatures.len() > key_count {
+			return Err("Too many multisig signatures.");
+		}
+		Ok(())
+	}
+
+	pub fn address(&self) -> Address {
+		multisig_address(self.threshold, &self.public_keys)
+	}
+
+	pub fn add_signature(
+		&mut self,
+		public_key: &[u8],
+		signature: Vec<u8>,
+	) -> Result<(), &'static str> {
+		if !self
+			.public_keys
+			.iter()
+			.any(|key| key == public_key)
+		{
+			return Err("Public key is not part of the multisig address.");
+		}
+		self.signatures
+			.retain(|partial| partial.public_key != public_key);
+		self.signatures.push(PartialSignature {
+			public_key: public_key.to_vec(),
+			signature,
+		});
+		Ok(())
+	}
+
+	/// Number of distinct listed keys with a valid signature over `payload`.
+	pub fn valid_signers(&self, payload: &[u8]) -> usize {
+		let mut signers: HashSet<&Vec<u8>> = HashSet::new();
+		for partial in &self.signatures {
+			if self.public_keys.contains(&partial.public_key)
+				&& Wallet::verify_signature(
+					&partial.public_key,
+					payload,
+					&partial.signature,
+				) {
+				signers.insert(&partial.public_key);
+			}
+		}
+		signers.len()
+	}
+
+	pub fn is_satisfied(&self, payload: &[u8]) -> bool {
+		self.valid_signers(payload) >= self.threshold as usize
+	}
+}
+
+/// Address of an M-of-N multisig: the multisig version with the SHA3 hash
+/// of the threshold and the sorted public keys, so key order does not matter.
+pub fn multisig_address(threshold: u32, public_keys: &[Vec<u8>]) -> Address {
+	let mut sorted_keys = public_keys.to_vec();
+	sorted_keys.sort();
+	sorted_keys.dedup();
+
+	let mut bytes: Vec<u8> = Vec::new();
+	bytes.extend(threshold.to_le_bytes());
+	bytes.extend((sorted_keys.len() as u32).to_le_bytes());
+	for key in &sorted_keys {
+		extend_len_prefixed(&mut bytes, key);
+	}
+
+	Address::new(MULTISIG_ADDRESS_VERSION, Sha3_256::digest(&bytes).into())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use libp2p::identity::Keypair;
+	use pretty_assertions::assert_eq;
+
+	fn wallets(count: usize) -> Vec<Wallet> {
+		(0..count)
+			.map(|_| Wallet::new(&Keypair::generate_ed25519()))
+			.collect()
+	}
+
+	fn keys(wallets: &[Wallet]) -> Vec<Vec<u8>> {
+		wallets
+			.iter()
+			.map(|wallet| wallet.public_key.clone())
+			.collect()
+	}
+
+	#[test]
+	fn address_ignores_key_order() {
+		let keys = keys(&wallets(3));
+		let mut reversed = keys.clone();
+		reversed.reverse();
+
+		assert_eq!(multisig_address(2, &keys), multisig_address(2, &reversed));
+		assert_ne!(multisig_address(2, &keys), multisig_address(3, &keys));
+	}
+
+	#[test]
+	fn rejects_threshold_out_of_range() {
+		let keys = keys(&wallets(2));
+
+		assert_eq!(MultisigInput::new(0, &keys).is_err(), true);
+		assert_eq!(MultisigInput::new(3, &keys).is_err(), true);
+	}
+
+	#[test]
+	fn rejects_duplicate_keys_below_threshold() {
+		let wallets = wallets(1);
+		let keys = vec![wallets[0].public_key.clone(); 2];
+
+		assert_eq!(MultisigInput::new(2, &keys).is_err(), true);
+	}
+
+	#[test]
+	fn check_rejects_malformed_decoded_inputs() {
+		let wallets = wallets(3);
+		let input = MultisigInput::new(2, &keys(&wallets)).unwrap();
+
+		let mut zero_threshold = input.clone();
+		zero_threshold.threshold = 0;
+		let mut unsorted = input.clone();
+		unsorted.public_keys.reverse();
+		let mut duplicated = input.clone();
+		duplicated.public_keys[1] = duplicated.public_keys[0].clone();
+		let mut too_many_keys = input.clone();
+		too_many_keys.public_keys = (0..=MAX_MULTISIG_KEYS as u32)
+			.map(|idx| idx.to_be_bytes().to_vec())
+			.collect();
+		let mut too_many_signatures = input.clone();
+		too_many_signatures.signatures = vec![
+			PartialSignature {
+				public_key: wallets[0].public_key.clone(),
+				signature: Vec::new(),
+			};
+			4
+		];
+
+		assert_eq!(input.check(), Ok(()));
+		assert!(zero_threshold.check().is_err());
+		assert!(unsorted.check().is_err());
+		assert!(duplicated.check().is_err());
+		assert!(too_many_keys.check().is_err());
+		assert!(too_many_signatures.check().is_err());
+	}
+
+	#[test]
+	fn counts_distinct_valid_signers() {
+		let wallets = wallets(3);
+		let mut input = MultisigInput::new(2, &keys(&wallets)).unwrap();
+		let payload = b"payload";
+
+		input
+			.add_signature(
+				&wallets[0].public_key,
+				wallets[0].sign(payload).unwrap(),
+			)
+			.unwrap();
+		// signing twice with the same key replaces the earlier signature
+		input
+			.add_signature(
+				&wallets[0].public_key,
+				wallets[0].sign(payload).unwrap(),
+			)
+			.unwrap();
+		assert_eq!(input.valid_signers(payload), 1);
+		assert_eq!(input.is_satisfied(payload), false);
+
+		input
+			.add_signature(
+				&wallets[1].public_key,
+				wallets[2].sign(payload).unwrap(),
+			)
+			.unwrap();
+		assert_eq!(input.valid_signers(payload), 1);
+
+		input
+			.add_signature(
+				&wallets[2].public_key,
+				wallets[2].sign(payload).unwrap(),
+			)
+			.unwrap();
+		assert_eq!(input.is_satisfied(payload), true);
+	}
+
+	#[test]
+	fn rejects_foreign_signer() {
+		let wallets = wallets(3);
+		let mut input = MultisigInput::new(1, &keys(&wallets[..2])).unwrap();
+
+		let res = input.add_signature(&wallets[2].public_key, vec![1, 2, 3]);
+		assert_eq!(res.is_err(), true);
+	}
+}
@@ -0,0 +1,731 @@
+use std::{
+	collections::{HashMap, HashSet},
+	error::Error,
+	fmt,
+	time::Duration,
+};
+
+use futures::StreamExt;
+use libp2p::{
+	Multiaddr, PeerId, StreamProtocol, Swarm,
+	gossipsub::{self, IdentTopic, MessageAcceptance, MessageId, PublishError},
+	identify, kad, mdns, ping,
+	swarm::{DialError, SwarmEvent, dial_opts::DialOpts},
+};
+use strum::IntoEnumIterator;
+use tokio::sync::{broadcast, mpsc, oneshot};
+
+use crate::{
+	config::{GOSSIP_MAX_TRANSMIT_SIZE, PEER_BAN_SCORE},
+	constants::KAD_PROTOCOL,
+	p2p_mdns_bc_coms::{
+		P2PBehaviour, P2PBehaviourEvent, P2PConfig, TopicEnum, build_swarm,
+	},
+};
+
+const COMMAND_CHANNEL_SIZE: usize = 256;
+const EVENT_CHANNEL_SIZE: usize = 1024;
+
+#[derive(Debug)]
+pub enum NetworkCommand {
+	Publish {
+		topic: TopicEnum,
+		data: Vec<u8>,
+		reply: oneshot::Sender<Result<MessageId, PublishError>>,
+	},
+	Dial(DialOpts),
+	DialKnownPeers(Vec<(PeerId, Vec<Multiaddr>)>),
+	ConnectedPeers(oneshot::Sender<Vec<PeerId>>),
+	ListenAddrs(oneshot::Sender<Vec<Multiaddr>>),
+	ReportValidation {
+		message_id: MessageId,
+		propagation_source: PeerId,
+		acceptance: MessageAcceptance,
+	},
+	LowScorePeers(oneshot::Sender<Vec<PeerId>>),
+	SyncBans(HashSet<PeerId>),
+	RandomWalk,
+	Shutdown(oneshot::Sender<()>),
+}
+
+/// Events of the swarm the node reacts to. Gossip messages have to be
+/// answered with `NetworkHandle::report_validation` before they are
+/// forwarded.
+#[derive(Debug, Clone)]
+pub enum NetworkEvent {
+	Message {
+		propagation_source: PeerId,
+		message_id: MessageId,
+		message: gossipsub::Message,
+	},
+	NewListenAddr(Multiaddr),
+	ConnectionEstablished {
+		peer_id: PeerId,
+		// set when we dialed, inbound connections come from ephemeral ports
+		dialed_addr: Option<Multiaddr>,
+	},
+	ConnectionClosed(PeerId),
+	DialFailed(PeerId),
+	Identified {
+		peer_id: PeerId,
+		listen_addrs: Vec<Multiaddr>,
+	},
+	Latency {
+		peer_id: PeerId,
+		latency: Duration,
+	},
+}
+
+#[derive(Debug)]
+pub enum NetworkError {
+	Publish(PublishError),
+	// message of this many bytes exceeds `GOSSIP_MAX_TRANSMIT_SIZE`
+	TooLarge(usize),
+	// the service task has exited
+	Stopped,
+}
+
+impl fmt::Display for NetworkError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			NetworkError::Publish(err) => write!(f, "{err}"),
+			NetworkError::TooLarge(size) => write!(
+				f,
+				"Message of {size} bytes exceeds the gossip limit of {GOSSIP_MAX_TRANSMIT_SIZE} bytes."
+			),
+			NetworkError::Stopped => write!(f, "Network service stopped."),
+		}
+	}
+}
+
+impl Error for NetworkError {}
+
+/// Cheap to clone handle that controls a running `NetworkService`.
+#[derive(Debug, Clone)]
+pub struct NetworkHandle {
+	peer_id: PeerId,
+	command_tx: mpsc::Sender<NetworkCommand>,
+	event_tx: broadcast::Sender<NetworkEvent>,
+}
+
+impl NetworkHandle {
+	pub fn peer_id(&self) -> PeerId {
+		self.peer_id
+	}
+
+	/// Receives the events emitted after this call.
+	pub fn subscribe(&self) -> broadcast::Receiver<NetworkEvent> {
+		self.event_tx.subscribe()
+	}
+
+	/// Publishes `data` on `topic`. Messages over `GOSSIP_MAX_TRANSMIT_SIZE`
+	/// are refused here, peers would drop them anyway.
+	pub async fn publish(
+		&self,
+		topic: TopicEnum,
+		data: Vec<u8>,
+	) -> Result<MessageId, NetworkError> {
+		if data.len() > GOSSIP_MAX_TRANSMIT_SIZE {
+			return Err(NetworkError::TooLarge(data.len()));
+		}
+		let (reply, response) = oneshot::channel();
+		self.send(NetworkCommand::Publish { topic, data, reply })
+			.await;
+		match response.await {
+			Ok(result) => result.map_err(NetworkError::Publish),
+			Err(_) => Err(NetworkError::Stopped),
+		}
+	}
+
+	pub async fn dial(&self, opts: impl Into<DialOpts>) {
+		self.send(NetworkCommand::Dial(opts.into())).await;
+	}
+
+	/// Dials known peers we are not connected to yet, so they join the
+	/// gossipsub mesh.
+	pub async fn dial_known_peers(&self, peers: Vec<(PeerId, Vec<Multiaddr>)>) {
+		if !peers.is_empty() {
+			self.send(NetworkCommand::DialKnownPeers(peers))
+				.await;
+		}
+	}
+
+	pub async fn connected_peers(&self) -> Vec<PeerId> {
+		let (reply, response) = oneshot::channel();
+		self.send(NetworkCommand::ConnectedPeers(reply))
+			.await;
+		response.await.unwrap_or_default()
+	}
+
+	pub async fn listen_addrs(&self) -> Vec<Multiaddr> {
+		let (reply, response) = oneshot::channel();
+		self.send(NetworkCommand::ListenAddrs(reply))
+			.await;
+		response.await.unwrap_or_default()
+	}
+
+	pub async fn report_validation(
+		&self,
+		message_id: MessageId,
+		propagation_source: PeerId,
+		acceptance: MessageAcceptance,
+	) {
+		self.send(NetworkCommand::ReportValidation {
+			message_id,
+			propagation_source,
+			acceptance,
+		})
+		.await;
+	}
+
+	/// Connected peers whose gossipsub score dropped below `PEER_BAN_SCORE`.
+	pub async fn low_score_peers(&self) -> Vec<PeerId> {
+		let (reply, response) = oneshot::channel();
+		self.send(NetworkCommand::LowScorePeers(reply))
+			.await;
+		response.await.unwrap_or_default()
+	}
+
+	/// Blocks connections of `banned` peers, closing the open ones, and
+	/// lifts the block of peers that are no longer banned.
+	pub async fn sync_bans(&self, banned: HashSet<PeerId>) {
+		self.send(NetworkCommand::SyncBans(banned)).await;
+	}
+
+	/// Looks up the peers closest to a random key, which refreshes the
+	/// routing table and discovers peers outside the local network.
+	pub async fn random_walk(&self) {
+		self.send(NetworkCommand::RandomWalk).await;
+	}
+
+	/// Unsubscribes from every topic, closes all connections and stops the
+	/// service. Returns once the last connection is closed.
+	pub async fn shutdown(&self) {
+		let (reply, response) = oneshot::channel();
+		self.send(NetworkCommand::Shutdown(reply)).await;
+		let _ = response.await;
+	}
+
+	// a stopped service drops the command, replies then fail
+	async fn send(&self, command: NetworkCommand) {
+		let _ = self.command_tx.send(command).await;
+	}
+}
+
+/// Owns the swarm of a node and drives it from its own task. Other tasks
+/// talk to it through a `NetworkHandle`, so several nodes can run in one
+/// process. The service stops once every handle is dropped or after
+/// `NetworkHandle::shutdown`.
+pub struct NetworkService {
+	swarm: Swarm<P2PBehaviour>,
+	command_rx: mpsc::Receiver<NetworkCommand>,
+	event_tx: broadcast::Sender<NetworkEvent>,
+	connected_peers: HashMap<PeerId, u32>,
+	// answered once the last connection is closed
+	shutdown_reply: Option<oneshot::Sender<()>>,
+}
+
+impl NetworkService {
+	pub fn new(
+		config: P2PConfig,
+	) -> Result<(Self, NetworkHandle), Box<dyn Error>> {
+		let swarm = build_swarm(config)?;
+		let (command_tx, command_rx) = mpsc::channel(COMMAND_CHANNEL_SIZE);
+		let (event_tx, _) = broadcast::channel(EVENT_CHANNEL_SIZE);
+		let handle = NetworkHandle {
+			peer_id: *swarm.local_peer_id(),
+			command_tx,
+			event_tx: event_tx.clone(),
+		};
+		let service = Self {
+			swarm,
+			command_rx,
+			event_tx,
+			connected_peers: HashMap::new(),
+			shutdown_reply: None,
+		};
+		Ok((service, handle))
+	}
+
+	pub async fn run(mut self) {
+		loop {
+			tokio::select! {
+				command = self.command_rx.recv() => match command {
+					Some(command) => self.handle_command(command),
+					None => break,
+				},
+				event = self.swarm.select_next_some() => {
+					self.handle_swarm_event(event);
+				}
+			}
+			if self.shutdown_reply.is_some() && self.connected_peers.is_empty()
+			{
+				if let Some(reply) = self.shutdown_reply.take() {
+					let _ = reply.send(());
+				}
+				break;
+			}
+		}
+	}
+
+	fn emit(&self, event: NetworkEvent) {
+		// nobody listening is fine
+		let _ = self.event_tx.send(event);
+	}
+
+	fn handle_command(&mut self, command: NetworkCommand) {
+		match command {
+			NetworkCommand::Publish { topic, data, reply } => {
+				let result = self
+					.swarm
+					.behaviour_mut()
+					.gossipsub
+					.publish(IdentTopic::new(topic.to_string()), data);
+				let _ = reply.send(result);
+			}
+			NetworkCommand::Dial(opts) => {
+				if let Err(e) = self.swarm.dial(opts) {
+					eprintln!("Failed to dial: {}", e);
+				}
+			}
+			NetworkCommand::DialKnownPeers(peers) => {
+				self.dial_known_peers(peers)
+			}
+			NetworkCommand::ConnectedPeers(reply) => {
+				let _ =
+					reply.send(self.connected_peers.keys().copied().collect());
+			}
+			NetworkCommand::ListenAddrs(reply) => {
+				let _ = reply.send(self.swarm.listeners().cloned().collect());
+			}
+			NetworkCommand::ReportValidation {
+				message_id,
+				propagation_source,
+				acceptance,
+			} => {
+				self.swarm
+					.behaviour_mut()
+					.gossipsub
+					.report_message_validation_result(
+						&message_id,
+						&propagation_source,
+						acceptance,
+					);
+			}
+			NetworkCommand::LowScorePeers(reply) => {
+				let gossipsub = &self.swarm.behaviour().gossipsub;
+				let peers = self
+					.connected_peers
+					.keys()
+					.filter(|peer_id| {
+						gossipsub
+							.peer_score(peer_id)
+							.is_some_and(|score| score < PEER_BAN_SCORE)
+					})
+					.copied()
+					.collect();
+				let _ = reply.send(peers);
+			}
+			NetworkCommand::SyncBans(banned) => self.sync_bans(&banned),
+			NetworkCommand::RandomWalk => {
+				self.swarm
+					.behaviour_mut()
+					.kademlia
+					.get_closest_peers(PeerId::random());
+			}
+			NetworkCommand::Shutdown(reply) => self.start_shutdown(reply),
+		}
+	}
+
+	fn start_shutdown(&mut self, reply: oneshot::Sender<()>) {
+		let gossipsub = &mut self.swarm.behaviour_mut().gossipsub;
+		for topic in TopicEnum::iter() {
+			gossipsub.unsubscribe(&IdentTopic::new(topic.to_string()));
+		}
+		let peers: Vec<PeerId> = self.connected_peers.keys().copied().collect();
+		for peer_id in peers {
+			let _ = self.swarm.disconnect_peer_id(peer_id);
+		}
+		self.shutdown_reply = Some(reply);
+	}
+
+	fn dial_known_peers(&mut self, peers: Vec<(PeerId, Vec<Multiaddr>)>) {
+		let local_peer_id = *self.swarm.local_peer_id();
+		for (peer_id, addrs) in peers {
+			if peer_id == local_peer_id
+				|| self.connected_peers.contains_key(&peer_id)
+			{
+				continue;
+			}
+			let opts = DialOpts::peer_id(peer_id)
+				.addresses(addrs)
+				.build();
+			if let Err(e) = self.swarm.dial(opts) {
+				eprintln!("Failed to connect to {}: {}", peer_id, e);
+			}
+		}
+	}
+
+	fn sync_bans(&mut self, banned: &HashSet<PeerId>) {
+		let behaviour = self.swarm.behaviour_mut();
+		let unbanned: Vec<PeerId> = behaviour
+			.block_list
+			.blocked_peers()
+			.difference(banned)
+			.copied()
+			.collect();
+		for peer_id in unbanned {
+			println!("* Peer unbanned: {}", peer_id);
+			behaviour.block_list.unblock_peer(peer_id);
+			behaviour
+				.gossipsub
+				.remove_blacklisted_peer(&peer_id);
+		}
+		for peer_id in banned {
+			if behaviour.block_list.block_peer(*peer_id) {
+				println!("* Peer banned: {}", peer_id);
+				behaviour.gossipsub.blacklist_peer(peer_id);
+			}
+		}
+	}
+
+	fn add_connected_peer(&mut self, peer_id: PeerId) {
+		// peer number deduplication
+		let count = self
+			.connected_peers
+			.entry(peer_id)
+			.and_modify(|c| *c += 1)
+			.or_insert(1);
+
+		if *count == 1 {
+			println!(
+				"* New peer: {} ({} unique peers)",
+				peer_id,
+				self.connected_peers.len()
+			);
+		} else {
+			println!(
+				"* Additional connection to: {} ({} total connections)",
+				peer_id, count
+			);
+		}
+	}
+
+	fn closed_connection(&mut self, peer_id: PeerId) {
+		if let Some(count) = self.connected_peers.get_mut(&peer_id) {
+			*count -= 1;
+			if *count == 0 {
+				self.connected_peers.remove(&peer_id);
+				println!(
+					"Peer disconnected: {} ({} peers left)",
+					peer_id,
+					self.connected_peers.len()
+				);
+			} else {
+				println!("Connection closed: {} ({} remain)", peer_id, count);
+			}
+		}
+	}
+
+	// Adds the listen addresses reported by a DHT capable peer to the
+	// routing table.
+	fn add_identified_peer(&mut self, peer_id: &PeerId, info: &identify::Info) {
+		let kad_protocol = StreamProtocol::new(KAD_PROTOCOL);
+		if !info.protocols.contains(&kad_protocol) {
+			return;
+		}
+		for addr in &info.listen_addrs {
+			self.swarm
+				.behaviour_mut()
+				.kademlia
+				.add_address(peer_id, addr.clone());
+		}
+	}
+
+	fn handle_swarm_event(&mut self, event: SwarmEvent<P2PBehaviourEvent>) {
+		match event {
+			SwarmEvent::Behaviour(P2PBehaviourEvent::Gossipsub(
+				gossipsub::Event::Message {
+					propagation_source,
+					message_id,
+					message,
+				},
+			)) => {
+				self.emit(NetworkEvent::Message {
+					propagation_source,
+					message_id,
+					message,
+				});
+			}
+			SwarmEvent::NewListenAddr { address, .. } => {
+				println!("* Listening on: {}", address);
+				self.emit(NetworkEvent::NewListenAddr(address));
+			}
+			SwarmEvent::OutgoingConnectionError { peer_id, error, .. } => {
+				if let Some(peer_id) = peer_id {
+					eprintln!("Failed to connect to {}: {}", peer_id, error);
+					// another dial to the peer is in flight or already
+					// succeeded
+					if !matches!(error, DialError::DialPeerConditionFalse(_)) {
+						self.emit(NetworkEvent::DialFailed(peer_id));
+					}
+				} else {
+					eprintln!("Failed to connect: {}", error);
+				}
+			}
+			SwarmEvent::Behaviour(P2PBehaviourEvent::Mdns(
+				mdns::Event::Discovered(list),
+			)) => {
+				for (peer_id, addr) in list {
+					println!("* Discovered peer: {} at {}", peer_id, addr);
+					// Force connection to discovered peer
+					if let Err(e) = self.swarm.dial(addr) {
+						eprintln!("Failed to connect to {}: {}", peer_id, e);
+					}
+				}
+			}
+			SwarmEvent::Behaviour(P2PBehaviourEvent::Mdns(
+				mdns::Event::Expired(list),
+			)) => {
+				for (peer_id, _addr) in list {
+					println!("* Peer expired: {}", peer_id);
+				}
+			}
+			SwarmEvent::Behaviour(P2PBehaviourEvent::Identify(
+				identify::Event::Received { peer_id, info, .. },
+			)) => {
+				self.add_identified_peer(&peer_id, &info);
+				self.emit(NetworkEvent::Identified {
+					peer_id,
+					listen_addrs: info.listen_addrs,
+				});
+			}
+			SwarmEvent::Behaviour(P2PBehaviourEvent::Kademlia(
+				kad::Event::RoutingUpdated { peer, addresses, .. },
+			)) => {
+				self.dial_known_peers(vec![(peer, addresses.into_vec())]);
+			}
+			SwarmEvent::Behaviour(P2PBehaviourEvent::Kademlia(
+				kad::Event::OutboundQueryProgressed {
+					result: kad::QueryResult::GetClosestPeers(Ok(closest)),
+					..
+				},
+			)) => {
+				let peers = closest
+					.peers
+					.into_iter()
+					.map(|peer| (peer.peer_id, peer.addrs))
+					.collect();
+				self.dial_known_peers(peers);
+			}
+			SwarmEvent::Behaviour(P2PBehaviourEvent::Ping(ping::Event {
+				peer,
+				result: Ok(latency),
+				..
+			})) => {
+				self.emit(NetworkEvent::Latency { peer_id: peer, latency });
+			}
+			SwarmEvent::ConnectionEstablished { peer_id, endpoint, .. } => {
+				self.add_connected_peer(peer_id);
+				if self.shutdown_reply.is_some() {
+					let _ = self.swarm.disconnect_peer_id(peer_id);
+				}
+				let dialed_addr = endpoint
+					.is_dialer()
+					.then(|| endpoint.get_remote_address().clone());
+				self.emit(NetworkEvent::ConnectionEstablished {
+					peer_id,
+					dialed_addr,
+				});
+			}
+			SwarmEvent::ConnectionClosed { peer_id, .. } => {
+				self.closed_connection(peer_id);
+				self.emit(NetworkEvent::ConnectionClosed(peer_id));
+			}
+			_ => {}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		block::{Block, BlockTr},
+		blockchain::{Blockchain, BlockchainTr},
+		config::MAX_BLOCK_BYTES,
+		p2p_mdns_bc_coms::Transports,
+	};
+	use libp2p::identity::Keypair;
+	use pretty_assertions::assert_eq;
+
+	fn loopback_config() -> P2PConfig {
+		P2PConfig {
+			keypair: Keypair::generate_ed25519(),
+			enable_mdns: false,
+			bootstrap_peers: Vec::new(),
+			transports: Transports { tcp: true, quic: false, websocket: false },
+			listen_addrs: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+			external_addrs: Vec::new(),
+		}
+	}
+
+	fn start_node() -> NetworkHandle {
+		let (service, handle) = NetworkService::new(loopback_config()).unwrap();
+		tokio::spawn(service.run());
+		handle
+	}
+
+	async fn wait_for<F>(
+		events: &mut broadcast::Receiver<NetworkEvent>,
+		mut matches: F,
+	) -> NetworkEvent
+	where
+		F: FnMut(&NetworkEvent) -> bool,
+	{
+		tokio::time::timeout(Duration::from_secs(10), async {
+			loop {
+				let event = events.recv().await.unwrap();
+				if matches(&event) {
+					return event;
+				}
+			}
+		})
+		.await
+		.expect("Timed out waiting for network event.")
+	}
+
+	#[tokio::test]
+	async fn two_services_connect_in_one_process() {
+		let first = start_node();
+		let second = start_node();
+		let mut first_events = first.subscribe();
+		let mut second_events = second.subscribe();
+
+		let addr = match wait_for(&mut first_events, |event| {
+			matches!(event, NetworkEvent::NewListenAddr(_))
+		})
+		.await
+		{
+			NetworkEvent::NewListenAddr(addr) => addr,
+			_ => unreachable!(),
+		};
+		second.dial(addr).await;
+
+		wait_for(&mut second_events, |event| {
+			matches!(
+				event,
+				NetworkEvent::ConnectionEstablished { peer_id, .. }
+					if *peer_id == first.peer_id()
+			)
+		})
+		.await;
+		assert_eq!(second.connected_peers().await, vec![first.peer_id()]);
+		assert_eq!(first.listen_addrs().await.len(), 1);
+	}
+
+	#[tokio::test]
+	async fn stopped_service_fails_publish() {
+		let (service, handle) = NetworkService::new(loopback_config()).unwrap();
+		drop(service);
+
+		let result = handle
+			.publish(TopicEnum::Blockchain, b"data".to_vec())
+			.await;
+
+		assert!(matches!(result, Err(NetworkError::Stopped)));
+		assert_eq!(handle.connected_peers().await, vec![]);
+	}
+
+	#[tokio::test]
+	async fn refuses_message_over_transmit_size() {
+		let (service, handle) = NetworkService::new(loopback_config()).unwrap();
+		drop(service);
+
+		let result = handle
+			.publish(
+				TopicEnum::Blockchain,
+				vec![0; GOSSIP_MAX_TRANSMIT_SIZE + 1],
+			)
+			.await;
+
+		assert!(matches!(
+			result,
+			Err(NetworkError::TooLarge(size)) if size == GOSSIP_MAX_TRANSMIT_SIZE + 1
+		));
+	}
+
+	#[tokio::test]
+	async fn shutdown_closes_connections() {
+		let first = start_node();
+		let second = start_node();
+		let mut second_events = second.subscribe();
+		let addr = loop {
+			if let Some(addr) = first.listen_addrs().await.into_iter().next() {
+				break addr;
+			}
+			tokio::time::sleep(Duration::from_millis(10)).await;
+		};
+		second.dial(addr).await;
+		wait_for(&mut second_events, |event| {
+			matches!(event, NetworkEvent::ConnectionEstablished { .. })
+		})
+		.await;
+
+		first.shutdown().await;
+
+		wait_for(&mut second_events, |event| {
+			matches!(
+				event,
+				NetworkEvent::ConnectionClosed(peer_id)
+					if *peer_id == first.peer_id()
+			)
+		})
+		.await;
+		let result = first
+			.publish(TopicEnum::Blockchain, b"data".to_vec())
+			.await;
+		assert!(matches!(result, Err(NetworkError::Stopped)));
+	}
+
+	#[tokio::test]
+	async fn delivers_chain_larger_than_default_transmit_size() {
+		let first = start_node();
+		let second = start_node();
+		let mut second_events = second.subscribe();
+		let addr = loop {
+			if let Some(addr) = first.listen_addrs().await.into_iter().next() {
+				break addr;
+			}
+			tokio::time::sleep(Duration::from_millis(10)).await;
+		};
+		second.dial(addr).await;
+
+		let mut block = Block::genesis();
+		block.data = vec!["x".repeat(MAX_BLOCK_BYTES / 2)];
+		let data = Blockchain::to_bytes(&vec![block; 4]).unwrap();
+		assert!(data.len() > 64 * 1024);
+
+		// publishing fails until the subscription of the peer arrives
+		tokio::time::timeout(Duration::from_secs(10), async {
+			while first
+				.publish(TopicEnum::Blockchain, data.clone())
+				.await
+				.is_err()
+			{
+				tokio::time::sleep(Duration::from_millis(50)).await;
+			}
+		})
+		.await
+		.expect("Timed out publishing the chain.");
+
+		let event = wait_for(&mut second_events, |event| {
+			matches!(event, NetworkEvent::Message { .. })
+		})
+		.await;
+		let NetworkEvent::Message { message, .. } = event else {
+			unreachable!()
+		};
+		assert_eq!(message.data, data);
+	}
+}
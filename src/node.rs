@@ -0,0 +1,121 @@
+use std::{error::Error, path::Path, sync::Arc, time::Duration};
+
+use tokio::sync::RwLock;
+
+use crate::{
+	address_book::AddressBook,
+	blockchain::Blockchain,
+	channels::create_unbounded_channel,
+	config::{
+		ADDRESS_BOOK_FILE, CHAIN_FILE, PEER_BANS_FILE, SHUTDOWN_TIMEOUT,
+		TRANSACTION_POOL_FILE, WALLET_KEY_FILE,
+	},
+	console::start_console_task,
+	http_server::{AppState, start_http_server_task},
+	network_service::NetworkService,
+	node_config::NodeConfig,
+	p2p_task::start_p2p_task,
+	peer_bans::BanList,
+	shutdown::Shutdown,
+	transaction_pool::TransactionPool,
+	txn_admission::load_transaction_pool,
+	wallet::Wallet,
+};
+
+/// Runs a node until Ctrl-C, SIGTERM or a failing task. On the way out
+/// HTTP stops accepting requests, mining is cancelled, the p2p task leaves
+/// the network and chain, pool, bans and address book are written to the
+/// data directory.
+pub async fn run(
+	node_config: NodeConfig,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+	let p2p_config = node_config.p2p_config()?;
+	let data_dir = &node_config.data_dir;
+
+	let (event_tx, event_rx) = create_unbounded_channel();
+	let blockchain = Blockchain::load(data_dir.join(CHAIN_FILE))?;
+	// stored transactions may have been mined by now
+	let transaction_pool = load_transaction_pool(
+		data_dir.join(TRANSACTION_POOL_FILE),
+		&blockchain.chain,
+	)?;
+	let blockchain = Arc::new(RwLock::new(blockchain));
+	let transaction_pool = Arc::new(RwLock::new(transaction_pool));
+	let wallet = Wallet::load_or_generate(data_dir.join(WALLET_KEY_FILE))?;
+	println!("* Wallet address: {}", wallet.address());
+	let wallet = Arc::new(RwLock::new(wallet));
+	let ban_list =
+		Arc::new(RwLock::new(BanList::load(data_dir.join(PEER_BANS_FILE))?));
+	let address_book = Arc::new(RwLock::new(AddressBook::load(
+		data_dir.join(ADDRESS_BOOK_FILE),
+	)?));
+
+	let (network_service, network) = NetworkService::new(p2p_config)
+		.map_err(|err| format!("Failed to init P2P: {err}"))?;
+
+	let mut shutdown = Shutdown::new();
+	let p2p_handle = start_p2p_task(
+		blockchain.clone(),
+		transaction_pool.clone(),
+		ban_list.clone(),
+		address_book.clone(),
+		network,
+		event_rx,
+		shutdown.token(),
+	);
+	shutdown.track("p2p", p2p_handle);
+	shutdown.track("network", tokio::spawn(network_service.run()));
+	let state = AppState::new(
+		blockchain.clone(),
+		wallet,
+		transaction_pool.clone(),
+		ban_list.clone(),
+		address_book.clone(),
+		event_tx,
+		shutdown.token(),
+	);
+	if node_config.console {
+		// not tracked, closing stdin leaves the node running
+		start_console_task(state.clone());
+	}
+	let http_server_handle =
+		start_http_server_task(node_config.http_addr, state);
+	shutdown.track("HTTP server", http_server_handle);
+
+	let stopped = shutdown.wait().await;
+	flush_state(
+		data_dir,
+		&blockchain,
+		&transaction_pool,
+		&ban_list,
+		&address_book,
+	)
+	.await?;
+	println!("* State saved to {}", data_dir.display());
+	stopped.map_err(Into::into)
+}
+
+async fn flush_state(
+	data_dir: &Path,
+	blockchain: &RwLock<Blockchain>,
+	transaction_pool: &RwLock<TransactionPool>,
+	ban_list: &RwLock<BanList>,
+	address_book: &RwLock<AddressBook>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+	// a task that missed the shutdown timeout may still hold a lock
+	let timeout = Duration::from_secs(SHUTDOWN_TIMEOUT);
+	tokio::time::timeout(timeout, async {
+		blockchain
+			.read()
+			.await
+			.save(data_dir.join(CHAIN_FILE))?;
+		transaction_pool
+			.read()
+			.await
+			.save(data_dir.join(TRANSACTION_POOL_FILE))?;
+		ban_list.read().await.save()?;
+		address_book.read().await.save()
+	})
+	.await
+	.map_err(|_| "Timed out waiting to save state.")?
+}
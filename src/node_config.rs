@@ -0,0 +1,332 @@
+use std::{
+	error::Error,
+	fs,
+	net::SocketAddr,
+	path::{Path, PathBuf},
+};
+
+use clap::Parser;
+use libp2p::Multiaddr;
+use serde::Deserialize;
+use serde_with::{DisplayFromStr, serde_as};
+
+use crate::{
+	config::{DATA_DIR, HTTP_ADDR, NODE_KEY_FILE},
+	node_key,
+	p2p_mdns_bc_coms::{P2PConfig, Transports},
+};
+
+/// Node settings read from a TOML file. Missing keys keep their defaults,
+/// and an empty `listen_addrs` listens on a random port per transport.
+///
+/// ```toml
+/// data_dir = "data"
+/// http_addr = "0.0.0.0:3005"
+/// listen_addrs = ["/ip4/0.0.0.0/tcp/4001", "/ip4/0.0.0.0/udp/4001/quic-v1"]
+/// external_addrs = ["/ip4/203.0.113.7/tcp/4001"]
+/// bootstrap_peers = []
+/// mdns = false
+/// console = false
+///
+/// [transports]
+/// tcp = true
+/// quic = true
+/// websocket = false
+/// ```
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NodeConfig {
+	// node key, peer bans and address book
+	pub data_dir: PathBuf,
+	pub http_addr: SocketAddr,
+	#[serde_as(as = "Vec<DisplayFromStr>")]
+	pub listen_addrs: Vec<Multiaddr>,
+	#[serde_as(as = "Vec<DisplayFromStr>")]
+	pub external_addrs: Vec<Multiaddr>,
+	#[serde_as(as = "Vec<DisplayFromStr>")]
+	pub bootstrap_peers: Vec<Multiaddr>,
+	pub mdns: bool,
+	pub transports: Transports,
+	// operator console on stdin
+	pub console: bool,
+}
+
+impl Default for NodeConfig {
+	fn default() -> Self {
+		let p2p_config = P2PConfig::default();
+		Self {
+			data_dir: PathBuf::from(DATA_DIR),
+			http_addr: HTTP_ADDR.parse().expect("Valid HTTP address."),
+			listen_addrs: Vec::new(),
+			external_addrs: p2p_config.external_addrs,
+			bootstrap_peers: p2p_config.bootstrap_peers,
+			mdns: p2p_config.enable_mdns,
+			transports: p2p_config.transports,
+			console: false,
+		}
+	}
+}
+
+/// Command line arguments of a node. Every option can also be set through
+/// its `CRYPTOCHAIN_*` environment variable and overrides the config file.
+/// List variables are comma separated.
+#[derive(Debug, Default, Parser)]
+#[command(about = "Runs a cryptochain node.")]
+pub struct NodeArgs {
+	/// TOML config file.
+	#[arg(long, env = "CRYPTOCHAIN_CONFIG")]
+	pub config: Option<PathBuf>,
+	/// HTTP port on the configured address.
+	pub port: Option<u16>,
+	#[arg(long, env = "CRYPTOCHAIN_DATA_DIR")]
+	pub data_dir: Option<PathBuf>,
+	#[arg(long, env = "CRYPTOCHAIN_HTTP_ADDR")]
+	pub http_addr: Option<SocketAddr>,
+	/// Replaces the configured listen addresses.
+	#[arg(long = "listen", env = "CRYPTOCHAIN_LISTEN", value_delimiter = ',')]
+	pub listen_addrs: Vec<Multiaddr>,
+	/// Replaces the configured external addresses.
+	#[arg(
+		long = "external",
+		env = "CRYPTOCHAIN_EXTERNAL",
+		value_delimiter = ','
+	)]
+	pub external_addrs: Vec<Multiaddr>,
+	/// Added to the configured bootstrap peers.
+	#[arg(
+		long = "bootstrap",
+		env = "CRYPTOCHAIN_BOOTSTRAP",
+		value_delimiter = ','
+	)]
+	pub bootstrap_peers: Vec<Multiaddr>,
+	#[arg(
+		long,
+		env = "CRYPTOCHAIN_MDNS",
+		num_args = 0..=1,
+		default_missing_value = "true"
+	)]
+	pub mdns: Option<bool>,
+	/// Same as `--mdns false`.
+	#[arg(long, conflicts_with = "mdns")]
+	pub no_mdns: bool,
+	#[arg(
+		long,
+		env = "CRYPTOCHAIN_TCP",
+		num_args = 0..=1,
+		default_missing_value = "true"
+	)]
+	pub tcp: Option<bool>,
+	#[arg(
+		long,
+		env = "CRYPTOCHAIN_QUIC",
+		num_args = 0..=1,
+		default_missing_value = "true"
+	)]
+	pub quic: Option<bool>,
+	#[arg(
+		long,
+		env = "CRYPTOCHAIN_WEBSOCKET",
+		num_args = 0..=1,
+		default_missing_value = "true"
+	)]
+	pub websocket: Option<bool>,
+	/// Reads operator commands from stdin, type `help` to list them.
+	#[arg(long)]
+	pub console: bool,
+}
+
+impl NodeConfig {
+	pub fn load(
+		path: impl AsRef<Path>,
+	) -> Result<Self, Box<dyn Error + Send + Sync>> {
+		let text = fs::read_to_string(path)?;
+		Ok(toml::from_str(&text)?)
+	}
+
+	/// Reads the config file named in `args`, if any, and applies the
+	/// arguments on top of it.
+	pub fn from_args(
+		args: NodeArgs,
+	) -> Result<Self, Box<dyn Error + Send + Sync>> {
+		let mut config = match &args.config {
+			Some(path) => Self::load(path)?,
+			None => Self::default(),
+		};
+		config.apply(args);
+		config.validate()?;
+		Ok(config)
+	}
+
+	fn apply(&mut self, args: NodeArgs) {
+		if let Some(data_dir) = args.data_dir {
+			self.data_dir = data_dir;
+		}
+		if let Some(http_addr) = args.http_addr {
+			self.http_addr = http_addr;
+		}
+		if let Some(port) = args.port {
+			self.http_addr.set_port(port);
+		}
+		if !args.listen_addrs.is_empty() {
+			self.listen_addrs = args.listen_addrs;
+		}
+		if !args.external_addrs.is_empty() {
+			self.external_addrs = args.external_addrs;
+		}
+		self.bootstrap_peers.extend(args.bootstrap_peers);
+		if let Some(mdns) = args.mdns {
+			self.mdns = mdns;
+		}
+		if args.no_mdns {
+			self.mdns = false;
+		}
+		if let Some(tcp) = args.tcp {
+			self.transports.tcp = tcp;
+		}
+		if let Some(quic) = args.quic {
+			self.transports.quic = quic;
+		}
+		if let Some(websocket) = args.websocket {
+			self.transports.websocket = websocket;
+		}
+		if args.console {
+			self.console = true;
+		}
+	}
+
+	pub fn validate(&self) -> Result<(), String> {
+		let transports = self.transports;
+		if !(transports.tcp || transports.quic || transports.websocket) {
+			return Err("At least one transport must be enabled.".to_string());
+		}
+		if let Some(addr) = self
+			.listen_addrs
+			.iter()
+			.find(|addr| !transports.supports(addr))
+		{
+			return Err(format!("No enabled transport can listen on {addr}."));
+		}
+		Ok(())
+	}
+
+	pub fn node_key_path(&self) -> PathBuf {
+		self.data_dir.join(NODE_KEY_FILE)
+	}
+
+	/// Builds the network config with the node key stored in the data
+	/// directory, which is created on first start.
+	pub fn p2p_config(
+		&self,
+	) -> Result<P2PConfig, Box<dyn Error + Send + Sync>> {
+		let keypair = node_key::load_or_generate(self.node_key_path())?;
+		let listen_addrs = if self.listen_addrs.is_empty() {
+			self.transports.default_listen_addrs()
+		} else {
+			self.listen_addrs.clone()
+		};
+		Ok(P2PConfig {
+			keypair,
+			enable_mdns: self.mdns,
+			bootstrap_peers: self.bootstrap_peers.clone(),
+			transports: self.transports,
+			listen_addrs,
+			external_addrs: self.external_addrs.clone(),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use pretty_assertions::assert_eq;
+
+	fn parse_args(args: &[&str]) -> NodeArgs {
+		NodeArgs::try_parse_from(
+			std::iter::once("cryptochain").chain(args.iter().copied()),
+		)
+		.unwrap()
+	}
+
+	#[test]
+	fn parses_config_file() {
+		let config: NodeConfig = toml::from_str(
+			r#"
+			http_addr = "0.0.0.0:4000"
+			listen_addrs = ["/ip4/0.0.0.0/tcp/4001"]
+			mdns = false
+
+			[transports]
+			quic = false
+			"#,
+		)
+		.unwrap();
+
+		assert_eq!(config.http_addr, "0.0.0.0:4000".parse().unwrap());
+		assert_eq!(config.mdns, false);
+		assert_eq!(
+			config.transports,
+			Transports { tcp: true, quic: false, websocket: false }
+		);
+		assert_eq!(config.validate(), Ok(()));
+	}
+
+	#[test]
+	fn rejects_unknown_keys() {
+		let config = toml::from_str::<NodeConfig>("http_port = 4000");
+
+		assert!(config.is_err());
+	}
+
+	#[test]
+	fn args_override_config() {
+		let mut config = NodeConfig::default();
+		config.apply(parse_args(&[
+			"4000",
+			"--listen",
+			"/ip4/127.0.0.1/tcp/4001,/ip4/127.0.0.1/tcp/4001/ws",
+			"--websocket",
+			"--quic",
+			"false",
+			"--no-mdns",
+		]));
+
+		assert_eq!(config.http_addr.port(), 4000);
+		assert_eq!(config.listen_addrs.len(), 2);
+		assert_eq!(
+			config.transports,
+			Transports { tcp: true, quic: false, websocket: true }
+		);
+		assert_eq!(config.mdns, false);
+		assert_eq!(config.validate(), Ok(()));
+	}
+
+	#[test]
+	fn rejects_listen_addr_of_disabled_transport() {
+		let mut config = NodeConfig::default();
+		config.apply(parse_args(&[
+			"--listen",
+			"/ip4/127.0.0.1/udp/4001/quic-v1",
+			"--quic=false",
+		]));
+
+		assert!(config.validate().is_err());
+	}
+
+	#[test]
+	fn empty_listen_addrs_use_transport_defaults() {
+		let data_dir = std::env::temp_dir().join(format!(
+			"cryptochain-node-config-{}",
+			libp2p::PeerId::random()
+		));
+		let mut config = NodeConfig { data_dir, ..NodeConfig::default() };
+		config.transports.quic = false;
+
+		let p2p_config = config.p2p_config().unwrap();
+		fs::remove_dir_all(&config.data_dir).unwrap();
+		assert_eq!(
+			p2p_config.listen_addrs,
+			vec!["/ip4/0.0.0.0/tcp/0".parse::<Multiaddr>().unwrap()]
+		);
+	}
+}
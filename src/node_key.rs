@@ -0,0 +1,96 @@
+use std::{error::Error, fs, path::Path};
+
+use libp2p::identity::Keypair;
+
+/// Loads the libp2p identity of the node from `path`, generating and
+/// storing a new ed25519 key on first start. The key only identifies the
+/// node on the network and is unrelated to the wallet key.
+pub fn load_or_generate(
+	path: impl AsRef<Path>,
+) -> Result<Keypair, Box<dyn Error + Send + Sync>> {
+	let path = path.as_ref();
+	if !path.exists() {
+		return rotate(path);
+	}
+	let bytes = fs::read(path)?;
+	Ok(Keypair::from_protobuf_encoding(&bytes)?)
+}
+
+/// Replaces the key stored at `path` with a new one. A running node keeps
+/// its old identity until it is restarted.
+pub fn rotate(
+	path: impl AsRef<Path>,
+) -> Result<Keypair, Box<dyn Error + Send + Sync>> {
+	let keypair = Keypair::generate_ed25519();
+	save(path.as_ref(), &keypair)?;
+	Ok(keypair)
+}
+
+/// Writes `keypair` to `path`, readable by the owner only.
+pub(crate) fn save(
+	path: &Path,
+	keypair: &Keypair,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+	if let Some(dir) = path.parent() {
+		fs::create_dir_all(dir)?;
+	}
+	// written next to the key and renamed, so a crash never leaves a
+	// truncated key behind
+	let tmp_path = path.with_extension("tmp");
+	fs::write(&tmp_path, keypair.to_protobuf_encoding()?)?;
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::PermissionsExt;
+		fs::set_permissions(&tmp_path, fs::Permissions::from_mode(0o600))?;
+	}
+	fs::rename(&tmp_path, path)?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use libp2p::PeerId;
+	use pretty_assertions::assert_eq;
+	use std::path::PathBuf;
+
+	fn key_path() -> PathBuf {
+		std::env::temp_dir()
+			.join(format!("cryptochain-node-key-{}.bin", PeerId::random()))
+	}
+
+	#[test]
+	fn keeps_identity_across_loads() {
+		let path = key_path();
+
+		let generated = load_or_generate(&path).unwrap();
+		let loaded = load_or_generate(&path).unwrap();
+		fs::remove_file(&path).unwrap();
+
+		assert_eq!(generated.public(), loaded.public());
+	}
+
+	#[test]
+	fn rotate_replaces_identity() {
+		let path = key_path();
+
+		let generated = load_or_generate(&path).unwrap();
+		let rotated = rotate(&path).unwrap();
+		let loaded = load_or_generate(&path).unwrap();
+		fs::remove_file(&path).unwrap();
+
+		assert_ne!(generated.public(), rotated.public());
+		assert_eq!(rotated.public(), loaded.public());
+	}
+
+	#[test]
+	fn rejects_corrupt_key() {
+		let path = key_path();
+		fs::write(&path, b"not a key").unwrap();
+
+		let loaded = load_or_generate(&path);
+		fs::remove_file(&path).unwrap();
+
+		assert!(loaded.is_err());
+	}
+}
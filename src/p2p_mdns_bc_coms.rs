@@ -1,29 +1,41 @@
+use libp2p::core::{
+	muxing::StreamMuxerBox,
+	transport::{Boxed, Transport, upgrade},
+};
 use libp2p::gossipsub::{
-	Behaviour, Config, IdentTopic, IdentityTransform, MessageAuthenticity,
-	MessageId, PublishError,
+	Behaviour, ConfigBuilder, IdentTopic, IdentityTransform,
+	MessageAuthenticity, PeerScoreParams, PeerScoreThresholds,
+	TopicScoreParams, ValidationMode,
 };
 use libp2p::identity::Keypair;
-use libp2p::swarm::SwarmEvent;
-use libp2p::{Multiaddr, PeerId, Swarm, SwarmBuilder, mdns, tcp, tls, yamux};
+use libp2p::multiaddr::Protocol;
+use libp2p::swarm::behaviour::toggle::Toggle;
+use libp2p::{
+	Multiaddr, PeerId, StreamProtocol, Swarm, SwarmBuilder, allow_block_list,
+	dns, identify, kad, mdns, ping, quic, tcp, tls, websocket, yamux,
+};
+use serde::Deserialize;
 
-use std::sync::Arc;
+use std::error::Error;
 use std::time::Duration;
-use std::{collections::HashMap, error::Error};
 use strum::{EnumString, IntoEnumIterator};
 use strum_macros::{Display, EnumIter};
-use tokio::sync::{Mutex, OnceCell, RwLock, mpsc};
 
-use crate::blockchain::{Blockchain, BlockchainTr};
+use crate::config::{BOOTSTRAP_PEERS, GOSSIP_MAX_TRANSMIT_SIZE};
+use crate::constants::{IDENTIFY_PROTOCOL, KAD_PROTOCOL};
 
 #[derive(libp2p::swarm::NetworkBehaviour)]
 pub struct P2PBehaviour {
+	// refuses connections from banned peers
+	pub block_list: allow_block_list::Behaviour<allow_block_list::BlockedPeers>,
 	pub gossipsub: Behaviour,
-	pub mdns: mdns::tokio::Behaviour,
+	pub mdns: Toggle<mdns::tokio::Behaviour>,
+	pub kademlia: kad::Behaviour<kad::store::MemoryStore>,
+	pub identify: identify::Behaviour,
+	pub ping: ping::Behaviour,
 }
 
-type SwarmEventType = SwarmEvent<P2PBehaviourEvent>;
-
-#[derive(Debug, EnumIter, Display, EnumString)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, Display, EnumString)]
 pub enum TopicEnum {
 	#[strum(serialize = "blockchain")]
 	Blockchain,
@@ -33,180 +45,385 @@ pub enum TopicEnum {
 	TransactionPool,
 }
 
-pub struct P2PConnection {
-	pub keypair: Keypair,
-	pub peer_id: PeerId,
-	pub swarm: Mutex<Swarm<P2PBehaviour>>,
-	pub connected_peers: RwLock<HashMap<PeerId, u32>>,
-	pub event_tx: mpsc::Sender<SwarmEventType>,
+impl TopicEnum {
+	/// Gossipsub score parameters of the topic. Invalid messages weigh far
+	/// more than delivery rewards, so a couple of bad blocks push a peer
+	/// below `PEER_BAN_SCORE`.
+	pub fn score_params(&self) -> TopicScoreParams {
+		let (topic_weight, first_delivery_weight, invalid_delivery_weight) =
+			match self {
+				TopicEnum::Blockchain => (1.0, 1.0, -100.0),
+				TopicEnum::Transaction => (0.5, 0.5, -20.0),
+				TopicEnum::TransactionPool => (0.25, 0.2, -50.0),
+			};
+		TopicScoreParams {
+			topic_weight,
+			time_in_mesh_weight: 0.01,
+			time_in_mesh_quantum: Duration::from_secs(1),
+			time_in_mesh_cap: 3600.0,
+			first_message_deliveries_weight: first_delivery_weight,
+			first_message_deliveries_decay: 0.9,
+			first_message_deliveries_cap: 20.0,
+			// chain and pool messages are rare, quiet meshes are not a fault
+			mesh_message_deliveries_weight: 0.0,
+			mesh_failure_penalty_weight: 0.0,
+			invalid_message_deliveries_weight: invalid_delivery_weight,
+			invalid_message_deliveries_decay: 0.9,
+			..TopicScoreParams::default()
+		}
+	}
 }
 
-impl P2PConnection {
-	pub async fn global() -> Arc<P2PConnection> {
-		static INSTANCE: OnceCell<Arc<P2PConnection>> = OnceCell::const_new();
-		INSTANCE
-			.get_or_init(|| async {
-				P2PConnection::new()
-					.await
-					.map(Arc::new)
-					.expect("Failed to init P2P")
-			})
-			.await
-			.clone()
+fn peer_score_params() -> PeerScoreParams {
+	let topics = TopicEnum::iter()
+		.map(|item| {
+			let topic = IdentTopic::new(item.to_string());
+			(topic.hash(), item.score_params())
+		})
+		.collect();
+	PeerScoreParams {
+		topics,
+		topic_score_cap: 50.0,
+		// local nodes and containers often share one address
+		ip_colocation_factor_weight: 0.0,
+		..PeerScoreParams::default()
 	}
+}
 
-	async fn new() -> Result<Self, Box<dyn Error>> {
-		// 1. Create identity
-		let keypair = libp2p::identity::Keypair::generate_ed25519();
-		let peer_id = PeerId::from(keypair.public());
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Transports {
+	pub tcp: bool,
+	pub quic: bool,
+	pub websocket: bool,
+}
 
-		// 2. Create gossip behavior
-		let mut gossip_sub: Behaviour<IdentityTransform> = Behaviour::new(
-			MessageAuthenticity::Signed(keypair.clone()),
-			Config::default(),
-		)?;
+impl Default for Transports {
+	fn default() -> Self {
+		Self { tcp: true, quic: true, websocket: false }
+	}
+}
 
-		// 3. Create topic and subscribe.
-		for item in TopicEnum::iter() {
-			let topic = IdentTopic::new(item.to_string());
-			gossip_sub.subscribe(&topic)?;
+impl Transports {
+	/// Listens on a random port of every interface for each enabled
+	/// transport.
+	pub fn default_listen_addrs(&self) -> Vec<Multiaddr> {
+		let mut addrs: Vec<&str> = Vec::new();
+		if self.tcp {
+			addrs.push("/ip4/0.0.0.0/tcp/0");
 		}
+		if self.quic {
+			addrs.push("/ip4/0.0.0.0/udp/0/quic-v1");
+		}
+		if self.websocket {
+			addrs.push("/ip4/0.0.0.0/tcp/0/ws");
+		}
+		addrs
+			.into_iter()
+			.map(|addr| addr.parse().expect("Valid listen multiaddr."))
+			.collect()
+	}
 
-		let mdns =
-			mdns::tokio::Behaviour::new(mdns::Config::default(), peer_id)?;
-		println!(
-			"* MDNS discovery enabled - will automatically find local peers"
-		);
+	/// Whether one of the enabled transports can listen on or dial `addr`.
+	pub fn supports(&self, addr: &Multiaddr) -> bool {
+		let mut quic = false;
+		let mut websocket = false;
+		let mut tcp = false;
+		for protocol in addr.iter() {
+			match protocol {
+				Protocol::Tcp(_) => tcp = true,
+				Protocol::QuicV1 => quic = true,
+				Protocol::Ws(_) | Protocol::Wss(_) => websocket = true,
+				_ => {}
+			}
+		}
+		if websocket {
+			self.websocket
+		} else if quic {
+			self.quic
+		} else {
+			tcp && self.tcp
+		}
+	}
+
+	/// Combines the enabled transports, tried in the order TCP, QUIC,
+	/// WebSocket. All of them are secured with TLS, TCP based ones are
+	/// multiplexed with yamux. `/dns4` and `/dns6` addresses are resolved
+	/// with the system resolver before dialing.
+	fn build(
+		&self,
+		keypair: &Keypair,
+	) -> Result<Boxed<(PeerId, StreamMuxerBox)>, Box<dyn Error>> {
+		let mut transports: Vec<Boxed<(PeerId, StreamMuxerBox)>> = Vec::new();
+		if self.tcp {
+			transports.push(
+				tcp::tokio::Transport::new(tcp::Config::default())
+					.upgrade(upgrade::Version::V1)
+					.authenticate(tls::Config::new(keypair)?)
+					.multiplex(yamux::Config::default())
+					.map(|(peer_id, muxer), _| {
+						(peer_id, StreamMuxerBox::new(muxer))
+					})
+					.boxed(),
+			);
+		}
+		if self.quic {
+			transports.push(
+				quic::tokio::Transport::new(quic::Config::new(keypair))
+					.map(|(peer_id, connection), _| {
+						(peer_id, StreamMuxerBox::new(connection))
+					})
+					.boxed(),
+			);
+		}
+		if self.websocket {
+			transports.push(
+				websocket::Config::new(tcp::tokio::Transport::new(
+					tcp::Config::default(),
+				))
+				.upgrade(upgrade::Version::V1)
+				.authenticate(tls::Config::new(keypair)?)
+				.multiplex(yamux::Config::default())
+				.map(|(peer_id, muxer), _| {
+					(peer_id, StreamMuxerBox::new(muxer))
+				})
+				.boxed(),
+			);
+		}
 
-		// 4. Create communication swarm.
-		let mut swarm = SwarmBuilder::with_existing_identity(keypair.clone())
-			.with_tokio()
-			.with_tcp(
-				tcp::Config::default(),
-				tls::Config::new,
-				yamux::Config::default,
-			)?
-			.with_quic()
-			.with_behaviour(|_key| {
-				Ok(P2PBehaviour { gossipsub: gossip_sub, mdns })
-			})?
-			.with_swarm_config(|cfg| {
-				cfg.with_idle_connection_timeout(Duration::from_secs(60))
+		let combined = transports
+			.into_iter()
+			.reduce(|combined, transport| {
+				combined
+					.or_transport(transport)
+					.map(|either, _| either.into_inner())
+					.boxed()
 			})
-			.build();
+			.ok_or("At least one transport must be enabled.")?;
+		Ok(dns::tokio::Transport::system(combined)?.boxed())
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct P2PConfig {
+	pub keypair: Keypair,
+	pub enable_mdns: bool,
+	pub bootstrap_peers: Vec<Multiaddr>,
+	pub transports: Transports,
+	pub listen_addrs: Vec<Multiaddr>,
+	// announced to peers in addition to the observed addresses, e.g. the
+	// public address of a node behind NAT or in a container
+	pub external_addrs: Vec<Multiaddr>,
+}
 
-		swarm.listen_on("/ip4/0.0.0.0/tcp/0".parse()?)?;
-		swarm.listen_on("/ip4/0.0.0.0/udp/0/quic-v1".parse()?)?;
+impl Default for P2PConfig {
+	fn default() -> Self {
+		let transports = Transports::default();
+		Self {
+			keypair: Keypair::generate_ed25519(),
+			enable_mdns: true,
+			bootstrap_peers: BOOTSTRAP_PEERS
+				.iter()
+				.filter_map(|addr| addr.parse().ok())
+				.collect(),
+			transports,
+			listen_addrs: transports.default_listen_addrs(),
+			external_addrs: Vec::new(),
+		}
+	}
+}
 
-		let (event_tx, _) = mpsc::channel(32);
+/// Builds the swarm of a node from `config`: subscribes to every topic,
+/// listens on the configured addresses and dials the bootstrap peers.
+pub fn build_swarm(
+	config: P2PConfig,
+) -> Result<Swarm<P2PBehaviour>, Box<dyn Error>> {
+	// 1. Load identity
+	let keypair = config.keypair;
+	let peer_id = PeerId::from(keypair.public());
+	println!("* Local peer id: {}", peer_id);
 
-		let connected_peers: HashMap<PeerId, u32> = HashMap::new();
+	// 2. Create gossip behavior. Messages are only forwarded once
+	// p2p_task reports them as valid.
+	let gossip_config = ConfigBuilder::default()
+		.validation_mode(ValidationMode::Strict)
+		.validate_messages()
+		.max_transmit_size(GOSSIP_MAX_TRANSMIT_SIZE)
+		.build()?;
+	let mut gossip_sub: Behaviour<IdentityTransform> = Behaviour::new(
+		MessageAuthenticity::Signed(keypair.clone()),
+		gossip_config,
+	)?;
+	gossip_sub
+		.with_peer_score(peer_score_params(), PeerScoreThresholds::default())?;
+
+	// 3. Create topic and subscribe.
+	for item in TopicEnum::iter() {
+		let topic = IdentTopic::new(item.to_string());
+		gossip_sub.subscribe(&topic)?;
+	}
 
-		Ok(Self {
-			keypair,
+	let mdns = if config.enable_mdns {
+		println!(
+			"* MDNS discovery enabled - will automatically find local peers"
+		);
+		Toggle::from(Some(mdns::tokio::Behaviour::new(
+			mdns::Config::default(),
 			peer_id,
-			swarm: Mutex::new(swarm),
-			connected_peers: RwLock::new(connected_peers),
-			event_tx,
+		)?))
+	} else {
+		println!("* MDNS discovery disabled");
+		Toggle::from(None)
+	};
+
+	let mut kademlia = kad::Behaviour::with_config(
+		peer_id,
+		kad::store::MemoryStore::new(peer_id),
+		kad::Config::new(StreamProtocol::new(KAD_PROTOCOL)),
+	);
+	// answer DHT queries even before our external address is confirmed
+	kademlia.set_mode(Some(kad::Mode::Server));
+
+	let identify = identify::Behaviour::new(identify::Config::new(
+		IDENTIFY_PROTOCOL.to_string(),
+		keypair.public(),
+	));
+
+	// 4. Create communication swarm.
+	let transport = config.transports.build(&keypair)?;
+	let mut swarm = SwarmBuilder::with_existing_identity(keypair.clone())
+		.with_tokio()
+		.with_other_transport(|_key| transport)?
+		.with_behaviour(|_key| {
+			Ok(P2PBehaviour {
+				block_list: allow_block_list::Behaviour::default(),
+				gossipsub: gossip_sub,
+				mdns,
+				kademlia,
+				identify,
+				ping: ping::Behaviour::default(),
+			})
+		})?
+		.with_swarm_config(|cfg| {
+			cfg.with_idle_connection_timeout(Duration::from_secs(60))
 		})
-	}
+		.build();
 
-	pub async fn dial_discovered_peers(&self, list: Vec<(PeerId, Multiaddr)>) {
-		let mut swarm = self.swarm.lock().await;
-		for (peer_id, addr) in list {
-			println!("* Discovered peer: {} at {}", peer_id, addr);
-			// Force connection to discovered peer
-			if let Err(e) = swarm.dial(addr) {
-				eprintln!("Failed to connect to {}: {}", peer_id, e);
-			}
-		}
+	for addr in config.listen_addrs {
+		swarm.listen_on(addr)?;
+	}
+	for addr in config.external_addrs {
+		println!("* External address: {}", addr);
+		swarm.add_external_address(addr);
 	}
 
-	pub async fn publish(
-		&self,
-		topic: &IdentTopic,
-		input: &[u8],
-	) -> Result<MessageId, PublishError> {
-		let topic = topic.clone();
-		let mut swarm = self.swarm.lock().await;
-		swarm
-			.behaviour_mut()
-			.gossipsub
-			.publish(topic, input)
-	}
-
-	pub async fn add_connected_peer(&self, peer_id: &PeerId) {
-		// peer number deduplication
-		let mut connected_peers = self.connected_peers.write().await;
-		let mut swarm = self.swarm.lock().await;
-		let count = connected_peers
-			.entry(*peer_id)
-			.and_modify(|c| *c += 1)
-			.or_insert(1);
-
-		if *count == 1 {
-			println!(
-				"* New peer: {} ({} unique peers)",
-				peer_id,
-				connected_peers.len()
-			);
-		} else {
-			println!(
-				"* Additional connection to: {} ({} total connections)",
-				peer_id, count
-			);
+	for addr in config.bootstrap_peers {
+		if let Some(Protocol::P2p(peer_id)) = addr.iter().last() {
+			swarm
+				.behaviour_mut()
+				.kademlia
+				.add_address(&peer_id, addr.clone());
 		}
-		// IMPORTANT: When we connect to a new peer, make sure gossipsub knows about it
-		// This helps with topic propagation and mesh formation
-		// Add this to all connections, but keep peer itself deduplicated.
-		swarm
-			.behaviour_mut()
-			.gossipsub
-			.add_explicit_peer(&peer_id);
-	}
-
-	pub async fn remove_peer(&self, list: Vec<(PeerId, Multiaddr)>) {
-		let mut connected_peers = self.connected_peers.write().await;
-		for (peer_id, _addr) in list {
-			println!("* Peer removed: {}", peer_id);
-			// connected_peers = connected_peers.saturating_sub(1);
-			connected_peers.remove(&peer_id);
+		println!("* Dialing bootstrap peer: {}", addr);
+		if let Err(e) = swarm.dial(addr.clone()) {
+			eprintln!("Failed to dial bootstrap peer {}: {}", addr, e);
 		}
 	}
+	// fails only while the routing table is empty
+	let _ = swarm.behaviour_mut().kademlia.bootstrap();
 
-	pub async fn closed_connection(&self, peer_id: &PeerId) {
-		let mut connected_peers = self.connected_peers.write().await;
-		// connected_peers = connected_peers.saturating_sub(1);
-		if let Some(count) = connected_peers.get_mut(&peer_id) {
-			*count -= 1;
-			if *count == 0 {
-				connected_peers.remove(&peer_id);
-				println!(
-					"Peer disconnected: {} ({} peers left)",
-					peer_id,
-					connected_peers.len()
-				);
-			} else {
-				println!("Connection closed: {} ({} remain)", peer_id, count);
-			}
-		}
+	Ok(swarm)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use libp2p::core::{
+		Endpoint,
+		transport::{DialOpts, PortUse},
+	};
+
+	#[test]
+	fn peer_score_params_are_valid() {
+		let params = peer_score_params();
+
+		assert_eq!(params.topics.len(), TopicEnum::iter().count());
+		assert!(params.validate().is_ok());
 	}
 
-	pub async fn get_connected_peers_len(&self) -> usize {
-		self.connected_peers.read().await.len()
+	#[test]
+	fn default_config_uses_bootstrap_peers() {
+		let config = P2PConfig::default();
+
+		assert!(config.enable_mdns);
+		assert_eq!(config.bootstrap_peers.len(), BOOTSTRAP_PEERS.len());
+		assert_eq!(config.listen_addrs.len(), 2);
 	}
 
-	pub async fn broadcast_chain(
-		&self,
-		topic: &IdentTopic,
-		blockchain: &Blockchain,
-	) {
-		let blockchain_guard = blockchain;
-		if let Ok(bytes_chain) = Blockchain::to_bytes(&blockchain_guard.chain) {
-			match self.publish(topic, &bytes_chain).await {
-				Ok(_) => println!("Debounced blockchain published!"),
-				Err(e) => println!("Failed to send: {}", e),
-			}
-		}
+	#[test]
+	fn transports_support_matching_addrs() {
+		let transports = Transports { tcp: true, quic: false, websocket: true };
+		let supports = |addr: &str| transports.supports(&addr.parse().unwrap());
+
+		assert!(supports("/ip4/127.0.0.1/tcp/4001"));
+		assert!(supports("/dns4/example.com/tcp/443/wss"));
+		assert!(!supports("/ip4/127.0.0.1/udp/4001/quic-v1"));
+		assert!(!supports("/ip4/127.0.0.1/udp/4001"));
+	}
+
+	#[tokio::test]
+	async fn built_transport_dials_dns_addrs() {
+		let transports =
+			Transports { tcp: true, quic: false, websocket: false };
+		let mut transport = transports
+			.build(&Keypair::generate_ed25519())
+			.unwrap();
+		let opts = DialOpts { role: Endpoint::Dialer, port_use: PortUse::New };
+
+		assert!(
+			transport
+				.dial("/dns4/localhost/tcp/4001".parse().unwrap(), opts)
+				.is_ok()
+		);
+	}
+
+	#[test]
+	fn default_listen_addrs_follow_transports() {
+		let transports = Transports { tcp: false, quic: true, websocket: true };
+		let addrs: Vec<String> = transports
+			.default_listen_addrs()
+			.iter()
+			.map(|addr| addr.to_string())
+			.collect();
+
+		assert_eq!(
+			addrs,
+			vec!["/ip4/0.0.0.0/udp/0/quic-v1", "/ip4/0.0.0.0/tcp/0/ws"]
+		);
+	}
+
+	#[test]
+	fn no_transport_fails_to_build() {
+		let transports =
+			Transports { tcp: false, quic: false, websocket: false };
+
+		assert!(
+			transports
+				.build(&Keypair::generate_ed25519())
+				.is_err()
+		);
+	}
+
+	#[test]
+	fn invalid_blocks_weigh_most() {
+		let block_penalty = TopicEnum::Blockchain.score_params();
+		let txn_penalty = TopicEnum::Transaction.score_params();
+
+		assert!(
+			block_penalty.invalid_message_deliveries_weight
+				* block_penalty.topic_weight
+				< txn_penalty.invalid_message_deliveries_weight
+					* txn_penalty.topic_weight
+		);
 	}
 }
@@ -1,51 +1,64 @@
-/**
- * Testing libp2p communicator singleton class with terminal chat.
- */
-use futures::StreamExt;
-use libp2p::{gossipsub, mdns, swarm::SwarmEvent};
-use std::{str::FromStr, sync::Arc, time::Duration};
-use tokio::io::{self, AsyncBufReadExt};
-use tokio::sync::{RwLock, mpsc};
+use chrono::Utc;
+use libp2p::{PeerId, gossipsub::MessageAcceptance};
+use std::{collections::HashSet, str::FromStr, sync::Arc, time::Duration};
+use tokio::sync::{RwLock, broadcast::error::RecvError, mpsc};
 use tokio::task::JoinHandle;
 use tokio::time::interval;
+use tokio_util::sync::CancellationToken;
 
+use crate::address_book::AddressBook;
+use crate::chain_admission::{apply_chain, check_chain};
 use crate::channels::AppEvent;
+use crate::config::{
+	GOSSIP_MAX_TRANSMIT_SIZE, KAD_RANDOM_WALK_INTERVAL, PEER_BAN_DURATION,
+	PEER_SCORE_CHECK_INTERVAL, REDIAL_CHECK_INTERVAL,
+};
+use crate::network_service::{NetworkError, NetworkEvent, NetworkHandle};
+use crate::peer_bans::{BanList, Misbehavior};
 use crate::traits::BinarySerializable;
 use crate::transaction::Transaction;
 use crate::transaction_pool::TransactionPool;
+use crate::txn_admission::{admit_transaction, admit_transaction_pool};
 use crate::{
-	blockchain::{Blockchain, BlockchainTr},
+	blockchain::{Blockchain, BlockchainTr, ChainIndex},
 	comms_debounce::Debouncer,
 	constants,
-	p2p_mdns_bc_coms::{self, P2PBehaviourEvent, TopicEnum},
+	p2p_mdns_bc_coms::TopicEnum,
 };
 
+// Drops expired bans and applies the current ban list to the swarm.
+async fn sync_bans(network: &NetworkHandle, ban_list: &RwLock<BanList>) {
+	let now = Utc::now().timestamp_millis();
+	let banned: HashSet<PeerId> = {
+		let mut ban_list = ban_list.write().await;
+		ban_list.remove_expired(now);
+		ban_list.active_bans(now).into_keys().collect()
+	};
+	network.sync_bans(banned).await;
+}
+
 // Should have initialization script, and continuous event loop.
 pub fn start_p2p_task(
 	blockchain: Arc<RwLock<Blockchain>>,
 	transaction_pool: Arc<RwLock<TransactionPool>>,
+	ban_list: Arc<RwLock<BanList>>,
+	address_book: Arc<RwLock<AddressBook>>,
+	network: NetworkHandle,
 	mut event_rx: mpsc::UnboundedReceiver<AppEvent>,
+	shutdown: CancellationToken,
 ) -> JoinHandle<()> {
+	// subscribed before spawning so no gossip message goes unvalidated
+	let mut network_events = network.subscribe();
 	tokio::spawn(async move {
-		let connection = p2p_mdns_bc_coms::P2PConnection::global().await;
+		sync_bans(&network, &ban_list).await;
 		tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
 
-		println!("\nType messages to send. 'exit' to quit.");
-		println!("=================\n");
-
-		let mut stdin = io::BufReader::new(io::stdin()).lines();
-
-		let chain_topic = Arc::new(gossipsub::IdentTopic::new(
-			TopicEnum::Blockchain.to_string(),
-		));
-		let txn_topic = Arc::new(gossipsub::IdentTopic::new(
-			TopicEnum::Transaction.to_string(),
-		));
-		let txn_pool_topic = Arc::new(gossipsub::IdentTopic::new(
-			TopicEnum::TransactionPool.to_string(),
-		));
-
 		let mut heartbeat = interval(Duration::from_millis(100));
+		let mut score_check =
+			interval(Duration::from_secs(PEER_SCORE_CHECK_INTERVAL));
+		let mut redial = interval(Duration::from_secs(REDIAL_CHECK_INTERVAL));
+		let mut random_walk =
+			interval(Duration::from_secs(KAD_RANDOM_WALK_INTERVAL));
 		let mut debouncer_brodcast_chain =
 			Debouncer::new(Duration::from_secs(10));
 
@@ -66,124 +79,158 @@ pub fn start_p2p_task(
 
 		loop {
 			tokio::select! {
-				// Send blockchain sync event instead of text arg. Need to do some work, and add
-				line = stdin.next_line() => {
-					if let Ok(Some(input)) = line {
-						let input = input.trim();
-						if input == "exit" {
-							break;
-						}
-						if connection.get_connected_peers_len().await > 0 && !input.is_empty() {
-							match connection.publish(&chain_topic, input.as_bytes()).await {
-								Ok(_) => println!("Message sent!"),
-								Err(e) => eprintln!("Failed to send: {}", e),
-							}
-						} else if !input.is_empty() {
-							println!("No connected peers yet. Waiting for automatic discovery...");
-						}
-					}
-				},
 				event_channel = event_rx.recv() => {
 					match event_channel {
 						Some(AppEvent::BroadcastMessage(message)) => {
 							if message.action == constants::BROADCAST_TXN_POOL {
-								let txn_pool = transaction_pool.read().await;
-								if let Some(transaction) = txn_pool.transaction_map.get(&message.uuid) {
-									if let Ok(encoded_txn) = transaction.to_bytes() {
-										match connection.publish(&txn_topic, &encoded_txn).await {
-											Ok(_) => println!("Transaction published!"),
-											Err(e) => println!("Failed to send: {}", e),
-										}
+								let encoded_txn = transaction_pool
+									.read()
+									.await
+									.transaction_map()
+									.get(&message.uuid)
+									.and_then(|transaction| transaction.to_bytes().ok());
+								if let Some(encoded_txn) = encoded_txn {
+									match network.publish(TopicEnum::Transaction, encoded_txn).await {
+										Ok(_) => println!("Transaction published!"),
+										Err(e) => println!("Failed to send: {}", e),
 									}
 								}
 							}
 							println!("Message {message:?}")
 						}
+						Some(AppEvent::SyncBlockchain) => {
+							publish_chain(&network, &blockchain).await;
+						}
+						Some(AppEvent::SyncBans) => {
+							sync_bans(&network, &ban_list).await;
+						}
+						Some(AppEvent::Dial(addr)) => {
+							println!("* Dialing: {}", addr);
+							network.dial(addr).await;
+						}
 						_ => {
 							continue;
 						}
 					}
 				},
-				event = async {
-					// Only lock swarm long enough to get one event
-					let event = {
-						let mut swarm = connection.swarm.lock().await;
-						swarm.select_next_some().await
-					};
-					event // Lock released before returning from the async block
-				} => {
+				event = network_events.recv() => {
 					match event {
-						SwarmEvent::Behaviour(P2PBehaviourEvent::Gossipsub(gossipsub::Event::Message { message, .. })) => {
+						Ok(NetworkEvent::Message { propagation_source, message_id, message }) => {
 							let topic: &String = &message.topic.to_string();
 
-							if let Ok(topic_enum) = TopicEnum::from_str(&topic) {
-								match topic_enum {
-									TopicEnum::Blockchain => {
-										// chain replacement.
-										if let Ok(new_chain) = Blockchain::from_bytes(&message.data) {
-											blockchain.write().await.replace_chain(new_chain);
+							// validation mode is manual, only accepted messages are forwarded
+							let (acceptance, misbehavior) = match TopicEnum::from_str(topic) {
+								Ok(TopicEnum::Blockchain) => {
+									// chain replacement.
+									match Blockchain::from_bytes(&message.data) {
+										Ok(new_chain) => {
+											// validated without locks, the write locks are only taken to swap the chain in
+											let chain_len = blockchain.read().await.chain.len();
+											let acceptance = match check_chain(chain_len, &new_chain) {
+												MessageAcceptance::Accept => {
+													let mut blockchain_guard = blockchain.write().await;
+													let mut txn_pool = transaction_pool.write().await;
+													apply_chain(&mut blockchain_guard, &mut txn_pool, new_chain)
+												}
+												acceptance => acceptance,
+											};
+											(acceptance, Misbehavior::InvalidBlock)
 										}
-									},
-									TopicEnum::Transaction => {
-										if let Ok(transaction) = Transaction::from_bytes(&message.data) {
+										Err(_) => (MessageAcceptance::Reject, Misbehavior::MalformedMessage),
+									}
+								}
+								Ok(TopicEnum::Transaction) => {
+									match Transaction::from_bytes(&message.data) {
+										Ok(transaction) => {
+											let blockchain_guard = blockchain.read().await;
+											let chain_index = ChainIndex::new(&blockchain_guard.chain);
 											let mut txn_pool = transaction_pool.write().await;
-											txn_pool.set_transaction(transaction);
+											(admit_transaction(&mut txn_pool, &chain_index, transaction), Misbehavior::InvalidTransaction)
 										}
+										Err(_) => (MessageAcceptance::Reject, Misbehavior::MalformedMessage),
 									}
-									TopicEnum::TransactionPool => {
-										if let Ok(incoming_txn_pool) = TransactionPool::from_bytes(&message.data) {
+								}
+								Ok(TopicEnum::TransactionPool) => {
+									match TransactionPool::from_bytes(&message.data) {
+										Ok(incoming_txn_pool) => {
+											let blockchain_guard = blockchain.read().await;
+											let chain_index = ChainIndex::new(&blockchain_guard.chain);
 											let mut txn_pool = transaction_pool.write().await;
-											txn_pool.update_transaction_pool(incoming_txn_pool);
+											(admit_transaction_pool(&mut txn_pool, &chain_index, incoming_txn_pool), Misbehavior::InvalidTransaction)
 										}
+										Err(_) => (MessageAcceptance::Reject, Misbehavior::MalformedMessage),
 									}
 								}
+								Err(_) => (MessageAcceptance::Ignore, Misbehavior::MalformedMessage),
+							};
+							if matches!(acceptance, MessageAcceptance::Reject) {
+								eprintln!("Rejected {} message from {}", topic, propagation_source);
+								let now = Utc::now().timestamp_millis();
+								if ban_list.write().await.record(&propagation_source, misbehavior, now) {
+									sync_bans(&network, &ban_list).await;
+								}
 							}
+							network.report_validation(message_id, propagation_source, acceptance).await;
 						}
-						SwarmEvent::NewListenAddr { address, .. } => {
-							println!("* Listening on: {}", address);
+						Ok(NetworkEvent::DialFailed(peer_id)) => {
+							address_book.write().await.record_failure(&peer_id, Utc::now().timestamp_millis());
 						}
-						SwarmEvent::OutgoingConnectionError { peer_id, error, .. } => {
-							if let Some(peer) = peer_id {
-								eprintln!("Failed to connect to {}: {}", peer, error);
-							} else {
-								eprintln!("Failed to connect: {}", error);
+						Ok(NetworkEvent::Identified { peer_id, listen_addrs }) => {
+							let mut address_book = address_book.write().await;
+							for addr in listen_addrs {
+								address_book.add_address(&peer_id, addr);
 							}
 						}
-						SwarmEvent::Behaviour(P2PBehaviourEvent::Mdns(mdns::Event::Discovered(list))) => {
-							connection.dial_discovered_peers(list).await;
-						}
-						SwarmEvent::Behaviour(P2PBehaviourEvent::Mdns(mdns::Event::Expired(list))) => {
-							connection.remove_peer(list).await;
+						Ok(NetworkEvent::Latency { peer_id, latency }) => {
+							address_book.write().await.set_latency(&peer_id, latency);
 						}
-						SwarmEvent::ConnectionEstablished { peer_id, .. } => {
-							connection.add_connected_peer(&peer_id).await;
+						Ok(NetworkEvent::ConnectionEstablished { peer_id, dialed_addr }) => {
+							{
+								let mut address_book = address_book.write().await;
+								if let Some(addr) = dialed_addr {
+									address_book.add_address(&peer_id, addr);
+								}
+								address_book.connection_established(&peer_id, Utc::now().timestamp_millis());
+							}
 							debouncer_brodcast_chain.on_event();
 						}
-						SwarmEvent::ConnectionClosed { peer_id, .. } => {
-							connection.closed_connection(&peer_id).await;
+						Ok(NetworkEvent::ConnectionClosed(peer_id)) => {
+							address_book.write().await.connection_closed(&peer_id);
+						}
+						Ok(NetworkEvent::NewListenAddr(_)) => {}
+						Err(RecvError::Lagged(skipped)) => {
+							eprintln!("Skipped {} network events", skipped);
 						}
-						_ => {}
+						Err(RecvError::Closed) => break,
 					}
 				},
-				_ = heartbeat.tick() => {} // unblock timed tasks by heartbeat. other continuous option: tokio::task::yield_now().await;
-			}
-			if debouncer_brodcast_chain.check() {
-				let blockchain_quard = blockchain.read().await;
-				if let Ok(bytes_chain) =
-					Blockchain::to_bytes(&blockchain_quard.chain)
-				{
-					match connection
-						.publish(&chain_topic, &bytes_chain)
-						.await
-					{
-						Ok(_) => println!("Debounced blockchain published!"),
-						Err(e) => println!("Failed to send: {}", e),
+				_ = score_check.tick() => {
+					let low_score_peers = network.low_score_peers().await;
+					if !low_score_peers.is_empty() {
+						let now = Utc::now().timestamp_millis();
+						let mut ban_list_guard = ban_list.write().await;
+						for peer_id in low_score_peers {
+							ban_list_guard.ban(&peer_id, PEER_BAN_DURATION, "Gossipsub score too low.", now);
+						}
 					}
+					sync_bans(&network, &ban_list).await;
+				}
+				_ = redial.tick() => {
+					let due = address_book.write().await.take_due(Utc::now().timestamp_millis());
+					network.dial_known_peers(due).await;
 				}
-				let txn_pool = transaction_pool.read().await;
-				if let Ok(bytes_txn_pool) = txn_pool.to_bytes() {
-					match connection
-						.publish(&txn_pool_topic, &bytes_txn_pool)
+				_ = random_walk.tick() => {
+					network.random_walk().await;
+				}
+				_ = heartbeat.tick() => {} // wakes the loop so the debounced broadcast fires on time
+				_ = shutdown.cancelled() => break,
+			}
+			if debouncer_brodcast_chain.check() {
+				publish_chain(&network, &blockchain).await;
+				let bytes_txn_pool = transaction_pool.read().await.to_bytes();
+				if let Ok(bytes_txn_pool) = bytes_txn_pool {
+					match network
+						.publish(TopicEnum::TransactionPool, bytes_txn_pool)
 						.await
 					{
 						Ok(_) => {
@@ -196,5 +243,31 @@ pub fn start_p2p_task(
 				}
 			}
 		}
+		// peers see us leave the topics instead of a dropped connection
+		network.shutdown().await;
 	})
 }
+
+async fn publish_chain(
+	network: &NetworkHandle,
+	blockchain: &RwLock<Blockchain>,
+) {
+	let (chain_len, bytes_chain) = {
+		let blockchain = blockchain.read().await;
+		(blockchain.chain.len(), Blockchain::to_bytes(&blockchain.chain))
+	};
+	if let Ok(bytes_chain) = bytes_chain {
+		match network
+			.publish(TopicEnum::Blockchain, bytes_chain)
+			.await
+		{
+			Ok(_) => println!("Blockchain published!"),
+			// the whole chain travels as one message, past the limit peers
+			// have to catch up from other nodes
+			Err(NetworkError::TooLarge(size)) => eprintln!(
+				"Chain of {chain_len} blocks not published, {size} bytes exceed the gossip limit of {GOSSIP_MAX_TRANSMIT_SIZE} bytes."
+			),
+			Err(e) => println!("Failed to send: {}", e),
+		}
+	}
+}
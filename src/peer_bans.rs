@@ -0,0 +1,291 @@
+use std::{
+	collections::HashMap,
+	error::Error,
+	fs,
+	path::{Path, PathBuf},
+};
+
+use bincode::{Decode, Encode};
+use libp2p::PeerId;
+use serde::Serialize;
+
+use crate::{
+	config::{
+		MISBEHAVIOR_BAN_SCORE, MISBEHAVIOR_DECAY_INTERVAL, PEER_BAN_DURATION,
+	},
+	utils::write_file_atomic,
+	wire::{self, MessageType},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Misbehavior {
+	InvalidBlock,
+	InvalidTransaction,
+	MalformedMessage,
+}
+
+impl Misbehavior {
+	pub fn penalty(&self) -> u32 {
+		match self {
+			Misbehavior::InvalidBlock => 50,
+			Misbehavior::InvalidTransaction => 20,
+			Misbehavior::MalformedMessage => 35,
+		}
+	}
+
+	fn reason(&self) -> &'static str {
+		match self {
+			Misbehavior::InvalidBlock => "Sent invalid blocks.",
+			Misbehavior::InvalidTransaction => "Sent invalid transactions.",
+			Misbehavior::MalformedMessage => "Sent malformed messages.",
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Encode, Decode)]
+pub struct Ban {
+	pub until: i64, // milliseconds
+	pub reason: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Score {
+	points: u32,
+	updated: i64, // milliseconds
+}
+
+impl Score {
+	// points left at `now` after decaying since the last update
+	fn points_at(&self, now: i64) -> u32 {
+		let elapsed = now.saturating_sub(self.updated).max(0);
+		let decayed = elapsed / MISBEHAVIOR_DECAY_INTERVAL;
+		self.points
+			.saturating_sub(u32::try_from(decayed).unwrap_or(u32::MAX))
+	}
+}
+
+/// Misbehavior scores and time limited bans of peers. Bans are written to
+/// `path` on every change so they survive restarts, scores are kept in
+/// memory only and decay by one point every `MISBEHAVIOR_DECAY_INTERVAL`.
+#[derive(Debug, Default)]
+pub struct BanList {
+	bans: HashMap<PeerId, Ban>,
+	scores: HashMap<PeerId, Score>,
+	path: Option<PathBuf>,
+}
+
+impl BanList {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Loads bans stored at `path`. A missing file gives an empty list.
+	pub fn load(
+		path: impl AsRef<Path>,
+	) -> Result<Self, Box<dyn Error + Send + Sync>> {
+		let path = path.as_ref().to_path_buf();
+		let mut ban_list = Self { path: Some(path.clone()), ..Self::default() };
+		if !path.exists() {
+			return Ok(ban_list);
+		}
+
+		let bytes = fs::read(&path)?;
+		let config = bincode::config::standard();
+		let payload = wire::open(MessageType::PeerBans, &bytes)?;
+		let (entries, _): (Vec<(Vec<u8>, Ban)>, usize) =
+			bincode::decode_from_slice(payload, config)?;
+		for (peer_bytes, ban) in entries {
+			let peer_id = PeerId::from_bytes(&peer_bytes)?;
+			ban_list.bans.insert(peer_id, ban);
+		}
+		Ok(ban_list)
+	}
+
+	pub fn save(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+		let Some(path) = &self.path else {
+			return Ok(());
+		};
+		let entries: Vec<(Vec<u8>, Ban)> = self
+			.bans
+			.iter()
+			.map(|(peer_id, ban)| (peer_id.to_bytes(), ban.clone()))
+			.collect();
+		let config = bincode::config::standard();
+		let payload = bincode::encode_to_vec(entries, config)?;
+		let bytes = wire::seal(MessageType::PeerBans, &payload);
+		write_file_atomic(path, &bytes)?;
+		Ok(())
+	}
+
+	/// Adds the misbehavior penalty to the peer score and bans the peer for
+	/// `PEER_BAN_DURATION` once the score reaches `MISBEHAVIOR_BAN_SCORE`.
+	/// Returns true when the peer got banned.
+	pub fn record(
+		&mut self,
+		peer_id: &PeerId,
+		misbehavior: Misbehavior,
+		now: i64,
+	) -> bool {
+		let points = self
+			.score(peer_id, now)
+			.saturating_add(misbehavior.penalty());
+		self.scores
+			.insert(*peer_id, Score { points, updated: now });
+		if points < MISBEHAVIOR_BAN_SCORE {
+			return false;
+		}
+		self.ban(peer_id, PEER_BAN_DURATION, misbehavior.reason(), now);
+		true
+	}
+
+	pub fn score(&self, peer_id: &PeerId, now: i64) -> u32 {
+		self.scores
+			.get(peer_id)
+			.map_or(0, |score| score.points_at(now))
+	}
+
+	pub fn ban(
+		&mut self,
+		peer_id: &PeerId,
+		duration: i64,
+		reason: &str,
+		now: i64,
+	) {
+		self.scores.remove(peer_id);
+		self.bans.insert(
+			*peer_id,
+			Ban {
+				until: now.saturating_add(duration),
+				reason: reason.to_string(),
+			},
+		);
+		self.persist();
+	}
+
+	pub fn unban(&mut self, peer_id: &PeerId) -> bool {
+		self.scores.remove(peer_id);
+		let removed = self.bans.remove(peer_id).is_some();
+		if removed {
+			self.persist();
+		}
+		removed
+	}
+
+	pub fn is_banned(&self, peer_id: &PeerId, now: i64) -> bool {
+		self.bans
+			.get(peer_id)
+			.is_some_and(|ban| ban.until > now)
+	}
+
+	pub fn active_bans(&self, now: i64) -> HashMap<PeerId, Ban> {
+		self.bans
+			.iter()
+			.filter(|(_, ban)| ban.until > now)
+			.map(|(peer_id, ban)| (*peer_id, ban.clone()))
+			.collect()
+	}
+
+	/// Drops bans that ran out, and scores that decayed to zero, and returns
+	/// how many bans were removed.
+	pub fn remove_expired(&mut self, now: i64) -> usize {
+		self.scores
+			.retain(|_, score| score.points_at(now) > 0);
+		let before = self.bans.len();
+		self.bans.retain(|_, ban| ban.until > now);
+		let removed = before - self.bans.len();
+		if removed > 0 {
+			self.persist();
+		}
+		removed
+	}
+
+	fn persist(&self) {
+		if let Err(err) = self.save() {
+			eprintln!("Failed to save peer bans: {}", err);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use pretty_assertions::assert_eq;
+
+	#[test]
+	fn bans_after_repeated_misbehavior() {
+		let mut ban_list = BanList::new();
+		let peer_id = PeerId::random();
+
+		assert_eq!(
+			ban_list.record(&peer_id, Misbehavior::InvalidBlock, 0),
+			false
+		);
+		assert_eq!(ban_list.score(&peer_id, 0), 50);
+		assert_eq!(
+			ban_list.record(&peer_id, Misbehavior::InvalidBlock, 0),
+			true
+		);
+		assert_eq!(ban_list.is_banned(&peer_id, 0), true);
+		assert_eq!(ban_list.score(&peer_id, 0), 0);
+	}
+
+	#[test]
+	fn scores_decay_over_time() {
+		let mut ban_list = BanList::new();
+		let peer_id = PeerId::random();
+		ban_list.record(&peer_id, Misbehavior::InvalidBlock, 0);
+
+		assert_eq!(
+			ban_list.score(&peer_id, 10 * MISBEHAVIOR_DECAY_INTERVAL),
+			40
+		);
+		// once decayed the next invalid block no longer gets the peer banned
+		let later = 50 * MISBEHAVIOR_DECAY_INTERVAL;
+		assert_eq!(ban_list.score(&peer_id, later), 0);
+		assert_eq!(
+			ban_list.record(&peer_id, Misbehavior::InvalidBlock, later),
+			false
+		);
+		assert_eq!(ban_list.score(&peer_id, later), 50);
+
+		ban_list.remove_expired(later + 50 * MISBEHAVIOR_DECAY_INTERVAL);
+		assert_eq!(ban_list.scores.is_empty(), true);
+	}
+
+	#[test]
+	fn bans_expire() {
+		let mut ban_list = BanList::new();
+		let peer_id = PeerId::random();
+		ban_list.ban(&peer_id, 1_000, "Manual ban.", 0);
+
+		assert_eq!(ban_list.is_banned(&peer_id, 999), true);
+		assert_eq!(ban_list.is_banned(&peer_id, 1_000), false);
+		assert_eq!(ban_list.active_bans(1_000).is_empty(), true);
+		assert_eq!(ban_list.remove_expired(1_000), 1);
+	}
+
+	#[test]
+	fn unban_removes_ban() {
+		let mut ban_list = BanList::new();
+		let peer_id = PeerId::random();
+		ban_list.ban(&peer_id, 1_000, "Manual ban.", 0);
+
+		assert_eq!(ban_list.unban(&peer_id), true);
+		assert_eq!(ban_list.unban(&peer_id), false);
+		assert_eq!(ban_list.is_banned(&peer_id, 0), false);
+	}
+
+	#[test]
+	fn bans_persist_across_loads() {
+		let path = std::env::temp_dir()
+			.join(format!("cryptochain-bans-{}.bin", PeerId::random()));
+		let peer_id = PeerId::random();
+
+		let mut ban_list = BanList::load(&path).unwrap();
+		ban_list.ban(&peer_id, 1_000, "Manual ban.", 0);
+		let loaded = BanList::load(&path).unwrap();
+		fs::remove_file(&path).unwrap();
+
+		assert_eq!(loaded.active_bans(0), ban_list.active_bans(0));
+	}
+}
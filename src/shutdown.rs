@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use tokio::task::{JoinHandle, JoinSet};
+use tokio_util::sync::CancellationToken;
+
+use crate::config::SHUTDOWN_TIMEOUT;
+
+/// Coordinates the shutdown of the node tasks. Tasks get a clone of the
+/// token and stop once it is cancelled, which happens on Ctrl-C or SIGTERM,
+/// when anyone cancels the token or when a tracked task exits on its own.
+#[derive(Debug, Default)]
+pub struct Shutdown {
+	token: CancellationToken,
+	tasks: JoinSet<&'static str>,
+}
+
+impl Shutdown {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn token(&self) -> CancellationToken {
+		self.token.clone()
+	}
+
+	/// Waits for `handle` during shutdown. A task exiting before the token
+	/// is cancelled stops the node.
+	pub fn track(&mut self, name: &'static str, handle: JoinHandle<()>) {
+		self.tasks.spawn(async move {
+			if let Err(err) = handle.await {
+				eprintln!("The {name} task failed: {err}");
+			}
+			name
+		});
+	}
+
+	/// Runs until shutdown is requested, cancels the token and gives the
+	/// tracked tasks `SHUTDOWN_TIMEOUT` to finish. Fails when a task exited
+	/// on its own or did not stop in time.
+	pub async fn wait(self) -> Result<(), String> {
+		self.wait_for(termination_signal()).await
+	}
+
+	async fn wait_for(
+		mut self,
+		signal: impl Future<Output = ()>,
+	) -> Result<(), String> {
+		let exited = tokio::select! {
+			_ = signal => None,
+			_ = self.token.cancelled() => None,
+			Some(Ok(name)) = self.tasks.join_next() => Some(name),
+		};
+		println!("Shutting down...");
+		self.token.cancel();
+
+		let timeout = Duration::from_secs(SHUTDOWN_TIMEOUT);
+		let stopped = tokio::time::timeout(timeout, async {
+			while self.tasks.join_next().await.is_some() {}
+		})
+		.await;
+		if let Some(name) = exited {
+			return Err(format!("The {name} task stopped unexpectedly."));
+		}
+		stopped.map_err(|_| {
+			format!("Tasks did not stop within {SHUTDOWN_TIMEOUT} seconds.")
+		})
+	}
+}
+
+/// Resolves on Ctrl-C or, on unix, SIGTERM.
+pub async fn termination_signal() {
+	#[cfg(unix)]
+	{
+		use tokio::signal::unix::{SignalKind, signal};
+		if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+			tokio::select! {
+				_ = tokio::signal::ctrl_c() => {}
+				_ = terminate.recv() => {}
+			}
+			return;
+		}
+	}
+	let _ = tokio::signal::ctrl_c().await;
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use pretty_assertions::assert_eq;
+
+	#[tokio::test]
+	async fn cancelled_token_stops_tasks() {
+		let mut shutdown = Shutdown::new();
+		let token = shutdown.token();
+		shutdown.track(
+			"worker",
+			tokio::spawn(async move { token.cancelled().await }),
+		);
+
+		shutdown.token().cancel();
+
+		assert_eq!(shutdown.wait_for(std::future::pending()).await, Ok(()));
+	}
+
+	#[tokio::test]
+	async fn exited_task_stops_the_others() {
+		let mut shutdown = Shutdown::new();
+		let token = shutdown.token();
+		shutdown.track(
+			"worker",
+			tokio::spawn(async move { token.cancelled().await }),
+		);
+		shutdown.track("crashing", tokio::spawn(async {}));
+		let token = shutdown.token();
+
+		let result = shutdown.wait_for(std::future::pending()).await;
+
+		assert_eq!(
+			result,
+			Err("The crashing task stopped unexpectedly.".to_string())
+		);
+		assert!(token.is_cancelled());
+	}
+}
@@ -1,8 +1,22 @@
+use std::error::Error;
+
+use crate::wire::{self, MessageType};
+
+/// Binary encoding used on the network and on disk. `to_bytes` frames the
+/// payload in a versioned envelope, see `wire::seal`.
 pub trait BinarySerializable: Sized {
-	fn to_bytes(
-		&self,
-	) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>>;
-	fn from_bytes(
-		bytes: &[u8],
-	) -> Result<Self, Box<dyn std::error::Error + Send + Sync>>;
+	const MESSAGE_TYPE: MessageType;
+
+	fn encode_payload(&self) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>>;
+	fn decode_payload(
+		payload: &[u8],
+	) -> Result<Self, Box<dyn Error + Send + Sync>>;
+
+	fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+		Ok(wire::seal(Self::MESSAGE_TYPE, &self.encode_payload()?))
+	}
+
+	fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn Error + Send + Sync>> {
+		Self::decode_payload(wire::open(Self::MESSAGE_TYPE, bytes)?)
+	}
 }
@@ -1,37 +1,216 @@
-use std::collections::HashMap;
-
-use crate::constants::{U32_SIZE, UUID_SIZE};
+use std::collections::BTreeMap;
+
+use crate::address::Address;
+use crate::amount::Amount;
+use crate::config::{CHAIN_ID, REWARD_INPUT_ADDRESS, SIGNING_PAYLOAD_VERSION};
+use crate::constants::{TXN_DATA_PREFIX, U32_SIZE, U64_SIZE, UUID_SIZE};
+use crate::lock_time::LockTime;
+use crate::multisig::MultisigInput;
 use crate::traits::BinarySerializable;
 use crate::txn_input::TransactionInput;
-use crate::utils::output_map_to_bytes;
+use crate::utils::extend_len_prefixed;
 use crate::wallet::Wallet;
-use libp2p::identity::PublicKey;
+use crate::wire::MessageType;
+use chrono::Utc;
 use rand::Rng;
 use serde::Serialize;
-use serde_with::serde_as;
 use uuid::Uuid;
 
-#[serde_as]
 #[derive(Debug, PartialEq, Clone, Serialize, Eq)]
 pub struct Transaction {
 	pub id: Uuid,
-	pub amount: u32,
+	pub amount: Amount,
+	pub fee: Amount,
+	pub lock_time: Option<LockTime>,
 	pub input: TransactionInput,
-	#[serde_as(as = "HashMap<serde_with::hex::Hex, _>")]
-	pub output_map: HashMap<Vec<u8>, u32>,
+	pub output_map: BTreeMap<Address, Amount>,
 }
 
 impl Transaction {
 	pub fn new(
 		sender_wallet: &Wallet,
-		recipient_pk: &Vec<u8>,
-		amount: u32,
-	) -> Self {
+		recipient: &Address,
+		amount: Amount,
+	) -> Result<Self, &'static str> {
+		Self::with_fee(sender_wallet, recipient, amount, Amount::ZERO)
+	}
+
+	pub fn with_fee(
+		sender_wallet: &Wallet,
+		recipient: &Address,
+		amount: Amount,
+		fee: Amount,
+	) -> Result<Self, &'static str> {
 		let id = Self::generate_uuid_v1();
-		let output_map =
-			Transaction::create_output_map(sender_wallet, recipient_pk, amount);
-		let input = TransactionInput::new(sender_wallet, &output_map);
-		Self { id, amount, output_map, input }
+		let output_map = Transaction::create_output_map(
+			sender_wallet,
+			recipient,
+			amount,
+			fee,
+		)?;
+		let input = TransactionInput::new(sender_wallet);
+		let mut transaction =
+			Self { id, amount, fee, lock_time: None, output_map, input };
+		transaction.sign_input(sender_wallet);
+		Ok(transaction)
+	}
+
+	/// Locks the transaction until `lock_time` and signs it again.
+	pub fn with_lock_time(
+		mut self,
+		sender_wallet: &Wallet,
+		lock_time: LockTime,
+	) -> Self {
+		self.lock_time = Some(lock_time);
+		self.sign_input(sender_wallet);
+		self
+	}
+
+	fn sign_input(&mut self, sender_wallet: &Wallet) {
+		self.input = TransactionInput::new(sender_wallet);
+		self.input.signature = sender_wallet
+			.sign(&self.signing_payload())
+			.expect("Failed to generate signature.");
+	}
+
+	pub fn is_lock_expired(
+		&self,
+		block_height: u64,
+		block_timestamp: i64,
+	) -> bool {
+		match &self.lock_time {
+			Some(lock_time) => {
+				lock_time.is_expired(block_height, block_timestamp)
+			}
+			None => true,
+		}
+	}
+
+	/// Unsigned transaction spending from a multisig address. Cosigners add
+	/// their signatures with `add_partial_signature` until the threshold is
+	/// met.
+	pub fn new_multisig(
+		multisig: MultisigInput,
+		balance: Amount,
+		recipient: &Address,
+		amount: Amount,
+		fee: Amount,
+	) -> Result<Self, &'static str> {
+		let sender_address = multisig.address();
+		let output_map = Self::payment_outputs(
+			&sender_address,
+			balance,
+			recipient,
+			amount,
+			fee,
+		)?;
+
+		let input = TransactionInput {
+			timestamp: Utc::now().timestamp_millis(),
+			amount: balance,
+			sender_address,
+			public_key: Vec::new(),
+			signature: Vec::new(),
+			multisig: Some(multisig),
+		};
+
+		Ok(Self {
+			id: Self::generate_uuid_v1(),
+			amount,
+			fee,
+			lock_time: None,
+			input,
+			output_map,
+		})
+	}
+
+	pub fn add_partial_signature(
+		&mut self,
+		public_key: &[u8],
+		signature: Vec<u8>,
+	) -> Result<(), &'static str> {
+		let payload = self.signing_payload();
+		let multisig = self
+			.input
+			.multisig
+			.as_mut()
+			.ok_or("Transaction is not a multisig transaction.")?;
+		if !Wallet::verify_signature(public_key, &payload, &signature) {
+			return Err("Invalid partial signature.");
+		}
+		multisig.add_signature(public_key, signature)
+	}
+
+	pub fn sign_multisig(
+		&mut self,
+		wallet: &Wallet,
+	) -> Result<(), &'static str> {
+		let signature = wallet
+			.sign(&self.signing_payload())
+			.map_err(|_| "Failed to generate signature.")?;
+		self.add_partial_signature(&wallet.public_key, signature)
+	}
+
+	pub fn reward_transaction(miner_address: &Address, reward: Amount) -> Self {
+		let mut output_map: BTreeMap<Address, Amount> = BTreeMap::new();
+		output_map.insert(*miner_address, reward);
+
+		let input = TransactionInput {
+			timestamp: Utc::now().timestamp_millis(),
+			amount: reward,
+			sender_address: REWARD_INPUT_ADDRESS,
+			public_key: Vec::new(),
+			signature: Vec::new(),
+			multisig: None,
+		};
+
+		Self {
+			id: Self::generate_uuid_v1(),
+			amount: reward,
+			fee: Amount::ZERO,
+			lock_time: None,
+			input,
+			output_map,
+		}
+	}
+
+	pub fn is_reward(&self) -> bool {
+		self.input.sender_address == REWARD_INPUT_ADDRESS
+	}
+
+	pub fn is_valid_reward(&self, expected_reward: Amount) -> bool {
+		let output_total =
+			Amount::checked_sum(self.output_map.values().copied());
+		self.is_reward()
+			&& self.output_map.len() == 1
+			&& self.fee.is_zero()
+			&& self.amount == expected_reward
+			&& self.input.amount == expected_reward
+			&& output_total == Ok(expected_reward)
+	}
+
+	pub fn size(&self) -> usize {
+		self.to_bytes()
+			.expect("Transaction failed to encode.")
+			.len()
+	}
+
+	pub fn to_block_data(
+		&self,
+	) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+		Ok(format!("{TXN_DATA_PREFIX}{}", hex::encode(self.to_bytes()?)))
+	}
+
+	/// Returns `None` when the block data entry is not a transaction.
+	pub fn from_block_data(
+		data: &str,
+	) -> Option<Result<Self, Box<dyn std::error::Error + Send + Sync>>> {
+		let encoded = data.strip_prefix(TXN_DATA_PREFIX)?;
+		Some(
+			hex::decode(encoded)
+				.map_err(|e| e.into())
+				.and_then(|bytes| Self::from_bytes(&bytes)),
+		)
 	}
 
 	pub fn generate_uuid_v1() -> Uuid {
@@ -43,47 +222,121 @@ impl Transaction {
 
 	pub fn create_output_map(
 		sender_wallet: &Wallet,
-		recipient_pk: &Vec<u8>,
-		amount: u32,
-	) -> HashMap<Vec<u8>, u32> {
-		let mut output_map: HashMap<Vec<u8>, u32> = HashMap::new();
+		recipient: &Address,
+		amount: Amount,
+		fee: Amount,
+	) -> Result<BTreeMap<Address, Amount>, &'static str> {
+		Self::payment_outputs(
+			&sender_wallet.address(),
+			sender_wallet.balance,
+			recipient,
+			amount,
+			fee,
+		)
+	}
 
-		output_map.insert(recipient_pk.clone(), amount);
-		output_map.insert(
-			sender_wallet.public_key.clone(),
-			sender_wallet.balance - amount,
-		);
+	/// Pays `amount` to `recipient` and returns what is left of `balance`
+	/// after the fee to the sender. A zero change output is left out.
+	fn payment_outputs(
+		sender_address: &Address,
+		balance: Amount,
+		recipient: &Address,
+		amount: Amount,
+		fee: Amount,
+	) -> Result<BTreeMap<Address, Amount>, &'static str> {
+		if amount.is_zero() {
+			return Err("Amount must be positive.");
+		}
+		if recipient == sender_address {
+			return Err("Cannot send to the sender address.");
+		}
+		let change = amount
+			.checked_add(fee)
+			.and_then(|spent| balance.checked_sub(spent))
+			.map_err(|_| "Insufficient balance.")?;
+
+		let mut output_map = BTreeMap::new();
+		output_map.insert(*recipient, amount);
+		if !change.is_zero() {
+			output_map.insert(*sender_address, change);
+		}
+		Ok(output_map)
+	}
 
-		output_map
+	/// Canonical bytes signed by the sender of a transaction.
+	///
+	/// Layout (integers little endian, byte strings prefixed with a u32
+	/// length): payload version (u8), chain id, transaction id (uuid
+	/// `to_bytes_le`), amount (u64), fee (u64), lock time (u8 tag 0 none,
+	/// 1 height u64, 2 timestamp i64), input amount (u64), input timestamp
+	/// (i64), sender address (version + hash), sender public key, output
+	/// count (u32), then every output as recipient address + amount (u64)
+	/// sorted by recipient.
+	pub fn signing_payload(&self) -> Vec<u8> {
+		let mut bytes: Vec<u8> = vec![SIGNING_PAYLOAD_VERSION];
+		extend_len_prefixed(&mut bytes, CHAIN_ID.as_bytes());
+		bytes.extend(self.id.to_bytes_le());
+		bytes.extend(self.amount.units().to_le_bytes());
+		bytes.extend(self.fee.units().to_le_bytes());
+		bytes.extend(LockTime::to_payload_bytes(&self.lock_time));
+		bytes.extend(self.input.amount.units().to_le_bytes());
+		bytes.extend(self.input.timestamp.to_le_bytes());
+		bytes.extend(self.input.sender_address.to_bytes());
+		extend_len_prefixed(&mut bytes, &self.input.public_key);
+
+		bytes.extend((self.output_map.len() as u32).to_le_bytes());
+		for (recipient, value) in &self.output_map {
+			bytes.extend(recipient.to_bytes());
+			bytes.extend(value.units().to_le_bytes());
+		}
+		bytes
 	}
 
 	pub fn is_valid(&self) -> bool {
-		let output_total: u32 = self.output_map.values().sum();
+		if self.is_reward() {
+			eprintln!("Reward transaction is only valid as part of a block.");
+			return false;
+		}
 
-		if self.input.amount != output_total {
+		if self
+			.output_map
+			.values()
+			.any(|amount| amount.is_zero())
+		{
+			eprintln!("Transaction has a zero amount output.");
+			return false;
+		}
+
+		let spent = Amount::checked_sum(self.output_map.values().copied())
+			.and_then(|output_total| output_total.checked_add(self.fee));
+		if spent != Ok(self.input.amount) {
 			let amt = self.input.amount;
-			let invalid_address =
-				PublicKey::try_decode_protobuf(&self.input.sender_address)
-					.expect("Failed to decode address protobuf.")
-					.to_peer_id()
-					.to_string();
+			let invalid_address = self.input.sender_address;
 			eprintln!(
-				"Invalid transaction data from address: {invalid_address}, {output_total}, {amt}"
+				"Invalid transaction data from address: {invalid_address}, {spent:?}, {amt}"
 			);
 			return false;
 		}
 
-		let data = output_map_to_bytes(&self.output_map);
-		if !Wallet::verify_signature(
-			&self.input.sender_address,
-			&data,
-			&self.input.signature,
-		) {
-			let invalid_address =
-				PublicKey::try_decode_protobuf(&self.input.sender_address)
-					.expect("Failed to decode address protobuf.")
-					.to_peer_id()
-					.to_string();
+		let data = self.signing_payload();
+		let is_signed = match &self.input.multisig {
+			Some(multisig) => {
+				multisig.check().is_ok()
+					&& multisig.address() == self.input.sender_address
+					&& multisig.is_satisfied(&data)
+			}
+			None => {
+				Address::from_public_key(&self.input.public_key)
+					== self.input.sender_address
+					&& Wallet::verify_signature(
+						&self.input.public_key,
+						&data,
+						&self.input.signature,
+					)
+			}
+		};
+		if !is_signed {
+			let invalid_address = self.input.sender_address;
 			eprintln!(
 				"Invalid transaction signature from address: {invalid_address}"
 			);
@@ -96,48 +349,58 @@ impl Transaction {
 	pub fn update(
 		&mut self,
 		sender_wallet: &Wallet,
-		next_recipient: &Vec<u8>,
-		next_amount: u32,
-	) -> Result<(), &str> {
-		let output_balance = *self
-			.output_map
-			.get(&sender_wallet.public_key)
-			.unwrap();
-
-		if next_amount > output_balance {
-			return Err("Insufficient wallet balance.");
+		next_recipient: &Address,
+		next_amount: Amount,
+	) -> Result<(), &'static str> {
+		let sender_address = sender_wallet.address();
+		if next_amount.is_zero() {
+			return Err("Amount must be positive.");
 		}
-
-		let mut recipient_amount = next_amount;
-		if self.output_map.contains_key(next_recipient) {
-			recipient_amount = *self
-				.output_map
-				.get(next_recipient)
-				.expect("Unable to get amount value.")
-				+ recipient_amount;
+		if *next_recipient == sender_address {
+			return Err("Cannot send to the sender address.");
 		}
-		self.output_map
-			.insert(next_recipient.clone(), recipient_amount);
-
-		self.output_map.insert(
-			sender_wallet.public_key.clone(),
-			output_balance - next_amount,
-		);
+		let recipient_amount = self
+			.output_map
+			.get(next_recipient)
+			.copied()
+			.unwrap_or_default()
+			.checked_add(next_amount)
+			.map_err(|_| "Amount overflow.")?;
+
+		let mut output_map = self.output_map.clone();
+		output_map.insert(*next_recipient, recipient_amount);
+		output_map.remove(&sender_address);
+		// the input is signed again with the current wallet balance, so the
+		// change is worked out from it and not from the pending change
+		let spent = Amount::checked_sum(output_map.values().copied())
+			.and_then(|output_total| output_total.checked_add(self.fee))
+			.map_err(|_| "Amount overflow.")?;
+		let change = sender_wallet
+			.balance
+			.checked_sub(spent)
+			.map_err(|_| "Insufficient wallet balance.")?;
+		if !change.is_zero() {
+			output_map.insert(sender_address, change);
+		}
+		self.output_map = output_map;
 
-		self.input = TransactionInput::new(sender_wallet, &self.output_map);
+		self.sign_input(sender_wallet);
 
 		Ok(())
 	}
 }
 
 impl BinarySerializable for Transaction {
-	fn to_bytes(
+	const MESSAGE_TYPE: MessageType = MessageType::Transaction;
+
+	fn encode_payload(
 		&self,
 	) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
 		let mut bytes: Vec<u8> = Vec::new();
 		let config = bincode::config::standard();
 		bytes.extend(self.id.to_bytes_le());
-		bytes.extend(self.amount.to_le_bytes());
+		bytes.extend(self.amount.units().to_le_bytes());
+		bytes.extend(self.fee.units().to_le_bytes());
 		let input_bytes = bincode::encode_to_vec(&self.input, config)?;
 		bytes.extend((input_bytes.len() as u32).to_le_bytes());
 		bytes.extend(input_bytes);
@@ -145,10 +408,13 @@ impl BinarySerializable for Transaction {
 			bincode::encode_to_vec(&self.output_map, config)?;
 		bytes.extend((output_map_bytes.len() as u32).to_le_bytes());
 		bytes.extend(output_map_bytes);
+		let lock_time_bytes = bincode::encode_to_vec(self.lock_time, config)?;
+		bytes.extend((lock_time_bytes.len() as u32).to_le_bytes());
+		bytes.extend(lock_time_bytes);
 		Ok(bytes)
 	}
 
-	fn from_bytes(
+	fn decode_payload(
 		bytes: &[u8],
 	) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
 		let config = bincode::config::standard();
@@ -163,14 +429,23 @@ impl BinarySerializable for Transaction {
 
 		cursor += UUID_SIZE;
 
-		if bytes.len() < cursor + U32_SIZE {
+		if bytes.len() < cursor + U64_SIZE {
 			return Err("Insufficient bytes for amount.".into());
 		}
-		let amount_bytes: [u8; U32_SIZE] =
-			bytes[cursor..cursor + U32_SIZE].try_into()?;
-		let amount = u32::from_le_bytes(amount_bytes);
+		let amount_bytes: [u8; U64_SIZE] =
+			bytes[cursor..cursor + U64_SIZE].try_into()?;
+		let amount = Amount::new(u64::from_le_bytes(amount_bytes));
 
-		cursor += U32_SIZE;
+		cursor += U64_SIZE;
+
+		if bytes.len() < cursor + U64_SIZE {
+			return Err("Insufficient bytes for fee.".into());
+		}
+		let fee_bytes: [u8; U64_SIZE] =
+			bytes[cursor..cursor + U64_SIZE].try_into()?;
+		let fee = Amount::new(u64::from_le_bytes(fee_bytes));
+
+		cursor += U64_SIZE;
 
 		if bytes.len() < cursor + U32_SIZE {
 			return Err("Insufficient bytes for input size.".into());
@@ -187,7 +462,7 @@ impl BinarySerializable for Transaction {
 		}
 
 		let input_bytes: Vec<u8> =
-			bytes[cursor..cursor + input_size as usize].try_into()?;
+			bytes[cursor..cursor + input_size as usize].into();
 		let (input, _bytes): (TransactionInput, usize) =
 			bincode::decode_from_slice(&input_bytes, config)?;
 
@@ -208,10 +483,32 @@ impl BinarySerializable for Transaction {
 		}
 
 		let output_map_bytes: Vec<u8> =
-			bytes[cursor..cursor + output_map_size as usize].try_into()?;
-		let (output_map, _bytes): (HashMap<Vec<u8>, u32>, usize) =
+			bytes[cursor..cursor + output_map_size as usize].into();
+		let (output_map, _bytes): (BTreeMap<Address, Amount>, usize) =
 			bincode::decode_from_slice(&output_map_bytes, config)?;
 
-		Ok(Self { id, amount, input, output_map })
+		cursor += output_map_size as usize;
+
+		if bytes.len() < cursor + U32_SIZE {
+			return Err("Insufficient bytes for lock time size.".into());
+		}
+
+		let lock_time_size_bytes: [u8; U32_SIZE] =
+			bytes[cursor..cursor + U32_SIZE].try_into()?;
+		let lock_time_size = u32::from_le_bytes(lock_time_size_bytes);
+
+		cursor += U32_SIZE;
+
+		if bytes.len() < cursor + lock_time_size as usize {
+			return Err("Insufficient bytes for lock time.".into());
+		}
+
+		let (lock_time, _bytes): (Option<LockTime>, usize) =
+			bincode::decode_from_slice(
+				&bytes[cursor..cursor + lock_time_size as usize],
+				config,
+			)?;
+
+		Ok(Self { id, amount, fee, lock_time, input, output_map })
 	}
 }
@@ -0,0 +1,385 @@
+use chrono::Utc;
+use std::error::Error;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+	address::Address,
+	amount::Amount,
+	block::Block,
+	blockchain::{Blockchain, ChainIndex},
+	config::{MAX_BLOCK_BYTES, block_subsidy},
+	transaction::Transaction,
+	transaction_pool::TransactionPool,
+};
+
+/// Mines the highest paying pool transactions into a new block together with
+/// the miner reward, then drops the mined transactions from the pool.
+pub fn mine_transactions(
+	blockchain: &mut Blockchain,
+	transaction_pool: &mut TransactionPool,
+	miner_address: &Address,
+) -> Result<Block, Box<dyn Error + Send + Sync>> {
+	mine_transactions_cancellable(
+		blockchain,
+		transaction_pool,
+		miner_address,
+		&CancellationToken::new(),
+	)
+}
+
+/// Same as `mine_transactions`, but gives up before the block is added once
+/// `cancel` is cancelled. The mined transactions then stay in the pool.
+pub fn mine_transactions_cancellable(
+	blockchain: &mut Blockchain,
+	transaction_pool: &mut TransactionPool,
+	miner_address: &Address,
+	cancel: &CancellationToken,
+) -> Result<Block, Box<dyn Error + Send + Sync>> {
+	transaction_pool.expire_transactions(Utc::now().timestamp_millis());
+	let block = block_template(blockchain, transaction_pool, miner_address)?
+		.mine(cancel)
+		.ok_or("Mining cancelled.")?;
+	append_block(blockchain, transaction_pool, block.clone())?;
+	Ok(block)
+}
+
+/// Data of the next block and the tip it builds on. Taken from the chain
+/// and the pool up front, so the proof of work runs without holding either.
+#[derive(Debug, Clone)]
+pub struct BlockTemplate {
+	pub data: Vec<String>,
+	pub last_block: Block,
+}
+
+impl BlockTemplate {
+	/// Runs the proof of work, `None` once `cancel` is cancelled.
+	pub fn mine(self, cancel: &CancellationToken) -> Option<Block> {
+		Block::mine_block_cancellable(self.data, &self.last_block, cancel)
+	}
+}
+
+/// Picks the highest paying pool transactions for the next block and adds
+/// the miner reward.
+pub fn block_template(
+	blockchain: &Blockchain,
+	transaction_pool: &TransactionPool,
+	miner_address: &Address,
+) -> Result<BlockTemplate, Box<dyn Error + Send + Sync>> {
+	// reserve room for the largest possible reward transaction
+	let reward_size =
+		Transaction::reward_transaction(miner_address, Amount::MAX).size();
+	let transactions = transaction_pool.select_transactions(
+		&ChainIndex::new(&blockchain.chain),
+		MAX_BLOCK_BYTES.saturating_sub(reward_size),
+		blockchain.chain.len() as u64,
+		Utc::now().timestamp_millis(),
+	);
+
+	let fees = Blockchain::block_fees(&transactions)?;
+	let subsidy = block_subsidy(
+		blockchain.chain.len() as u64,
+		Blockchain::circulating_supply(&blockchain.chain),
+	);
+	let reward = Transaction::reward_transaction(
+		miner_address,
+		subsidy.checked_add(fees)?,
+	);
+
+	let mut data: Vec<String> = Vec::new();
+	for transaction in transactions
+		.iter()
+		.chain(std::iter::once(&reward))
+	{
+		data.push(transaction.to_block_data()?);
+	}
+
+	let last_block = blockchain
+		.chain
+		.last()
+		.ok_or("Blockchain is empty.")?;
+	Ok(BlockTemplate { data, last_block: last_block.clone() })
+}
+
+/// Appends a block mined from a `BlockTemplate` and drops its transactions,
+/// and those it made stale, from the pool. Fails if the chain moved on while
+/// the block was mined or the block is not valid on the chain.
+pub fn append_block(
+	blockchain: &mut Blockchain,
+	transaction_pool: &mut TransactionPool,
+	block: Block,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+	let last_block = blockchain
+		.chain
+		.last()
+		.ok_or("Blockchain is empty.")?;
+	if block.last_hash != last_block.hash {
+		return Err("Chain tip changed while mining.".into());
+	}
+	let mut chain_index = ChainIndex::new(&blockchain.chain);
+	if !Blockchain::is_valid_next_block(last_block, &block)
+		|| !chain_index
+			.is_valid_block_data(&block, blockchain.chain.len() as u64)
+	{
+		return Err("Mined block is invalid.".into());
+	}
+	chain_index.add_block(&block);
+	blockchain.chain.push(block);
+	transaction_pool.remove_stale_transactions(&chain_index);
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		config::INITIAL_BLOCK_SUBSIDY, lock_time::LockTime, wallet::Wallet,
+	};
+	use libp2p::identity::Keypair;
+	use pretty_assertions::assert_eq;
+
+	// sender wallet is funded by mining the first block
+	fn before_each() -> (Blockchain, TransactionPool, Wallet, Wallet) {
+		let mut blockchain = Blockchain::new();
+		let mut transaction_pool = TransactionPool::new();
+		let miner_wallet = Wallet::new(&Keypair::generate_ed25519());
+		let mut sender_wallet = Wallet::new(&Keypair::generate_ed25519());
+
+		mine_transactions(
+			&mut blockchain,
+			&mut transaction_pool,
+			&sender_wallet.address(),
+		)
+		.unwrap();
+		sender_wallet.balance = Wallet::calculate_balance(
+			&blockchain.chain,
+			&sender_wallet.address(),
+		);
+
+		(blockchain, transaction_pool, miner_wallet, sender_wallet)
+	}
+
+	#[test]
+	fn rewards_block_subsidy() {
+		let (blockchain, _, _, sender_wallet) = before_each();
+
+		assert_eq!(sender_wallet.balance, INITIAL_BLOCK_SUBSIDY);
+		assert_eq!(
+			Blockchain::circulating_supply(&blockchain.chain),
+			INITIAL_BLOCK_SUBSIDY
+		);
+	}
+
+	#[test]
+	fn reward_includes_fees() {
+		let (mut blockchain, mut transaction_pool, miner_wallet, sender_wallet) =
+			before_each();
+		let recipient = Wallet::new(&Keypair::generate_ed25519());
+		transaction_pool
+			.set_transaction(
+				Transaction::with_fee(
+					&sender_wallet,
+					&recipient.address(),
+					Amount::new(20),
+					Amount::new(7),
+				)
+				.unwrap(),
+			)
+			.unwrap();
+
+		let block = mine_transactions(
+			&mut blockchain,
+			&mut transaction_pool,
+			&miner_wallet.address(),
+		)
+		.unwrap();
+
+		let transactions = block.transactions().unwrap();
+		let reward = transactions
+			.iter()
+			.find(|txn| txn.is_reward())
+			.unwrap();
+
+		assert_eq!(transactions.len(), 2);
+		assert_eq!(
+			*reward
+				.output_map
+				.get(&miner_wallet.address())
+				.unwrap(),
+			INITIAL_BLOCK_SUBSIDY
+				.checked_add(Amount::new(7))
+				.unwrap()
+		);
+		assert_eq!(Blockchain::is_valid_chain(&blockchain.chain), true);
+		assert_eq!(
+			Blockchain::circulating_supply(&blockchain.chain),
+			INITIAL_BLOCK_SUBSIDY
+				.checked_add(INITIAL_BLOCK_SUBSIDY)
+				.unwrap()
+		);
+	}
+
+	#[test]
+	fn clears_mined_transactions_from_pool() {
+		let (mut blockchain, mut transaction_pool, miner_wallet, sender_wallet) =
+			before_each();
+		let recipient = Wallet::new(&Keypair::generate_ed25519());
+		transaction_pool
+			.set_transaction(
+				Transaction::new(
+					&sender_wallet,
+					&recipient.address(),
+					Amount::new(20),
+				)
+				.unwrap(),
+			)
+			.unwrap();
+
+		mine_transactions(
+			&mut blockchain,
+			&mut transaction_pool,
+			&miner_wallet.address(),
+		)
+		.unwrap();
+
+		assert_eq!(transaction_pool.transaction_map().is_empty(), true);
+	}
+
+	#[test]
+	fn cancelled_mining_keeps_chain_and_pool() {
+		let (mut blockchain, mut transaction_pool, miner_wallet, sender_wallet) =
+			before_each();
+		let recipient = Wallet::new(&Keypair::generate_ed25519());
+		transaction_pool
+			.set_transaction(
+				Transaction::new(
+					&sender_wallet,
+					&recipient.address(),
+					Amount::new(20),
+				)
+				.unwrap(),
+			)
+			.unwrap();
+		let cancel = CancellationToken::new();
+		cancel.cancel();
+
+		let result = mine_transactions_cancellable(
+			&mut blockchain,
+			&mut transaction_pool,
+			&miner_wallet.address(),
+			&cancel,
+		);
+
+		assert!(result.is_err());
+		assert_eq!(blockchain.chain.len(), 2);
+		assert_eq!(transaction_pool.transaction_map().len(), 1);
+	}
+
+	#[test]
+	fn append_fails_once_chain_tip_moved() {
+		let (mut blockchain, mut transaction_pool, miner_wallet, _) =
+			before_each();
+		let template = block_template(
+			&blockchain,
+			&transaction_pool,
+			&miner_wallet.address(),
+		)
+		.unwrap();
+		mine_transactions(
+			&mut blockchain,
+			&mut transaction_pool,
+			&miner_wallet.address(),
+		)
+		.unwrap();
+		let chain_len = blockchain.chain.len();
+
+		let block = template.mine(&CancellationToken::new()).unwrap();
+		let result =
+			append_block(&mut blockchain, &mut transaction_pool, block);
+
+		assert!(result.is_err());
+		assert_eq!(blockchain.chain.len(), chain_len);
+	}
+
+	#[test]
+	fn append_rejects_invalid_block() {
+		let (mut blockchain, mut transaction_pool, miner_wallet, sender_wallet) =
+			before_each();
+		let recipient = Wallet::new(&Keypair::generate_ed25519());
+		let mut template = block_template(
+			&blockchain,
+			&transaction_pool,
+			&miner_wallet.address(),
+		)
+		.unwrap();
+		let mut unfunded_wallet = sender_wallet.clone();
+		unfunded_wallet.balance = Amount::new(1000);
+		let unfunded = Transaction::new(
+			&unfunded_wallet,
+			&recipient.address(),
+			Amount::new(20),
+		)
+		.unwrap();
+		template
+			.data
+			.insert(0, unfunded.to_block_data().unwrap());
+		let chain_len = blockchain.chain.len();
+
+		let block = template.mine(&CancellationToken::new()).unwrap();
+		let result =
+			append_block(&mut blockchain, &mut transaction_pool, block);
+
+		assert!(result.is_err());
+		assert_eq!(blockchain.chain.len(), chain_len);
+	}
+
+	#[test]
+	fn time_locked_transaction_goes_stale_once_balance_changed() {
+		let (mut blockchain, mut transaction_pool, miner_wallet, sender_wallet) =
+			before_each();
+		let recipient = Wallet::new(&Keypair::generate_ed25519());
+		let unlock_height = blockchain.chain.len() as u64 + 1;
+		let transaction = Transaction::new(
+			&sender_wallet,
+			&recipient.address(),
+			Amount::new(20),
+		)
+		.unwrap()
+		.with_lock_time(&sender_wallet, LockTime::Height(unlock_height));
+		transaction_pool
+			.set_transaction(transaction.clone())
+			.unwrap();
+
+		// the sender is paid while its transaction is still locked
+		mine_transactions(
+			&mut blockchain,
+			&mut transaction_pool,
+			&sender_wallet.address(),
+		)
+		.unwrap();
+		assert_eq!(transaction_pool.transaction_map().is_empty(), true);
+		assert_eq!(transaction_pool.metrics.evicted_stale, 1);
+
+		// a stale copy that reaches the pool again is not mined
+		transaction_pool
+			.set_transaction(transaction.clone())
+			.unwrap();
+		let block = mine_transactions(
+			&mut blockchain,
+			&mut transaction_pool,
+			&miner_wallet.address(),
+		)
+		.unwrap();
+
+		assert_eq!(blockchain.chain.len() as u64, unlock_height + 1);
+		assert_eq!(
+			block
+				.transactions()
+				.unwrap()
+				.iter()
+				.any(|txn| txn.id == transaction.id),
+			false
+		);
+		assert_eq!(Blockchain::is_valid_chain(&blockchain.chain), true);
+		assert_eq!(transaction_pool.transaction_map().is_empty(), true);
+	}
+}
@@ -1,68 +1,415 @@
 use crate::{
+	address::Address,
+	blockchain::ChainIndex,
+	config::{
+		MAX_CLOCK_DRIFT, POOL_MAX_BYTES, POOL_MAX_PER_SENDER,
+		POOL_MAX_TRANSACTIONS, POOL_TRANSACTION_EXPIRY,
+	},
 	constants::{U32_SIZE, UUID_SIZE},
+	lock_time::LockTime,
 	traits::BinarySerializable,
 	transaction::Transaction,
+	utils::write_file_atomic,
+	wire::MessageType,
 };
+use chrono::Utc;
 use serde::Serialize;
-use std::collections::HashMap;
+use std::{
+	cmp::Ordering,
+	collections::{BTreeSet, HashMap, HashSet},
+	error::Error,
+	fs,
+	path::Path,
+};
 use uuid::Uuid;
 
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub struct PoolLimits {
+	pub max_transactions: usize,
+	pub max_bytes: usize,
+	pub max_per_sender: usize,
+	pub expiry: i64, // milliseconds after the input timestamp
+}
+
+impl Default for PoolLimits {
+	fn default() -> Self {
+		Self {
+			max_transactions: POOL_MAX_TRANSACTIONS,
+			max_bytes: POOL_MAX_BYTES,
+			max_per_sender: POOL_MAX_PER_SENDER,
+			expiry: POOL_TRANSACTION_EXPIRY,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct PoolMetrics {
+	pub evicted_low_priority: u64,
+	pub evicted_expired: u64,
+	pub evicted_stale: u64,
+	pub rejected_full: u64,
+	pub rejected_sender_limit: u64,
+	pub rejected_expired: u64,
+	pub rejected_future: u64,
+}
+
 #[derive(Debug, Clone, Serialize, PartialEq, Eq)]
 pub struct TransactionPool {
-	pub transaction_map: HashMap<Uuid, Transaction>,
+	transaction_map: HashMap<Uuid, Transaction>,
+	pub limits: PoolLimits,
+	pub metrics: PoolMetrics,
+	// encoded size and priority of every pooled transaction, kept in step
+	// with `transaction_map` so admission does not re-encode the pool
+	#[serde(skip)]
+	sizes: HashMap<Uuid, usize>,
+	#[serde(skip)]
+	priorities: BTreeSet<Priority>,
+	#[serde(skip)]
+	total_bytes: usize,
 }
 
 impl TransactionPool {
 	pub fn new() -> Self {
-		Self { transaction_map: HashMap::new() }
+		Self::with_limits(PoolLimits::default())
 	}
 
-	pub fn set_transaction(&mut self, transaction: Transaction) {
+	pub fn with_limits(limits: PoolLimits) -> Self {
+		Self {
+			transaction_map: HashMap::new(),
+			limits,
+			metrics: PoolMetrics::default(),
+			sizes: HashMap::new(),
+			priorities: BTreeSet::new(),
+			total_bytes: 0,
+		}
+	}
+
+	/// Builds a pool holding `transaction_map` as is, without the admission
+	/// checks of `set_transaction`, like a pool decoded from bytes.
+	pub fn from_transaction_map(
+		transaction_map: HashMap<Uuid, Transaction>,
+	) -> Self {
+		let mut transaction_pool = Self::new();
+		for (uuid, transaction) in transaction_map {
+			transaction_pool.insert(uuid, transaction);
+		}
+		transaction_pool
+	}
+
+	/// Loads the transactions stored at `path`. A missing file gives an
+	/// empty pool. Transactions are not checked against the chain here.
+	pub fn load(
+		path: impl AsRef<Path>,
+	) -> Result<Self, Box<dyn Error + Send + Sync>> {
+		let path = path.as_ref();
+		if !path.exists() {
+			return Ok(Self::new());
+		}
+		Self::from_bytes(&fs::read(path)?)
+	}
+
+	pub fn save(
+		&self,
+		path: impl AsRef<Path>,
+	) -> Result<(), Box<dyn Error + Send + Sync>> {
+		write_file_atomic(path.as_ref(), &self.to_bytes()?)?;
+		Ok(())
+	}
+
+	pub fn transaction_map(&self) -> &HashMap<Uuid, Transaction> {
+		&self.transaction_map
+	}
+
+	pub fn into_transaction_map(self) -> HashMap<Uuid, Transaction> {
 		self.transaction_map
-			.insert(transaction.id, transaction);
 	}
 
+	/// Adds or replaces a transaction. Stale transactions are dropped first,
+	/// then the lowest priority transactions are evicted to make room. The
+	/// pool is left untouched when the transaction is rejected.
+	pub fn set_transaction(
+		&mut self,
+		transaction: Transaction,
+	) -> Result<(), &'static str> {
+		let now = Utc::now().timestamp_millis();
+		self.expire_transactions(now);
+
+		let previous = self.remove(&transaction.id);
+		let result = self.admit(transaction, now);
+		if result.is_err()
+			&& let Some(previous) = previous
+		{
+			self.insert(previous.id, previous);
+		}
+		result
+	}
+
+	fn admit(
+		&mut self,
+		transaction: Transaction,
+		now: i64,
+	) -> Result<(), &'static str> {
+		if transaction.input.timestamp > now.saturating_add(MAX_CLOCK_DRIFT) {
+			self.metrics.rejected_future += 1;
+			return Err("Transaction timestamp is in the future.");
+		}
+		if self.expires_at(&transaction) <= now {
+			self.metrics.rejected_expired += 1;
+			return Err("Transaction has expired.");
+		}
+
+		let sender = &transaction.input.sender_address;
+		let sender_count = self
+			.transaction_map
+			.values()
+			.filter(|txn| txn.input.sender_address == *sender)
+			.count();
+		if sender_count >= self.limits.max_per_sender {
+			self.metrics.rejected_sender_limit += 1;
+			return Err("Too many pending transactions from sender.");
+		}
+
+		let size = transaction.size();
+		let priority = Priority::new(transaction.id, &transaction, size);
+		let mut evicted: Vec<Transaction> = Vec::new();
+		while self.transaction_map.len() >= self.limits.max_transactions
+			|| self.total_bytes + size > self.limits.max_bytes
+		{
+			let lowest = self
+				.priorities
+				.first()
+				.filter(|lowest| priority > **lowest)
+				.map(|lowest| lowest.id);
+			let Some(id) = lowest else {
+				for txn in evicted {
+					self.insert(txn.id, txn);
+				}
+				self.metrics.rejected_full += 1;
+				return Err("Transaction pool is full.");
+			};
+			if let Some(txn) = self.remove(&id) {
+				evicted.push(txn);
+			}
+		}
+
+		self.metrics.evicted_low_priority += evicted.len() as u64;
+		self.insert(transaction.id, transaction);
+		Ok(())
+	}
+
+	/// Merges transactions sent by a peer, subject to the pool limits.
 	pub fn update_transaction_pool(
 		&mut self,
 		transaction_pool: TransactionPool,
 	) {
 		for (uuid, transaction) in transaction_pool.transaction_map {
 			if !self.transaction_map.contains_key(&uuid) {
-				self.transaction_map.insert(uuid, transaction);
+				let _ = self.set_transaction(transaction);
 			}
 		}
 	}
 
-	pub fn existing_transaction_mut(
+	/// Drops transactions that outlived `limits.expiry` and returns how many
+	/// were removed.
+	pub fn expire_transactions(&mut self, now: i64) -> usize {
+		let expired: Vec<Uuid> = self
+			.transaction_map
+			.iter()
+			.filter(|(_, txn)| self.expires_at(txn) <= now)
+			.map(|(uuid, _)| *uuid)
+			.collect();
+		for uuid in &expired {
+			self.remove(uuid);
+		}
+		self.metrics.evicted_expired += expired.len() as u64;
+		expired.len()
+	}
+
+	pub fn total_bytes(&self) -> usize {
+		self.total_bytes
+	}
+
+	fn expires_at(&self, transaction: &Transaction) -> i64 {
+		expires_at(transaction, self.limits.expiry)
+	}
+
+	fn insert(&mut self, uuid: Uuid, transaction: Transaction) {
+		self.remove(&uuid);
+		let size = transaction.size();
+		self.sizes.insert(uuid, size);
+		self.priorities
+			.insert(Priority::new(uuid, &transaction, size));
+		self.total_bytes += size;
+		self.transaction_map.insert(uuid, transaction);
+	}
+
+	fn remove(&mut self, uuid: &Uuid) -> Option<Transaction> {
+		let transaction = self.transaction_map.remove(uuid)?;
+		if let Some(size) = self.sizes.remove(uuid) {
+			self.priorities
+				.remove(&Priority::new(*uuid, &transaction, size));
+			self.total_bytes -= size;
+		}
+		Some(transaction)
+	}
+
+	pub fn existing_transaction(
+		&self,
+		input_address: &Address,
+	) -> Option<&Transaction> {
+		self.transaction_map
+			.values()
+			.find(|txn| txn.input.sender_address == *input_address)
+	}
+
+	pub fn valid_transactions(&self) -> Vec<Transaction> {
+		self.transaction_map
+			.values()
+			.filter(|txn| txn.is_valid())
+			.cloned()
+			.collect()
+	}
+
+	/// Picks valid transactions by highest fee per byte until `max_bytes` is
+	/// filled. Transactions that do not fit are skipped in favour of smaller
+	/// ones further down the list. Only one transaction per sender is picked
+	/// as each one spends the full sender balance. Time locked transactions
+	/// stay in the pool until their lock expires for the block being mined,
+	/// transactions past their pool expiry are left out. So are transactions
+	/// already mined or whose input no longer matches the sender balance on
+	/// `chain_index`, they would make the block invalid.
+	pub fn select_transactions(
+		&self,
+		chain_index: &ChainIndex,
+		max_bytes: usize,
+		block_height: u64,
+		block_timestamp: i64,
+	) -> Vec<Transaction> {
+		let mut selected: Vec<Transaction> = Vec::new();
+		let mut senders: HashSet<Address> = HashSet::new();
+		let mut total_bytes: usize = 0;
+		for priority in self.priorities.iter().rev() {
+			let Some(transaction) = self.transaction_map.get(&priority.id)
+			else {
+				continue;
+			};
+			if total_bytes + priority.size > max_bytes
+				|| senders.contains(&transaction.input.sender_address)
+				|| !transaction.is_lock_expired(block_height, block_timestamp)
+				|| self.expires_at(transaction) <= block_timestamp
+				|| !transaction.is_valid()
+				|| !matches_chain(transaction, chain_index)
+			{
+				continue;
+			}
+			senders.insert(transaction.input.sender_address);
+			total_bytes += priority.size;
+			selected.push(transaction.clone());
+		}
+		selected
+	}
+
+	/// Drops transactions that are mined or whose input no longer matches
+	/// the sender balance on `chain_index`, and returns how many were
+	/// removed. Called whenever blocks are added to the chain.
+	pub fn remove_stale_transactions(
 		&mut self,
-		input_address: &Vec<u8>,
-	) -> Option<&mut Transaction> {
-		let opt = self
+		chain_index: &ChainIndex,
+	) -> usize {
+		let stale: Vec<Uuid> = self
 			.transaction_map
-			.iter_mut()
-			.find(|txn| txn.1.input.sender_address == *input_address);
-		match opt {
-			Some((_, transaction)) => Some(transaction),
-			None => None,
+			.iter()
+			.filter(|(_, txn)| !matches_chain(txn, chain_index))
+			.map(|(uuid, _)| *uuid)
+			.collect();
+		for uuid in &stale {
+			self.remove(uuid);
 		}
+		self.metrics.evicted_stale += stale.len() as u64;
+		stale.len()
+	}
+}
+
+fn matches_chain(transaction: &Transaction, chain_index: &ChainIndex) -> bool {
+	!chain_index.is_mined(&transaction.id)
+		&& transaction.input.amount
+			== chain_index.balance(&transaction.input.sender_address)
+}
+
+// A timestamp lock postpones expiry so scheduled payouts are not dropped
+// before they can be mined. The lock counts for at most one more `expiry`,
+// so a far-off lock can not hold pool space indefinitely.
+fn expires_at(transaction: &Transaction, expiry: i64) -> i64 {
+	let timestamp = transaction.input.timestamp;
+	let start = match transaction.lock_time {
+		Some(LockTime::Timestamp(lock_timestamp)) => lock_timestamp
+			.min(timestamp.saturating_add(expiry))
+			.max(timestamp),
+		_ => timestamp,
+	};
+	start.saturating_add(expiry)
+}
+
+// Position of a transaction in the priority index, lowest first. Higher fee
+// per byte wins, older transactions win ties.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Priority {
+	fee: u64,
+	size: usize,
+	timestamp: i64,
+	id: Uuid,
+}
+
+impl Priority {
+	fn new(id: Uuid, transaction: &Transaction, size: usize) -> Self {
+		Self {
+			fee: transaction.fee.units(),
+			size,
+			timestamp: transaction.input.timestamp,
+			id,
+		}
+	}
+}
+
+impl Ord for Priority {
+	fn cmp(&self, other: &Self) -> Ordering {
+		let rate = self.fee as u128 * other.size as u128;
+		let other_rate = other.fee as u128 * self.size as u128;
+		rate.cmp(&other_rate)
+			.then(other.timestamp.cmp(&self.timestamp))
+			.then(self.id.cmp(&other.id))
+	}
+}
+
+impl PartialOrd for Priority {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Default for TransactionPool {
+	fn default() -> Self {
+		Self::new()
 	}
 }
 
 impl BinarySerializable for TransactionPool {
-	fn to_bytes(
+	const MESSAGE_TYPE: MessageType = MessageType::TransactionPool;
+
+	fn encode_payload(
 		&self,
 	) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
 		let mut bytes: Vec<u8> = Vec::new();
 		for (uuid, txn) in &self.transaction_map {
 			bytes.extend(uuid.to_bytes_le());
-			let txn_bytes = txn.to_bytes()?;
+			let txn_bytes = txn.encode_payload()?;
 			bytes.extend((txn_bytes.len() as u32).to_le_bytes());
 			bytes.extend(txn_bytes);
 		}
 		Ok(bytes)
 	}
 
-	fn from_bytes(
+	fn decode_payload(
 		bytes: &[u8],
 	) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
 		let mut transaction_map: HashMap<Uuid, Transaction> = HashMap::new();
@@ -95,7 +442,7 @@ impl BinarySerializable for TransactionPool {
 			if bytes.len() < cursor + txn_size as usize {
 				return Err("Insufficient bytes for transaction".into());
 			}
-			let transaction = Transaction::from_bytes(
+			let transaction = Transaction::decode_payload(
 				&bytes[cursor..cursor + txn_size as usize],
 			)?;
 
@@ -103,9 +450,65 @@ impl BinarySerializable for TransactionPool {
 
 			cursor += txn_size as usize;
 		}
-		let mut transaction_pool = TransactionPool::new();
-		transaction_pool.transaction_map = transaction_map;
-		Ok(transaction_pool)
+		Ok(TransactionPool::from_transaction_map(transaction_map))
+	}
+}
+
+/// Multisig transactions waiting for cosigner signatures. Bounded by the
+/// transaction count and expiry of `PoolLimits`, counted from when the
+/// node created the entry.
+#[derive(Debug, Default)]
+pub struct MultisigPool {
+	transactions: HashMap<Uuid, (Transaction, i64)>,
+	limits: PoolLimits,
+}
+
+impl MultisigPool {
+	pub fn with_limits(limits: PoolLimits) -> Self {
+		Self { transactions: HashMap::new(), limits }
+	}
+
+	pub fn insert(
+		&mut self,
+		transaction: Transaction,
+		now: i64,
+	) -> Result<(), &'static str> {
+		self.expire(now);
+		if !self.transactions.contains_key(&transaction.id)
+			&& self.transactions.len() >= self.limits.max_transactions
+		{
+			return Err("Multisig pool is full.");
+		}
+		self.transactions
+			.insert(transaction.id, (transaction, now));
+		Ok(())
+	}
+
+	pub fn get_mut(&mut self, id: &Uuid, now: i64) -> Option<&mut Transaction> {
+		self.expire(now);
+		self.transactions
+			.get_mut(id)
+			.map(|(transaction, _)| transaction)
+	}
+
+	pub fn remove(&mut self, id: &Uuid) -> Option<Transaction> {
+		self.transactions
+			.remove(id)
+			.map(|(transaction, _)| transaction)
+	}
+
+	pub fn len(&self) -> usize {
+		self.transactions.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.transactions.is_empty()
+	}
+
+	fn expire(&mut self, now: i64) {
+		let expiry = self.limits.expiry;
+		self.transactions
+			.retain(|_, (_, created)| created.saturating_add(expiry) > now);
 	}
 }
 
@@ -114,22 +517,23 @@ mod test_transaction_pool {
 	use libp2p::identity::Keypair;
 
 	use crate::{
-		transaction::Transaction, transaction_pool::TransactionPool,
-		wallet::Wallet,
+		amount::Amount, transaction::Transaction,
+		transaction_pool::TransactionPool, wallet::Wallet,
 	};
 
-	const AMOUNT: u32 = 50;
+	const AMOUNT: Amount = Amount::new(50);
 
 	fn before_each() -> (TransactionPool, Transaction, Wallet) {
 		let transaction_pool = TransactionPool::new();
-		let sender_wallet = Wallet::new(&Keypair::generate_ed25519());
+		let mut sender_wallet = Wallet::new(&Keypair::generate_ed25519());
+		sender_wallet.balance = Amount::new(1000);
 		let recipient_wallet = Wallet::new(&Keypair::generate_ed25519());
-		let amount: u32 = AMOUNT;
 		let transaction = Transaction::new(
 			&sender_wallet,
-			&recipient_wallet.public_key,
-			amount,
-		);
+			&recipient_wallet.address(),
+			AMOUNT,
+		)
+		.unwrap();
 
 		(transaction_pool, transaction, sender_wallet)
 	}
@@ -142,38 +546,535 @@ mod test_transaction_pool {
 		fn add_transaction() {
 			let (mut transaction_pool, transaction, _) = before_each();
 
-			transaction_pool.set_transaction(transaction.clone());
+			transaction_pool
+				.set_transaction(transaction.clone())
+				.unwrap();
 
 			let txn = transaction_pool
-				.transaction_map
+				.transaction_map()
 				.get(&transaction.id)
 				.unwrap();
 			assert_eq!(*txn, transaction);
 		}
 	}
 
-	mod existing_transaction_mut {
+	mod existing_transaction {
 		use super::*;
 		use pretty_assertions::assert_eq;
 
-		fn before_each() -> (TransactionPool, Transaction, Wallet) {
-			let (mut transaction_pool, transaction, sender_wallet) =
-				super::before_each();
-			transaction_pool.set_transaction(transaction.clone());
-			(transaction_pool, transaction, sender_wallet)
-		}
-
 		#[test]
-		fn existing_transaction_mut() {
+		fn finds_transaction_of_sender() {
 			let (mut transaction_pool, transaction, sender_wallet) =
 				before_each();
+			transaction_pool
+				.set_transaction(transaction.clone())
+				.unwrap();
+
 			let txn = transaction_pool
-				.existing_transaction_mut(&sender_wallet.public_key)
+				.existing_transaction(&sender_wallet.address())
 				.expect("Transaction should exist, but got None");
 			assert_eq!(*txn, transaction)
 		}
 	}
 
+	mod select_transactions {
+		use super::*;
+		use crate::{
+			address::Address, block::Block, blockchain::ChainIndex,
+			lock_time::LockTime,
+		};
+		use pretty_assertions::assert_eq;
+		use std::collections::HashMap;
+
+		// chain where each sender holds exactly the input of its transaction
+		fn funded_index<'a>(
+			transactions: impl IntoIterator<Item = &'a Transaction>,
+		) -> ChainIndex {
+			let balances: HashMap<Address, Amount> = transactions
+				.into_iter()
+				.map(|txn| (txn.input.sender_address, txn.input.amount))
+				.collect();
+			let data = balances
+				.iter()
+				.map(|(address, amount)| {
+					Transaction::reward_transaction(address, *amount)
+						.to_block_data()
+						.unwrap()
+				})
+				.collect();
+			ChainIndex::new(&[Block::new(0, vec![], vec![], data, 0, 0)])
+		}
+
+		fn pool_with_fees(fees: &[u64]) -> TransactionPool {
+			let mut transaction_pool = TransactionPool::new();
+			let recipient = Wallet::new(&Keypair::generate_ed25519());
+			for fee in fees {
+				let mut sender = Wallet::new(&Keypair::generate_ed25519());
+				sender.balance = Amount::new(1000);
+				transaction_pool
+					.set_transaction(
+						Transaction::with_fee(
+							&sender,
+							&recipient.address(),
+							AMOUNT,
+							Amount::new(*fee),
+						)
+						.unwrap(),
+					)
+					.unwrap();
+			}
+			transaction_pool
+		}
+
+		#[test]
+		fn orders_by_fee_per_byte() {
+			let transaction_pool = pool_with_fees(&[5, 50, 0, 20]);
+
+			let selected = transaction_pool.select_transactions(
+				&funded_index(transaction_pool.transaction_map().values()),
+				usize::MAX,
+				1,
+				0,
+			);
+			let fees: Vec<u64> = selected
+				.iter()
+				.map(|txn| txn.fee.units())
+				.collect();
+
+			assert_eq!(fees, vec![50, 20, 5, 0]);
+		}
+
+		#[test]
+		fn respects_size_limit() {
+			let transaction_pool = pool_with_fees(&[5, 50, 20]);
+			let txn_size = transaction_pool
+				.transaction_map()
+				.values()
+				.map(|txn| txn.size())
+				.max()
+				.unwrap();
+
+			let selected = transaction_pool.select_transactions(
+				&funded_index(transaction_pool.transaction_map().values()),
+				txn_size * 2,
+				1,
+				0,
+			);
+			let fees: Vec<u64> = selected
+				.iter()
+				.map(|txn| txn.fee.units())
+				.collect();
+
+			assert_eq!(fees, vec![50, 20]);
+		}
+
+		#[test]
+		fn picks_one_transaction_per_sender() {
+			let (mut transaction_pool, transaction, sender_wallet) =
+				super::before_each();
+			let recipient = Wallet::new(&Keypair::generate_ed25519());
+			transaction_pool
+				.set_transaction(transaction)
+				.unwrap();
+			transaction_pool
+				.set_transaction(
+					Transaction::with_fee(
+						&sender_wallet,
+						&recipient.address(),
+						AMOUNT,
+						Amount::new(9),
+					)
+					.unwrap(),
+				)
+				.unwrap();
+
+			let selected = transaction_pool.select_transactions(
+				&funded_index(transaction_pool.transaction_map().values()),
+				usize::MAX,
+				1,
+				0,
+			);
+
+			assert_eq!(selected.len(), 1);
+			assert_eq!(selected[0].fee, Amount::new(9));
+		}
+
+		#[test]
+		fn keeps_time_locked_transactions_until_expired() {
+			let (mut transaction_pool, transaction, sender_wallet) =
+				super::before_each();
+			let transaction =
+				transaction.with_lock_time(&sender_wallet, LockTime::Height(5));
+			transaction_pool
+				.set_transaction(transaction.clone())
+				.unwrap();
+
+			let selected = transaction_pool.select_transactions(
+				&funded_index(transaction_pool.transaction_map().values()),
+				usize::MAX,
+				4,
+				0,
+			);
+			assert_eq!(selected.len(), 0);
+			assert_eq!(transaction_pool.transaction_map().len(), 1);
+
+			let selected = transaction_pool.select_transactions(
+				&funded_index(transaction_pool.transaction_map().values()),
+				usize::MAX,
+				5,
+				0,
+			);
+			assert_eq!(selected, vec![transaction]);
+		}
+
+		#[test]
+		fn skips_transactions_not_matching_chain_balance() {
+			let transaction_pool = pool_with_fees(&[5, 50]);
+			let funded = transaction_pool
+				.transaction_map()
+				.values()
+				.filter(|txn| txn.fee == Amount::new(5));
+
+			let selected = transaction_pool.select_transactions(
+				&funded_index(funded),
+				usize::MAX,
+				1,
+				0,
+			);
+
+			assert_eq!(selected.len(), 1);
+			assert_eq!(selected[0].fee, Amount::new(5));
+		}
+
+		#[test]
+		fn skips_invalid_transactions() {
+			let mut transaction_pool = pool_with_fees(&[5]);
+			let (_, mut transaction, _) = super::before_each();
+			transaction.fee = Amount::new(100);
+			transaction_pool
+				.set_transaction(transaction)
+				.unwrap();
+
+			let selected = transaction_pool.select_transactions(
+				&funded_index(transaction_pool.transaction_map().values()),
+				usize::MAX,
+				1,
+				0,
+			);
+
+			assert_eq!(selected.len(), 1);
+			assert_eq!(selected[0].fee, Amount::new(5));
+		}
+	}
+
+	mod limits {
+		use super::*;
+		use crate::{
+			config::MAX_CLOCK_DRIFT,
+			lock_time::LockTime,
+			transaction_pool::{PoolLimits, PoolMetrics},
+		};
+		use chrono::Utc;
+		use pretty_assertions::assert_eq;
+
+		fn transaction_with_fee(fee: u64) -> Transaction {
+			let mut sender = Wallet::new(&Keypair::generate_ed25519());
+			sender.balance = Amount::new(1000);
+			let recipient = Wallet::new(&Keypair::generate_ed25519());
+			Transaction::with_fee(
+				&sender,
+				&recipient.address(),
+				AMOUNT,
+				Amount::new(fee),
+			)
+			.unwrap()
+		}
+
+		fn pool_with_limits(
+			max_transactions: usize,
+			max_bytes: usize,
+		) -> TransactionPool {
+			TransactionPool::with_limits(PoolLimits {
+				max_transactions,
+				max_bytes,
+				..PoolLimits::default()
+			})
+		}
+
+		#[test]
+		fn evicts_lowest_fee_rate_when_full() {
+			let mut transaction_pool = pool_with_limits(2, usize::MAX);
+			let low = transaction_with_fee(1);
+			let high = transaction_with_fee(10);
+			transaction_pool
+				.set_transaction(low.clone())
+				.unwrap();
+			transaction_pool
+				.set_transaction(high.clone())
+				.unwrap();
+
+			let higher = transaction_with_fee(20);
+			transaction_pool
+				.set_transaction(higher.clone())
+				.unwrap();
+
+			assert_eq!(transaction_pool.transaction_map().len(), 2);
+			assert_eq!(
+				transaction_pool
+					.transaction_map()
+					.contains_key(&low.id),
+				false
+			);
+			assert_eq!(transaction_pool.metrics.evicted_low_priority, 1);
+		}
+
+		#[test]
+		fn rejects_lower_priority_when_full() {
+			let mut transaction_pool = pool_with_limits(1, usize::MAX);
+			let high = transaction_with_fee(10);
+			transaction_pool
+				.set_transaction(high.clone())
+				.unwrap();
+
+			let result =
+				transaction_pool.set_transaction(transaction_with_fee(1));
+
+			assert_eq!(result, Err("Transaction pool is full."));
+			assert_eq!(
+				transaction_pool.transaction_map().get(&high.id),
+				Some(&high)
+			);
+			assert_eq!(transaction_pool.metrics.rejected_full, 1);
+		}
+
+		#[test]
+		fn evicts_by_total_bytes() {
+			let first = transaction_with_fee(1);
+			let second = transaction_with_fee(2);
+			let max_bytes = first.size() + second.size();
+			let mut transaction_pool = pool_with_limits(usize::MAX, max_bytes);
+			transaction_pool
+				.set_transaction(first.clone())
+				.unwrap();
+			transaction_pool
+				.set_transaction(second.clone())
+				.unwrap();
+
+			transaction_pool
+				.set_transaction(transaction_with_fee(3))
+				.unwrap();
+
+			assert_eq!(transaction_pool.transaction_map().len(), 2);
+			assert!(transaction_pool.total_bytes() <= max_bytes);
+			assert_eq!(
+				transaction_pool.total_bytes(),
+				transaction_pool
+					.transaction_map()
+					.values()
+					.map(|txn| txn.size())
+					.sum::<usize>()
+			);
+			assert_eq!(
+				transaction_pool
+					.transaction_map()
+					.contains_key(&first.id),
+				false
+			);
+		}
+
+		#[test]
+		fn rejects_transaction_larger_than_pool() {
+			let transaction = transaction_with_fee(10);
+			let mut transaction_pool =
+				pool_with_limits(usize::MAX, transaction.size() - 1);
+
+			let result = transaction_pool.set_transaction(transaction);
+
+			assert_eq!(result, Err("Transaction pool is full."));
+		}
+
+		#[test]
+		fn limits_transactions_per_sender() {
+			let mut transaction_pool =
+				TransactionPool::with_limits(PoolLimits {
+					max_per_sender: 1,
+					..PoolLimits::default()
+				});
+			let (_, transaction, sender_wallet) = super::before_each();
+			let recipient = Wallet::new(&Keypair::generate_ed25519());
+			transaction_pool
+				.set_transaction(transaction.clone())
+				.unwrap();
+
+			let result = transaction_pool.set_transaction(
+				Transaction::new(&sender_wallet, &recipient.address(), AMOUNT)
+					.unwrap(),
+			);
+
+			assert_eq!(
+				result,
+				Err("Too many pending transactions from sender.")
+			);
+			assert_eq!(transaction_pool.metrics.rejected_sender_limit, 1);
+
+			// replacing the pending transaction is still allowed
+			transaction_pool
+				.set_transaction(transaction)
+				.unwrap();
+		}
+
+		#[test]
+		fn expires_stale_transactions() {
+			let (mut transaction_pool, transaction, _) = super::before_each();
+			transaction_pool
+				.set_transaction(transaction.clone())
+				.unwrap();
+
+			let expired = transaction_pool.expire_transactions(
+				transaction.input.timestamp + transaction_pool.limits.expiry,
+			);
+
+			assert_eq!(expired, 1);
+			assert_eq!(transaction_pool.transaction_map().is_empty(), true);
+			assert_eq!(transaction_pool.metrics.evicted_expired, 1);
+		}
+
+		#[test]
+		fn rejects_expired_transaction() {
+			let (mut transaction_pool, mut transaction, _) =
+				super::before_each();
+			transaction.input.timestamp =
+				Utc::now().timestamp_millis() - transaction_pool.limits.expiry;
+
+			let result = transaction_pool.set_transaction(transaction);
+
+			assert_eq!(result, Err("Transaction has expired."));
+			assert_eq!(
+				transaction_pool.metrics,
+				PoolMetrics { rejected_expired: 1, ..PoolMetrics::default() }
+			);
+		}
+
+		#[test]
+		fn timestamp_lock_postpones_expiry() {
+			let (mut transaction_pool, transaction, sender_wallet) =
+				super::before_each();
+			let lock_timestamp =
+				transaction.input.timestamp + transaction_pool.limits.expiry;
+			let transaction = transaction.with_lock_time(
+				&sender_wallet,
+				LockTime::Timestamp(lock_timestamp),
+			);
+			transaction_pool
+				.set_transaction(transaction)
+				.unwrap();
+
+			assert_eq!(transaction_pool.expire_transactions(lock_timestamp), 0);
+		}
+
+		#[test]
+		fn timestamp_lock_postpones_expiry_by_at_most_one_expiry() {
+			let (mut transaction_pool, transaction, sender_wallet) =
+				super::before_each();
+			let expiry = transaction_pool.limits.expiry;
+			let lock_timestamp = transaction.input.timestamp + 100 * expiry;
+			let transaction = transaction.with_lock_time(
+				&sender_wallet,
+				LockTime::Timestamp(lock_timestamp),
+			);
+			let horizon = transaction.input.timestamp + 2 * expiry;
+			transaction_pool
+				.set_transaction(transaction)
+				.unwrap();
+
+			assert_eq!(transaction_pool.expire_transactions(horizon - 1), 0);
+			assert_eq!(transaction_pool.expire_transactions(horizon), 1);
+		}
+
+		#[test]
+		fn rejects_future_dated_transaction() {
+			let (mut transaction_pool, mut transaction, _) =
+				super::before_each();
+			transaction.input.timestamp =
+				Utc::now().timestamp_millis() + MAX_CLOCK_DRIFT + 60_000;
+
+			let result = transaction_pool.set_transaction(transaction);
+
+			assert_eq!(result, Err("Transaction timestamp is in the future."));
+			assert_eq!(transaction_pool.transaction_map().is_empty(), true);
+			assert_eq!(transaction_pool.metrics.rejected_future, 1);
+		}
+
+		#[test]
+		fn merged_pools_respect_limits() {
+			let mut transaction_pool = pool_with_limits(2, usize::MAX);
+			let mut incoming_pool = TransactionPool::new();
+			for fee in [1, 2, 3, 4] {
+				incoming_pool
+					.set_transaction(transaction_with_fee(fee))
+					.unwrap();
+			}
+
+			transaction_pool.update_transaction_pool(incoming_pool);
+
+			let mut fees: Vec<u64> = transaction_pool
+				.transaction_map()
+				.values()
+				.map(|txn| txn.fee.units())
+				.collect();
+			fees.sort();
+			assert_eq!(fees, vec![3, 4]);
+		}
+	}
+
+	mod multisig_pool {
+		use crate::transaction_pool::{MultisigPool, PoolLimits};
+		use pretty_assertions::assert_eq;
+
+		const NOW: i64 = 1_700_000_000_000;
+
+		fn pool(max_transactions: usize) -> MultisigPool {
+			MultisigPool::with_limits(PoolLimits {
+				max_transactions,
+				..PoolLimits::default()
+			})
+		}
+
+		#[test]
+		fn rejects_transactions_when_full() {
+			let mut multisig_pool = pool(1);
+			let (_, first, _) = super::before_each();
+			let (_, second, _) = super::before_each();
+
+			multisig_pool.insert(first.clone(), NOW).unwrap();
+
+			assert!(multisig_pool.insert(second, NOW).is_err());
+			assert!(multisig_pool.insert(first, NOW).is_ok());
+			assert_eq!(multisig_pool.len(), 1);
+		}
+
+		#[test]
+		fn drops_expired_transactions() {
+			let mut multisig_pool = pool(1);
+			let expiry = PoolLimits::default().expiry;
+			let (_, first, _) = super::before_each();
+			let (_, second, _) = super::before_each();
+			multisig_pool.insert(first.clone(), NOW).unwrap();
+
+			assert!(
+				multisig_pool
+					.get_mut(&first.id, NOW + expiry - 1)
+					.is_some()
+			);
+			assert!(
+				multisig_pool
+					.get_mut(&first.id, NOW + expiry)
+					.is_none()
+			);
+			assert!(multisig_pool.insert(second, NOW + expiry).is_ok());
+		}
+	}
+
 	mod test_byte_encode_decode {
 		use super::*;
 		use crate::traits::BinarySerializable;
@@ -188,5 +1089,21 @@ mod test_transaction_pool {
 
 			assert_eq!(transaction_pool, decoded);
 		}
+
+		#[test]
+		fn pool_persists_across_loads() {
+			let (mut transaction_pool, transaction, _) = super::before_each();
+			transaction_pool
+				.set_transaction(transaction)
+				.unwrap();
+			let path = std::env::temp_dir()
+				.join(format!("cryptochain-pool-{}.bin", uuid::Uuid::new_v4()));
+
+			transaction_pool.save(&path).unwrap();
+			let loaded = TransactionPool::load(&path).unwrap();
+			std::fs::remove_file(&path).unwrap();
+
+			assert_eq!(loaded, transaction_pool);
+		}
 	}
 }
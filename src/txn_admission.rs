@@ -0,0 +1,409 @@
+use std::{error::Error, path::Path};
+
+use libp2p::gossipsub::MessageAcceptance;
+
+use crate::{
+	block::Block, blockchain::ChainIndex, transaction::Transaction,
+	transaction_pool::TransactionPool,
+};
+
+/// Loads the pool stored at `path`, keeping the transactions that are
+/// still admissible on `chain`.
+pub fn load_transaction_pool(
+	path: impl AsRef<Path>,
+	chain: &[Block],
+) -> Result<TransactionPool, Box<dyn Error + Send + Sync>> {
+	let mut transaction_pool = TransactionPool::new();
+	admit_transaction_pool(
+		&mut transaction_pool,
+		&ChainIndex::new(chain),
+		TransactionPool::load(path)?,
+	);
+	Ok(transaction_pool)
+}
+
+/// Checks a transaction received over gossip against the pool and the chain
+/// and adds it to the pool when accepted.
+///
+/// Messages that can never be valid are rejected so the sender gets
+/// penalized. Messages that may be valid on another node (our chain lags
+/// behind, a conflicting spend was seen first, the pool is full) are ignored.
+pub fn admit_transaction(
+	transaction_pool: &mut TransactionPool,
+	chain_index: &ChainIndex,
+	transaction: Transaction,
+) -> MessageAcceptance {
+	match check_transaction(transaction_pool, chain_index, &transaction) {
+		MessageAcceptance::Accept => {
+			match transaction_pool.set_transaction(transaction) {
+				Ok(()) => MessageAcceptance::Accept,
+				Err(_) => MessageAcceptance::Ignore,
+			}
+		}
+		acceptance => acceptance,
+	}
+}
+
+/// Admits every transaction of a pool received over gossip. The whole
+/// message is rejected, and nothing is admitted, if any transaction in it is
+/// invalid.
+pub fn admit_transaction_pool(
+	transaction_pool: &mut TransactionPool,
+	chain_index: &ChainIndex,
+	incoming_pool: TransactionPool,
+) -> MessageAcceptance {
+	let mut admissible: Vec<Transaction> = Vec::new();
+	for (uuid, transaction) in incoming_pool.into_transaction_map() {
+		if uuid != transaction.id {
+			return MessageAcceptance::Reject;
+		}
+		match check_transaction(transaction_pool, chain_index, &transaction) {
+			MessageAcceptance::Reject => return MessageAcceptance::Reject,
+			MessageAcceptance::Accept => admissible.push(transaction),
+			MessageAcceptance::Ignore => {}
+		}
+	}
+
+	let mut acceptance = MessageAcceptance::Ignore;
+	for transaction in admissible {
+		if matches!(
+			admit_transaction(transaction_pool, chain_index, transaction),
+			MessageAcceptance::Accept
+		) {
+			acceptance = MessageAcceptance::Accept;
+		}
+	}
+	acceptance
+}
+
+pub fn check_transaction(
+	transaction_pool: &TransactionPool,
+	chain_index: &ChainIndex,
+	transaction: &Transaction,
+) -> MessageAcceptance {
+	// signature and input/output balance, rewards never travel alone
+	if !transaction.is_valid() {
+		return MessageAcceptance::Reject;
+	}
+
+	if chain_index.is_mined(&transaction.id) {
+		return MessageAcceptance::Ignore;
+	}
+
+	let sender = &transaction.input.sender_address;
+	if transaction.input.amount != chain_index.balance(sender) {
+		return MessageAcceptance::Ignore;
+	}
+
+	if let Some(existing) = transaction_pool
+		.transaction_map()
+		.get(&transaction.id)
+	{
+		// only newer updates of a pending transaction replace it
+		if existing.input.sender_address != *sender
+			|| existing.input.timestamp >= transaction.input.timestamp
+		{
+			return MessageAcceptance::Ignore;
+		}
+	} else if transaction_pool
+		.transaction_map()
+		.values()
+		.any(|txn| txn.input.sender_address == *sender)
+	{
+		// conflicting spend of the same balance, first seen wins
+		return MessageAcceptance::Ignore;
+	}
+
+	MessageAcceptance::Accept
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		address::Address, amount::Amount, blockchain::Blockchain,
+		transaction_miner::mine_transactions, wallet::Wallet,
+	};
+	use libp2p::identity::Keypair;
+	use pretty_assertions::assert_eq;
+
+	// sender wallet is funded by mining the first block
+	fn before_each() -> (Blockchain, TransactionPool, Wallet, Transaction) {
+		let mut blockchain = Blockchain::new();
+		let mut sender_wallet = Wallet::new(&Keypair::generate_ed25519());
+		let recipient_wallet = Wallet::new(&Keypair::generate_ed25519());
+
+		mine_transactions(
+			&mut blockchain,
+			&mut TransactionPool::new(),
+			&sender_wallet.address(),
+		)
+		.unwrap();
+		sender_wallet.balance = Wallet::calculate_balance(
+			&blockchain.chain,
+			&sender_wallet.address(),
+		);
+
+		let transaction = Transaction::new(
+			&sender_wallet,
+			&recipient_wallet.address(),
+			Amount::new(20),
+		)
+		.unwrap();
+		(blockchain, TransactionPool::new(), sender_wallet, transaction)
+	}
+
+	#[test]
+	fn accepts_valid_transaction() {
+		let (blockchain, mut transaction_pool, _, transaction) = before_each();
+
+		let acceptance = admit_transaction(
+			&mut transaction_pool,
+			&ChainIndex::new(&blockchain.chain),
+			transaction.clone(),
+		);
+
+		assert!(matches!(acceptance, MessageAcceptance::Accept));
+		assert_eq!(
+			transaction_pool
+				.transaction_map()
+				.get(&transaction.id),
+			Some(&transaction)
+		);
+	}
+
+	#[test]
+	fn rejects_invalid_signature() {
+		let (blockchain, mut transaction_pool, _, mut transaction) =
+			before_each();
+		transaction.input.signature = vec![0; 64];
+
+		let acceptance = admit_transaction(
+			&mut transaction_pool,
+			&ChainIndex::new(&blockchain.chain),
+			transaction,
+		);
+
+		assert!(matches!(acceptance, MessageAcceptance::Reject));
+		assert_eq!(transaction_pool.transaction_map().is_empty(), true);
+	}
+
+	#[test]
+	fn rejects_unbalanced_outputs() {
+		let (blockchain, mut transaction_pool, _, mut transaction) =
+			before_each();
+		transaction.input.amount = transaction
+			.input
+			.amount
+			.checked_add(Amount::new(1))
+			.unwrap();
+
+		let acceptance = admit_transaction(
+			&mut transaction_pool,
+			&ChainIndex::new(&blockchain.chain),
+			transaction,
+		);
+
+		assert!(matches!(acceptance, MessageAcceptance::Reject));
+	}
+
+	#[test]
+	fn rejects_reward_transaction() {
+		let (blockchain, mut transaction_pool, sender_wallet, _) =
+			before_each();
+		let reward = Transaction::reward_transaction(
+			&sender_wallet.address(),
+			Amount::new(50),
+		);
+
+		let acceptance = admit_transaction(
+			&mut transaction_pool,
+			&ChainIndex::new(&blockchain.chain),
+			reward,
+		);
+
+		assert!(matches!(acceptance, MessageAcceptance::Reject));
+	}
+
+	#[test]
+	fn ignores_balance_not_on_chain() {
+		let (blockchain, mut transaction_pool, _, _) = before_each();
+		let mut unfunded_wallet = Wallet::new(&Keypair::generate_ed25519());
+		unfunded_wallet.balance = Amount::new(1000);
+		let recipient_wallet = Wallet::new(&Keypair::generate_ed25519());
+		let transaction = Transaction::new(
+			&unfunded_wallet,
+			&recipient_wallet.address(),
+			Amount::new(20),
+		)
+		.unwrap();
+
+		let acceptance = admit_transaction(
+			&mut transaction_pool,
+			&ChainIndex::new(&blockchain.chain),
+			transaction,
+		);
+
+		assert!(matches!(acceptance, MessageAcceptance::Ignore));
+		assert_eq!(transaction_pool.transaction_map().is_empty(), true);
+	}
+
+	#[test]
+	fn ignores_conflicting_spend() {
+		let (blockchain, mut transaction_pool, sender_wallet, transaction) =
+			before_each();
+		let recipient_wallet = Wallet::new(&Keypair::generate_ed25519());
+		admit_transaction(
+			&mut transaction_pool,
+			&ChainIndex::new(&blockchain.chain),
+			transaction,
+		);
+
+		let conflicting = Transaction::new(
+			&sender_wallet,
+			&recipient_wallet.address(),
+			Amount::new(10),
+		)
+		.unwrap();
+		let acceptance = admit_transaction(
+			&mut transaction_pool,
+			&ChainIndex::new(&blockchain.chain),
+			conflicting,
+		);
+
+		assert!(matches!(acceptance, MessageAcceptance::Ignore));
+		assert_eq!(transaction_pool.transaction_map().len(), 1);
+	}
+
+	#[test]
+	fn accepts_newer_update_of_pending_transaction() {
+		let (blockchain, mut transaction_pool, sender_wallet, transaction) =
+			before_each();
+		let recipient_wallet = Wallet::new(&Keypair::generate_ed25519());
+		admit_transaction(
+			&mut transaction_pool,
+			&ChainIndex::new(&blockchain.chain),
+			transaction.clone(),
+		);
+
+		// updates are signed with a fresh input timestamp
+		std::thread::sleep(std::time::Duration::from_millis(2));
+		let mut updated = transaction.clone();
+		updated
+			.update(
+				&sender_wallet,
+				&recipient_wallet.address(),
+				Amount::new(10),
+			)
+			.unwrap();
+		let acceptance = admit_transaction(
+			&mut transaction_pool,
+			&ChainIndex::new(&blockchain.chain),
+			updated.clone(),
+		);
+
+		assert!(matches!(acceptance, MessageAcceptance::Accept));
+		assert_eq!(
+			transaction_pool
+				.transaction_map()
+				.get(&transaction.id),
+			Some(&updated)
+		);
+
+		let acceptance = admit_transaction(
+			&mut transaction_pool,
+			&ChainIndex::new(&blockchain.chain),
+			transaction,
+		);
+		assert!(matches!(acceptance, MessageAcceptance::Ignore));
+	}
+
+	#[test]
+	fn ignores_mined_transaction() {
+		let (mut blockchain, mut transaction_pool, _, transaction) =
+			before_each();
+		let mut mining_pool = TransactionPool::new();
+		mining_pool
+			.set_transaction(transaction.clone())
+			.unwrap();
+		let miner = Address::from_public_key(b"miner");
+		mine_transactions(&mut blockchain, &mut mining_pool, &miner).unwrap();
+
+		let acceptance = admit_transaction(
+			&mut transaction_pool,
+			&ChainIndex::new(&blockchain.chain),
+			transaction,
+		);
+
+		assert!(matches!(acceptance, MessageAcceptance::Ignore));
+	}
+
+	#[test]
+	fn rejects_pool_with_invalid_transaction() {
+		let (blockchain, mut transaction_pool, _, transaction) = before_each();
+		let mut invalid = transaction.clone();
+		invalid.id = Transaction::generate_uuid_v1();
+		invalid.amount = invalid
+			.amount
+			.checked_add(Amount::new(1))
+			.unwrap();
+		let mut incoming_pool = TransactionPool::new();
+		incoming_pool
+			.set_transaction(transaction)
+			.unwrap();
+		incoming_pool.set_transaction(invalid).unwrap();
+
+		let acceptance = admit_transaction_pool(
+			&mut transaction_pool,
+			&ChainIndex::new(&blockchain.chain),
+			incoming_pool,
+		);
+
+		assert!(matches!(acceptance, MessageAcceptance::Reject));
+		assert_eq!(transaction_pool.transaction_map().is_empty(), true);
+	}
+
+	#[test]
+	fn accepts_pool_of_valid_transactions() {
+		let (blockchain, mut transaction_pool, _, transaction) = before_each();
+		let mut incoming_pool = TransactionPool::new();
+		incoming_pool
+			.set_transaction(transaction.clone())
+			.unwrap();
+
+		let acceptance = admit_transaction_pool(
+			&mut transaction_pool,
+			&ChainIndex::new(&blockchain.chain),
+			incoming_pool,
+		);
+
+		assert!(matches!(acceptance, MessageAcceptance::Accept));
+		assert_eq!(transaction_pool.transaction_map().len(), 1);
+	}
+
+	#[test]
+	fn chain_index_matches_chain() {
+		let (mut blockchain, _, sender_wallet, transaction) = before_each();
+		let mut mining_pool = TransactionPool::new();
+		mining_pool
+			.set_transaction(transaction.clone())
+			.unwrap();
+		let miner = Address::from_public_key(b"miner");
+		mine_transactions(&mut blockchain, &mut mining_pool, &miner).unwrap();
+
+		let chain_index = ChainIndex::new(&blockchain.chain);
+
+		assert_eq!(chain_index.is_mined(&transaction.id), true);
+		let recipient = transaction
+			.output_map
+			.keys()
+			.find(|address| **address != sender_wallet.address())
+			.unwrap();
+		for address in [&sender_wallet.address(), recipient, &miner] {
+			assert_eq!(
+				chain_index.balance(address),
+				Wallet::calculate_balance(&blockchain.chain, address)
+			);
+		}
+	}
+}
@@ -1,7 +1,9 @@
-use std::collections::HashMap;
+use std::{collections::BTreeMap, fs, io, path::Path};
 
 use sha3::{Digest, Sha3_256};
 
+use crate::{address::Address, amount::Amount};
+
 pub fn cryptohash(
 	data: &[String],
 	last_hash: &str,
@@ -17,12 +19,28 @@ pub fn cryptohash(
 	hasher.finalize().to_vec()
 }
 
-pub fn output_map_to_bytes(output_map: &HashMap<Vec<u8>, u32>) -> Vec<u8> {
+pub fn output_map_to_bytes(output_map: &BTreeMap<Address, Amount>) -> Vec<u8> {
 	let config = bincode::config::standard();
 	bincode::encode_to_vec(output_map, config)
 		.expect("Output bytes failed to encode.")
 }
 
+pub(crate) fn extend_len_prefixed(bytes: &mut Vec<u8>, data: &[u8]) {
+	bytes.extend((data.len() as u32).to_le_bytes());
+	bytes.extend(data);
+}
+
+/// Writes `bytes` next to `path` and renames the file over it, so a crash
+/// never leaves a truncated file behind.
+pub(crate) fn write_file_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+	if let Some(dir) = path.parent() {
+		fs::create_dir_all(dir)?;
+	}
+	let tmp_path = path.with_extension("tmp");
+	fs::write(&tmp_path, bytes)?;
+	fs::rename(&tmp_path, path)
+}
+
 #[cfg(test)]
 mod test {
 	use super::*;
@@ -1,9 +1,11 @@
-use bs58;
 use hex;
+use std::{error::Error, fs, path::Path};
 
-use crate::{config::STARTING_BALANCE, transaction::Transaction};
+use crate::{
+	address::Address, amount::Amount, block::Block, node_key,
+	transaction::Transaction,
+};
 use libp2p::identity::{Keypair, PublicKey, SigningError};
-use sha3::{Digest, Sha3_256};
 
 #[derive(Clone, Debug)]
 pub struct Wallet {
@@ -11,7 +13,7 @@ pub struct Wallet {
 	//to be signed locally before being submitted into the system
 	pub keypair: Keypair,
 	pub public_key: Vec<u8>,
-	pub balance: u32,
+	pub balance: Amount,
 }
 
 impl Wallet {
@@ -19,17 +21,45 @@ impl Wallet {
 		// let priv_key = keypair.
 		let public_key = keypair.public().encode_protobuf();
 
-		let hex_pub_key = hex::encode(public_key.clone());
-
-		println!("Hex pub key: {hex_pub_key}");
-
 		Self {
-			balance: STARTING_BALANCE,
+			balance: Amount::ZERO,
 			public_key,
 			keypair: keypair.clone(), // will need to remove in production
 		}
 	}
 
+	/// Loads the wallet whose key is stored at `path`.
+	pub fn load(
+		path: impl AsRef<Path>,
+	) -> Result<Self, Box<dyn Error + Send + Sync>> {
+		let bytes = fs::read(path)?;
+		Ok(Self::new(&Keypair::from_protobuf_encoding(&bytes)?))
+	}
+
+	/// Loads the wallet stored at `path`, creating it on first use.
+	pub fn load_or_generate(
+		path: impl AsRef<Path>,
+	) -> Result<Self, Box<dyn Error + Send + Sync>> {
+		if path.as_ref().exists() {
+			return Self::load(path);
+		}
+		let wallet = Self::new(&Keypair::generate_ed25519());
+		wallet.save(path)?;
+		Ok(wallet)
+	}
+
+	/// Stores the wallet key at `path`, readable by the owner only.
+	pub fn save(
+		&self,
+		path: impl AsRef<Path>,
+	) -> Result<(), Box<dyn Error + Send + Sync>> {
+		node_key::save(path.as_ref(), &self.keypair)
+	}
+
+	pub fn address(&self) -> Address {
+		Address::from_public_key(&self.public_key)
+	}
+
 	// need to think more about this. Has to get wallet balance based on value stored in ledger.
 	// pub fn from_private_key(hex_private: &str) -> Result<(), Box<dyn Error>> {
 	// 	let bytes = hex::decode(hex_private)?;
@@ -39,13 +69,35 @@ impl Wallet {
 	// 	Ok(())
 	// }
 
-	pub fn derive_address(keypair: &Keypair) -> String {
-		let public_key = keypair.public();
-		let pubkey_bytes = public_key.encode_protobuf();
-		let mut hasher = Sha3_256::new();
-		hasher.update(&pubkey_bytes);
-		let hash = hasher.finalize();
-		bs58::encode(hash).into_string()
+	/// Balance of `address` as recorded on chain. The newest transaction sent
+	/// by the address carries its remaining balance as change output, so the
+	/// walk stops at the block containing it. The sum saturates instead of
+	/// wrapping on forged chains.
+	pub fn calculate_balance(chain: &[Block], address: &Address) -> Amount {
+		let mut has_conducted_transaction = false;
+		let mut outputs_total = Amount::ZERO;
+
+		for block in chain.iter().rev() {
+			let Ok(transactions) = block.transactions() else {
+				continue;
+			};
+			for transaction in transactions {
+				if transaction.input.sender_address == *address {
+					has_conducted_transaction = true;
+				}
+				if let Some(amount) = transaction.output_map.get(address) {
+					outputs_total = outputs_total.saturating_add(*amount);
+				}
+			}
+			if has_conducted_transaction {
+				break;
+			}
+		}
+		outputs_total
+	}
+
+	pub fn derive_address(keypair: &Keypair) -> Address {
+		Address::from_public_key(&keypair.public().encode_protobuf())
 	}
 
 	pub fn export_pk(keypair: &Keypair) -> String {
@@ -63,13 +115,14 @@ impl Wallet {
 	}
 
 	pub fn verify_signature(
-		public_key: &Vec<u8>,
+		public_key: &[u8],
 		data: &[u8],
 		signature: &[u8],
 	) -> bool {
-		let pk = PublicKey::try_decode_protobuf(public_key)
-			.expect("Failed to decode PK protobuf");
-		pk.verify(data, signature)
+		match PublicKey::try_decode_protobuf(public_key) {
+			Ok(pk) => pk.verify(data, signature),
+			Err(_) => false,
+		}
 	}
 
 	pub fn sign(&self, data: &[u8]) -> Result<Vec<u8>, SigningError> {
@@ -78,26 +131,26 @@ impl Wallet {
 
 	pub fn create_transaction(
 		&self,
-		amount: u32,
-		recipient: &Vec<u8>,
-	) -> Result<Transaction, &str> {
-		if self.balance < amount {
-			return Err("Insufficient ballance.");
-		}
-		Ok(Transaction::new(self, recipient, amount))
+		amount: Amount,
+		fee: Amount,
+		recipient: &Address,
+	) -> Result<Transaction, &'static str> {
+		Transaction::with_fee(self, recipient, amount, fee)
 	}
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::config::ADDRESS_VERSION;
 	use libp2p::identity::Keypair;
 	use pretty_assertions::assert_eq;
+	use sha3::{Digest, Sha3_256};
 
 	#[test]
 	fn test_default_balance() {
 		let wallet = Wallet::new(&Keypair::generate_ed25519());
-		assert_eq!(wallet.balance, STARTING_BALANCE);
+		assert_eq!(wallet.balance, Amount::ZERO);
 	}
 
 	#[test]
@@ -110,12 +163,25 @@ mod tests {
 		assert_eq!(wallet.public_key, pubkey);
 	}
 
-	fn build_address_for_test(public_key: &PublicKey) -> String {
+	fn build_address_for_test(public_key: &PublicKey) -> Address {
 		let pubkey_bytes = public_key.encode_protobuf();
 		let mut hasher = Sha3_256::new();
 		hasher.update(&pubkey_bytes);
-		let hash = hasher.finalize();
-		bs58::encode(hash).into_string()
+		Address::new(ADDRESS_VERSION, hasher.finalize().into())
+	}
+
+	#[test]
+	fn wallet_persists_across_loads() {
+		let path = std::env::temp_dir().join(format!(
+			"cryptochain-wallet-{}.bin",
+			libp2p::PeerId::random()
+		));
+
+		let generated = Wallet::load_or_generate(&path).unwrap();
+		let loaded = Wallet::load_or_generate(&path).unwrap();
+		fs::remove_file(&path).unwrap();
+
+		assert_eq!(loaded.public_key, generated.public_key);
 	}
 
 	#[test]
@@ -172,10 +238,11 @@ mod tests {
 		use super::*;
 		use pretty_assertions::assert_eq;
 
-		fn before_each() -> (u32, Wallet, Wallet) {
-			let amount: u32 = 50;
+		fn before_each() -> (Amount, Wallet, Wallet) {
+			let amount = Amount::new(50);
 			let recipient = Wallet::new(&Keypair::generate_ed25519());
-			let wallet = Wallet::new(&Keypair::generate_ed25519());
+			let mut wallet = Wallet::new(&Keypair::generate_ed25519());
+			wallet.balance = Amount::new(1000);
 
 			(amount, recipient, wallet)
 		}
@@ -184,7 +251,11 @@ mod tests {
 		fn create_transaction_amount_exceeds_balance() {
 			let (_amount, recipient, wallet) = before_each();
 
-			let res = wallet.create_transaction(999_999, &recipient.public_key);
+			let res = wallet.create_transaction(
+				Amount::new(999_999),
+				Amount::ZERO,
+				&recipient.address(),
+			);
 			assert_eq!(res.is_err(), true);
 		}
 
@@ -192,25 +263,145 @@ mod tests {
 		fn match_transaction_input_with_wallet() {
 			let (amount, recipient, wallet) = before_each();
 			let transaction = wallet
-				.create_transaction(amount, &recipient.public_key)
+				.create_transaction(amount, Amount::ZERO, &recipient.address())
 				.unwrap();
 
-			assert_eq!(transaction.input.sender_address, wallet.public_key);
+			assert_eq!(transaction.input.sender_address, wallet.address());
 		}
 
 		#[test]
 		fn output_recipient_amount() {
 			let (amount, recipient, wallet) = before_each();
 			let transaction = wallet
-				.create_transaction(amount, &recipient.public_key)
+				.create_transaction(amount, Amount::ZERO, &recipient.address())
 				.unwrap();
 
 			let txn_recipient_output_map_value = transaction
 				.output_map
-				.get(&recipient.public_key)
+				.get(&recipient.address())
 				.unwrap();
 
 			assert_eq!(*txn_recipient_output_map_value, amount);
 		}
+
+		#[test]
+		fn fee_exceeds_remaining_balance() {
+			let (_amount, recipient, wallet) = before_each();
+
+			let res = wallet.create_transaction(
+				wallet.balance,
+				Amount::new(1),
+				&recipient.address(),
+			);
+			assert_eq!(res.is_err(), true);
+		}
+
+		#[test]
+		fn rejects_zero_amount() {
+			let (_amount, recipient, wallet) = before_each();
+
+			let res = wallet.create_transaction(
+				Amount::ZERO,
+				Amount::new(1),
+				&recipient.address(),
+			);
+			assert_eq!(res.unwrap_err(), "Amount must be positive.");
+		}
+
+		#[test]
+		fn rejects_sending_to_self() {
+			let (amount, _recipient, wallet) = before_each();
+
+			let res = wallet.create_transaction(
+				amount,
+				Amount::ZERO,
+				&wallet.address(),
+			);
+			assert_eq!(res.unwrap_err(), "Cannot send to the sender address.");
+		}
+	}
+
+	mod test_calculate_balance {
+		use super::*;
+		use crate::blockchain::{Blockchain, BlockchainTr};
+		use pretty_assertions::assert_eq;
+
+		fn add_transactions(blockchain: &mut Blockchain, txns: &[Transaction]) {
+			let data = txns
+				.iter()
+				.map(|txn| txn.to_block_data().unwrap())
+				.collect();
+			blockchain.add_block(data);
+		}
+
+		#[test]
+		fn no_outputs_for_wallet() {
+			let blockchain = Blockchain::new();
+			let wallet = Wallet::new(&Keypair::generate_ed25519());
+
+			let balance =
+				Wallet::calculate_balance(&blockchain.chain, &wallet.address());
+			assert_eq!(balance, Amount::ZERO);
+		}
+
+		#[test]
+		fn sums_received_outputs() {
+			let mut blockchain = Blockchain::new();
+			let wallet = Wallet::new(&Keypair::generate_ed25519());
+			add_transactions(
+				&mut blockchain,
+				&[
+					Transaction::reward_transaction(
+						&wallet.address(),
+						Amount::new(30),
+					),
+					Transaction::reward_transaction(
+						&wallet.address(),
+						Amount::new(20),
+					),
+				],
+			);
+
+			let balance =
+				Wallet::calculate_balance(&blockchain.chain, &wallet.address());
+			assert_eq!(balance, Amount::new(50));
+		}
+
+		#[test]
+		fn counts_change_and_later_outputs_after_sending() {
+			let mut blockchain = Blockchain::new();
+			let mut wallet = Wallet::new(&Keypair::generate_ed25519());
+			let recipient = Wallet::new(&Keypair::generate_ed25519());
+			add_transactions(
+				&mut blockchain,
+				&[Transaction::reward_transaction(
+					&wallet.address(),
+					Amount::new(100),
+				)],
+			);
+			wallet.balance =
+				Wallet::calculate_balance(&blockchain.chain, &wallet.address());
+			add_transactions(
+				&mut blockchain,
+				&[wallet
+					.create_transaction(
+						Amount::new(30),
+						Amount::new(5),
+						&recipient.address(),
+					)
+					.unwrap()],
+			);
+			add_transactions(
+				&mut blockchain,
+				&[Transaction::reward_transaction(
+					&wallet.address(),
+					Amount::new(10),
+				)],
+			);
+
+			let balance =
+				Wallet::calculate_balance(&blockchain.chain, &wallet.address());
+			assert_eq!(balance, Amount::new(100 - 30 - 5 + 10));
+		}
 	}
 }
@@ -0,0 +1,165 @@
+use std::{error::Error, fmt};
+
+use sha3::{Digest, Sha3_256};
+
+use crate::config::WIRE_MAGIC;
+
+/// magic, message type, format version, payload length, checksum
+pub const HEADER_SIZE: usize = 4 + 1 + 1 + 4 + 4;
+
+/// Kind of payload in an envelope. Every encoding sent over the network or
+/// written to disk is framed by `seal` with its type and format version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageType {
+	Transaction = 1,
+	TransactionPool = 2,
+	Chain = 3,
+	PeerBans = 4,
+	AddressBook = 5,
+}
+
+impl MessageType {
+	/// Current payload layout of the type. Bump it when the layout changes,
+	/// payloads of other versions are then rejected instead of misparsed.
+	pub const fn format_version(self) -> u8 {
+		match self {
+			Self::Transaction => 3,
+			Self::TransactionPool => 3,
+			Self::Chain => 3,
+			Self::PeerBans => 1,
+			Self::AddressBook => 1,
+		}
+	}
+}
+
+impl TryFrom<u8> for MessageType {
+	type Error = String;
+
+	fn try_from(value: u8) -> Result<Self, Self::Error> {
+		match value {
+			1 => Ok(Self::Transaction),
+			2 => Ok(Self::TransactionPool),
+			3 => Ok(Self::Chain),
+			4 => Ok(Self::PeerBans),
+			5 => Ok(Self::AddressBook),
+			_ => Err(format!("Unknown message type {value}.")),
+		}
+	}
+}
+
+impl fmt::Display for MessageType {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let name = match self {
+			Self::Transaction => "transaction",
+			Self::TransactionPool => "transaction pool",
+			Self::Chain => "chain",
+			Self::PeerBans => "peer bans",
+			Self::AddressBook => "address book",
+		};
+		f.write_str(name)
+	}
+}
+
+/// Frames `payload` with the current format version of `message_type`.
+pub fn seal(message_type: MessageType, payload: &[u8]) -> Vec<u8> {
+	let mut bytes = Vec::with_capacity(HEADER_SIZE + payload.len());
+	bytes.extend(WIRE_MAGIC);
+	bytes.push(message_type as u8);
+	bytes.push(message_type.format_version());
+	bytes.extend((payload.len() as u32).to_le_bytes());
+	bytes.extend(checksum(payload));
+	bytes.extend(payload);
+	bytes
+}
+
+/// Returns the payload of an envelope holding `message_type` in its current
+/// format version.
+pub fn open(
+	message_type: MessageType,
+	bytes: &[u8],
+) -> Result<&[u8], Box<dyn Error + Send + Sync>> {
+	if bytes.len() < HEADER_SIZE {
+		return Err("Insufficient bytes for envelope header.".into());
+	}
+	let (header, payload) = bytes.split_at(HEADER_SIZE);
+	if header[..4] != WIRE_MAGIC {
+		return Err("Missing envelope magic.".into());
+	}
+	let actual_type = MessageType::try_from(header[4])?;
+	if actual_type != message_type {
+		return Err(
+			format!("Expected {message_type}, got {actual_type}.").into()
+		);
+	}
+	let version = header[5];
+	if version != message_type.format_version() {
+		return Err(format!(
+			"Unsupported {message_type} format version {version}, expected {}.",
+			message_type.format_version()
+		)
+		.into());
+	}
+	let length = u32::from_le_bytes(header[6..10].try_into()?) as usize;
+	if payload.len() != length {
+		return Err(format!(
+			"Envelope length {length} does not match payload of {} bytes.",
+			payload.len()
+		)
+		.into());
+	}
+	if header[10..] != checksum(payload) {
+		return Err("Envelope checksum mismatch.".into());
+	}
+	Ok(payload)
+}
+
+fn checksum(payload: &[u8]) -> [u8; 4] {
+	let hash = Sha3_256::digest(payload);
+	[hash[0], hash[1], hash[2], hash[3]]
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use pretty_assertions::assert_eq;
+
+	#[test]
+	fn opens_sealed_payload() {
+		let bytes = seal(MessageType::Chain, b"payload");
+
+		assert_eq!(bytes.len(), HEADER_SIZE + 7);
+		assert_eq!(open(MessageType::Chain, &bytes).unwrap(), b"payload");
+	}
+
+	#[test]
+	fn rejects_unknown_version() {
+		let mut bytes = seal(MessageType::Transaction, b"payload");
+		bytes[5] = 9;
+
+		let err = open(MessageType::Transaction, &bytes).unwrap_err();
+
+		assert_eq!(
+			err.to_string(),
+			"Unsupported transaction format version 9, expected 3."
+		);
+	}
+
+	#[test]
+	fn rejects_other_message_types() {
+		let bytes = seal(MessageType::TransactionPool, b"payload");
+
+		assert!(open(MessageType::Transaction, &bytes).is_err());
+	}
+
+	#[test]
+	fn rejects_damaged_envelopes() {
+		let bytes = seal(MessageType::Chain, b"payload");
+		let mut flipped = bytes.clone();
+		*flipped.last_mut().unwrap() ^= 1;
+
+		assert!(open(MessageType::Chain, &flipped).is_err());
+		assert!(open(MessageType::Chain, &bytes[..bytes.len() - 1]).is_err());
+		assert!(open(MessageType::Chain, &bytes[1..]).is_err());
+		assert!(open(MessageType::Chain, b"").is_err());
+	}
+}
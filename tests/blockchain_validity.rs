@@ -16,7 +16,12 @@ mod is_valid_chain {
 }
 
 mod chain_starts_with_genesis_block {
-	use cryptochain::{blockchain::Blockchain, blockchain::BlockchainTr};
+	use cryptochain::{
+		block::{Block, BlockTr},
+		blockchain::Blockchain,
+		blockchain::BlockchainTr,
+		utils::cryptohash,
+	};
 	use pretty_assertions::assert_eq;
 
 	#[test]
@@ -49,6 +54,40 @@ mod chain_starts_with_genesis_block {
 		assert_eq!(Blockchain::is_valid_chain(&blockchain.chain), false);
 	}
 
+	#[test]
+	fn chain_has_block_without_enough_work() {
+		// is valid chain returns false
+		let mut blockchain = Blockchain::new();
+		blockchain.add_block(vec![String::from("Alpha")]);
+		blockchain.add_block(vec![String::from("Bravo")]);
+
+		let block = blockchain.chain.pop().unwrap();
+		let last_hash = hex::encode(&block.last_hash);
+		let (nonce, hash) = (0..)
+			.map(|nonce| {
+				let hash = cryptohash(
+					&block.data,
+					&last_hash,
+					block.timestamp,
+					nonce,
+					block.difficulty,
+				);
+				(nonce, hash)
+			})
+			.find(|(_, hash)| !Block::is_valid_bit_hash(hash, block.difficulty))
+			.unwrap();
+		blockchain.chain.push(Block::new(
+			block.timestamp,
+			block.last_hash,
+			hash,
+			block.data,
+			nonce,
+			block.difficulty,
+		));
+
+		assert_eq!(Blockchain::is_valid_chain(&blockchain.chain), false);
+	}
+
 	#[test]
 	fn chain_containe_only_valid_blocks() {
 		// is valid chain returns true
@@ -113,3 +152,290 @@ mod chain_replacement {
 		assert_eq!(blockchain.chain, new_chain.chain);
 	}
 }
+
+mod transaction_data {
+	use chrono::Utc;
+	use cryptochain::{
+		amount::Amount,
+		block::{Block, BlockTr},
+		blockchain::{Blockchain, BlockchainTr},
+		config::{MAX_SUPPLY, block_subsidy},
+		lock_time::LockTime,
+		transaction::Transaction,
+		transaction_miner::mine_transactions,
+		transaction_pool::TransactionPool,
+		utils::cryptohash,
+		wallet::Wallet,
+	};
+	use libp2p::identity::Keypair;
+	use pretty_assertions::assert_eq;
+
+	// sender wallet is funded on chain by mining the first block
+	fn before_each() -> (Blockchain, Wallet, Transaction) {
+		let mut blockchain = Blockchain::new();
+		let mut sender_wallet = Wallet::new(&Keypair::generate_ed25519());
+		let recipient_wallet = Wallet::new(&Keypair::generate_ed25519());
+
+		mine_transactions(
+			&mut blockchain,
+			&mut TransactionPool::new(),
+			&sender_wallet.address(),
+		)
+		.unwrap();
+		sender_wallet.balance = Wallet::calculate_balance(
+			&blockchain.chain,
+			&sender_wallet.address(),
+		);
+
+		let transaction = Transaction::with_fee(
+			&sender_wallet,
+			&recipient_wallet.address(),
+			Amount::new(20),
+			Amount::new(4),
+		)
+		.unwrap();
+		(blockchain, sender_wallet, transaction)
+	}
+
+	fn add_transactions(blockchain: &mut Blockchain, txns: &[Transaction]) {
+		let data = txns
+			.iter()
+			.map(|txn| txn.to_block_data().unwrap())
+			.collect();
+		blockchain.add_block(data);
+	}
+
+	fn next_subsidy(blockchain: &Blockchain) -> Amount {
+		block_subsidy(
+			blockchain.chain.len() as u64,
+			Blockchain::circulating_supply(&blockchain.chain),
+		)
+	}
+
+	// searches for a nonce so the block carries valid proof of work
+	fn forge_block(
+		last_block: &Block,
+		data: Vec<String>,
+		timestamp: i64,
+	) -> Block {
+		let last_hash = hex::encode(&last_block.hash);
+		let difficulty = last_block.difficulty;
+		let (nonce, hash) = (0..)
+			.map(|nonce| {
+				let hash =
+					cryptohash(&data, &last_hash, timestamp, nonce, difficulty);
+				(nonce, hash)
+			})
+			.find(|(_, hash)| Block::is_valid_bit_hash(hash, difficulty))
+			.unwrap();
+		Block::new(
+			timestamp,
+			last_block.hash.clone(),
+			hash,
+			data,
+			nonce,
+			difficulty,
+		)
+	}
+
+	#[test]
+	fn valid_transactions_and_reward() {
+		let (mut blockchain, miner, transaction) = before_each();
+		let reward = Transaction::reward_transaction(
+			&miner.address(),
+			next_subsidy(&blockchain)
+				.checked_add(transaction.fee)
+				.unwrap(),
+		);
+		add_transactions(&mut blockchain, &[transaction, reward]);
+
+		assert_eq!(Blockchain::is_valid_chain(&blockchain.chain), true);
+	}
+
+	#[test]
+	fn reward_does_not_match_fees() {
+		let (mut blockchain, miner, transaction) = before_each();
+		let reward = Transaction::reward_transaction(
+			&miner.address(),
+			next_subsidy(&blockchain),
+		);
+		add_transactions(&mut blockchain, &[transaction, reward]);
+
+		assert_eq!(Blockchain::is_valid_chain(&blockchain.chain), false);
+	}
+
+	#[test]
+	fn reward_exceeds_subsidy() {
+		let (mut blockchain, miner, _) = before_each();
+		let reward = Transaction::reward_transaction(
+			&miner.address(),
+			next_subsidy(&blockchain)
+				.checked_add(Amount::new(1))
+				.unwrap(),
+		);
+		add_transactions(&mut blockchain, &[reward]);
+
+		assert_eq!(Blockchain::is_valid_chain(&blockchain.chain), false);
+	}
+
+	#[test]
+	fn multiple_rewards() {
+		let (mut blockchain, miner, _) = before_each();
+		let subsidy = next_subsidy(&blockchain);
+		let reward = Transaction::reward_transaction(&miner.address(), subsidy);
+		let second_reward =
+			Transaction::reward_transaction(&miner.address(), subsidy);
+		add_transactions(&mut blockchain, &[reward, second_reward]);
+
+		assert_eq!(Blockchain::is_valid_chain(&blockchain.chain), false);
+	}
+
+	#[test]
+	fn invalid_transaction_in_block() {
+		let (mut blockchain, _, mut transaction) = before_each();
+		transaction.amount = Amount::new(1);
+		add_transactions(&mut blockchain, &[transaction]);
+
+		assert_eq!(Blockchain::is_valid_chain(&blockchain.chain), false);
+	}
+
+	#[test]
+	fn duplicate_transaction_across_blocks() {
+		let (mut blockchain, _, transaction) = before_each();
+		add_transactions(&mut blockchain, std::slice::from_ref(&transaction));
+		add_transactions(&mut blockchain, &[transaction]);
+
+		assert_eq!(Blockchain::is_valid_chain(&blockchain.chain), false);
+	}
+
+	#[test]
+	fn malformed_transaction_entry() {
+		let (mut blockchain, _, _) = before_each();
+		blockchain.add_block(vec![String::from("txn:zz")]);
+
+		assert_eq!(Blockchain::is_valid_chain(&blockchain.chain), false);
+	}
+
+	#[test]
+	fn input_does_not_match_chain_balance() {
+		let (mut blockchain, _, _) = before_each();
+		let mut unfunded_wallet = Wallet::new(&Keypair::generate_ed25519());
+		unfunded_wallet.balance = Amount::new(1000);
+		let recipient_wallet = Wallet::new(&Keypair::generate_ed25519());
+		let transaction = Transaction::new(
+			&unfunded_wallet,
+			&recipient_wallet.address(),
+			Amount::new(100),
+		)
+		.unwrap();
+		add_transactions(&mut blockchain, &[transaction]);
+
+		assert_eq!(Blockchain::is_valid_chain(&blockchain.chain), false);
+	}
+
+	#[test]
+	fn same_sender_twice_in_block() {
+		let (mut blockchain, sender_wallet, transaction) = before_each();
+		let recipient_wallet = Wallet::new(&Keypair::generate_ed25519());
+		let second_transaction = Transaction::new(
+			&sender_wallet,
+			&recipient_wallet.address(),
+			Amount::new(10),
+		)
+		.unwrap();
+		add_transactions(&mut blockchain, &[transaction, second_transaction]);
+
+		assert_eq!(Blockchain::is_valid_chain(&blockchain.chain), false);
+	}
+
+	#[test]
+	fn transaction_locked_until_later_height() {
+		let (mut blockchain, sender_wallet, transaction) = before_each();
+		let lock_height = blockchain.chain.len() as u64 + 1;
+		let transaction = transaction
+			.with_lock_time(&sender_wallet, LockTime::Height(lock_height));
+		add_transactions(&mut blockchain, &[transaction]);
+
+		assert_eq!(Blockchain::is_valid_chain(&blockchain.chain), false);
+	}
+
+	#[test]
+	fn transaction_lock_expired_at_block_height() {
+		let (mut blockchain, sender_wallet, transaction) = before_each();
+		let lock_height = blockchain.chain.len() as u64;
+		let transaction = transaction
+			.with_lock_time(&sender_wallet, LockTime::Height(lock_height));
+		let reward = Transaction::reward_transaction(
+			&sender_wallet.address(),
+			next_subsidy(&blockchain)
+				.checked_add(transaction.fee)
+				.unwrap(),
+		);
+		add_transactions(&mut blockchain, &[transaction, reward]);
+
+		assert_eq!(Blockchain::is_valid_chain(&blockchain.chain), true);
+	}
+
+	#[test]
+	fn transaction_locked_until_later_timestamp() {
+		let (mut blockchain, sender_wallet, transaction) = before_each();
+		let transaction = transaction
+			.with_lock_time(&sender_wallet, LockTime::Timestamp(i64::MAX));
+		add_transactions(&mut blockchain, &[transaction]);
+
+		assert_eq!(Blockchain::is_valid_chain(&blockchain.chain), false);
+	}
+
+	#[test]
+	fn future_dated_block_can_not_unlock_transaction() {
+		let (mut blockchain, sender_wallet, transaction) = before_each();
+		let unlock_at = Utc::now().timestamp_millis() + 60 * 60 * 1000;
+		let transaction = transaction
+			.with_lock_time(&sender_wallet, LockTime::Timestamp(unlock_at));
+		let reward = Transaction::reward_transaction(
+			&sender_wallet.address(),
+			next_subsidy(&blockchain)
+				.checked_add(transaction.fee)
+				.unwrap(),
+		);
+		let data = vec![
+			transaction.to_block_data().unwrap(),
+			reward.to_block_data().unwrap(),
+		];
+
+		let forged =
+			forge_block(blockchain.chain.last().unwrap(), data, unlock_at);
+		blockchain.chain.push(forged);
+
+		assert_eq!(Blockchain::is_valid_chain(&blockchain.chain), false);
+	}
+
+	#[test]
+	fn block_not_after_last_block_is_invalid() {
+		let (mut blockchain, _, _) = before_each();
+		blockchain.add_block(vec![String::from("Alpha")]);
+		let last_block = blockchain.chain.pop().unwrap();
+		let previous = blockchain.chain.last().unwrap();
+		let backdated =
+			forge_block(previous, last_block.data.clone(), previous.timestamp);
+		blockchain.chain.push(backdated);
+
+		assert_eq!(Blockchain::is_valid_chain(&blockchain.chain), false);
+	}
+
+	#[test]
+	fn circulating_supply_counts_subsidies_only() {
+		let (mut blockchain, miner, transaction) = before_each();
+		let first_subsidy = Blockchain::circulating_supply(&blockchain.chain);
+		let subsidy = next_subsidy(&blockchain);
+		let reward = Transaction::reward_transaction(
+			&miner.address(),
+			subsidy.checked_add(transaction.fee).unwrap(),
+		);
+		add_transactions(&mut blockchain, &[transaction, reward]);
+
+		let supply = Blockchain::circulating_supply(&blockchain.chain);
+		assert_eq!(supply, first_subsidy.checked_add(subsidy).unwrap());
+		assert!(supply <= MAX_SUPPLY);
+	}
+}
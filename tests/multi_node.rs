@@ -0,0 +1,469 @@
+mod harness {
+	use std::{collections::BTreeSet, sync::Arc, time::Duration};
+
+	use axum::{
+		Router,
+		body::Body,
+		http::{Method, Request, StatusCode, header::CONTENT_TYPE},
+	};
+	use cryptochain::{
+		address_book::AddressBook,
+		amount::Amount,
+		blockchain::Blockchain,
+		channels::create_unbounded_channel,
+		http_server::{AppState, router},
+		network_service::{NetworkHandle, NetworkService},
+		p2p_mdns_bc_coms::{P2PConfig, Transports},
+		p2p_task::start_p2p_task,
+		peer_bans::BanList,
+		transaction_pool::TransactionPool,
+		wallet::Wallet,
+	};
+	use http_body_util::BodyExt;
+	use libp2p::{Multiaddr, PeerId, identity::Keypair, multiaddr::Protocol};
+	use serde_json::{Value, json};
+	use tokio::sync::RwLock;
+	use tokio_util::sync::CancellationToken;
+	use tower::ServiceExt;
+	use uuid::Uuid;
+
+	const TIMEOUT: Duration = Duration::from_secs(30);
+
+	/// A full node in the test process: network service, p2p task and the
+	/// HTTP routes, listening on a random loopback TCP port.
+	pub struct TestNode {
+		pub app: Router,
+		pub network: NetworkHandle,
+		pub addr: Multiaddr,
+		pub blockchain: Arc<RwLock<Blockchain>>,
+		pub transaction_pool: Arc<RwLock<TransactionPool>>,
+		pub wallet: Arc<RwLock<Wallet>>,
+	}
+
+	impl TestNode {
+		pub async fn start() -> Self {
+			let (event_tx, event_rx) = create_unbounded_channel();
+			let blockchain = Arc::new(RwLock::new(Blockchain::new()));
+			let wallet = Arc::new(RwLock::new(Wallet::new(
+				&Keypair::generate_ed25519(),
+			)));
+			let transaction_pool =
+				Arc::new(RwLock::new(TransactionPool::new()));
+			let ban_list = Arc::new(RwLock::new(BanList::new()));
+			let address_book = Arc::new(RwLock::new(AddressBook::new()));
+
+			let config = P2PConfig {
+				keypair: Keypair::generate_ed25519(),
+				enable_mdns: false,
+				bootstrap_peers: Vec::new(),
+				transports: Transports {
+					tcp: true,
+					quic: false,
+					websocket: false,
+				},
+				listen_addrs: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+				external_addrs: Vec::new(),
+			};
+			let (network_service, network) =
+				NetworkService::new(config).unwrap();
+			start_p2p_task(
+				blockchain.clone(),
+				transaction_pool.clone(),
+				ban_list.clone(),
+				address_book.clone(),
+				network.clone(),
+				event_rx,
+				CancellationToken::new(),
+			);
+			tokio::spawn(network_service.run());
+
+			let app = router(AppState::new(
+				blockchain.clone(),
+				wallet.clone(),
+				transaction_pool.clone(),
+				ban_list,
+				address_book,
+				event_tx,
+				CancellationToken::new(),
+			));
+			let addr = eventually("listen address", || async {
+				network.listen_addrs().await.into_iter().next()
+			})
+			.await
+			.with(Protocol::P2p(network.peer_id()));
+
+			Self { app, network, addr, blockchain, transaction_pool, wallet }
+		}
+
+		pub fn peer_id(&self) -> PeerId {
+			self.network.peer_id()
+		}
+
+		pub async fn request(
+			&self,
+			method: Method,
+			uri: &str,
+			body: Option<Value>,
+		) -> (StatusCode, Value) {
+			let builder = Request::builder().method(method).uri(uri);
+			let request = match body {
+				Some(json) => builder
+					.header(CONTENT_TYPE, "application/json")
+					.body(Body::from(json.to_string())),
+				None => builder.body(Body::empty()),
+			}
+			.unwrap();
+			let response = self.app.clone().oneshot(request).await.unwrap();
+			let status = response.status();
+			let bytes = response
+				.into_body()
+				.collect()
+				.await
+				.unwrap()
+				.to_bytes();
+			(status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+		}
+
+		pub async fn mine(&self) {
+			let (status, _) = self
+				.request(Method::POST, "/api/mine-transactions", None)
+				.await;
+			assert_eq!(status, StatusCode::OK);
+		}
+
+		pub async fn transact(
+			&self,
+			recipient: &TestNode,
+			amount: Amount,
+		) -> Uuid {
+			let recipient = recipient
+				.wallet
+				.read()
+				.await
+				.address()
+				.to_string();
+			let (status, body) = self
+				.request(
+					Method::POST,
+					"/api/transact",
+					Some(json!({ "recipient": recipient, "amount": amount })),
+				)
+				.await;
+			assert_eq!(status, StatusCode::OK, "{body}");
+			body["id"].as_str().unwrap().parse().unwrap()
+		}
+
+		pub async fn connect(&self, other: &TestNode) {
+			let (status, _) = self
+				.request(
+					Method::POST,
+					"/api/peers/connect",
+					Some(json!({ "address": other.addr.to_string() })),
+				)
+				.await;
+			assert_eq!(status, StatusCode::ACCEPTED);
+			eventually("connection", || async {
+				self.is_connected(other).await.then_some(())
+			})
+			.await;
+		}
+
+		pub async fn ban(&self, other: &TestNode) {
+			let (status, _) = self
+				.request(
+					Method::POST,
+					"/api/peers/bans",
+					Some(json!({ "peer_id": other.peer_id().to_string() })),
+				)
+				.await;
+			assert_eq!(status, StatusCode::OK);
+			eventually("disconnect", || async {
+				(!self.is_connected(other).await).then_some(())
+			})
+			.await;
+		}
+
+		pub async fn unban(&self, other: &TestNode) {
+			let uri = format!("/api/peers/bans/{}", other.peer_id());
+			let (status, _) = self.request(Method::DELETE, &uri, None).await;
+			assert_eq!(status, StatusCode::NO_CONTENT);
+		}
+
+		pub async fn is_connected(&self, other: &TestNode) -> bool {
+			self.network
+				.connected_peers()
+				.await
+				.contains(&other.peer_id())
+		}
+
+		pub async fn tip(&self) -> Vec<u8> {
+			let blockchain = self.blockchain.read().await;
+			blockchain.chain.last().unwrap().hash.clone()
+		}
+
+		pub async fn pool(&self) -> BTreeSet<Uuid> {
+			let transaction_pool = self.transaction_pool.read().await;
+			transaction_pool
+				.transaction_map()
+				.keys()
+				.copied()
+				.collect()
+		}
+	}
+
+	pub async fn start_nodes(count: usize) -> Vec<TestNode> {
+		let mut nodes = Vec::new();
+		for _ in 0..count {
+			nodes.push(TestNode::start().await);
+		}
+		for (index, node) in nodes.iter().enumerate() {
+			for other in &nodes[index + 1..] {
+				node.connect(other).await;
+			}
+		}
+		nodes
+	}
+
+	/// Polls `check` until it returns a value or `TIMEOUT` passes.
+	pub async fn eventually<T, F, Fut>(what: &str, mut check: F) -> T
+	where
+		F: FnMut() -> Fut,
+		Fut: Future<Output = Option<T>>,
+	{
+		tokio::time::timeout(TIMEOUT, async {
+			loop {
+				if let Some(value) = check().await {
+					return value;
+				}
+				tokio::time::sleep(Duration::from_millis(50)).await;
+			}
+		})
+		.await
+		.unwrap_or_else(|_| panic!("Timed out waiting for {what}."))
+	}
+
+	pub async fn converge_on_tip(nodes: &[&TestNode], tip: &[u8]) {
+		eventually("tip", || async {
+			for node in nodes {
+				if node.tip().await != tip {
+					return None;
+				}
+			}
+			Some(())
+		})
+		.await;
+	}
+
+	pub async fn converge_on_pool(nodes: &[&TestNode], pool: &BTreeSet<Uuid>) {
+		eventually("pool", || async {
+			for node in nodes {
+				if node.pool().await != *pool {
+					return None;
+				}
+			}
+			Some(())
+		})
+		.await;
+	}
+}
+
+mod convergence {
+	use super::harness::*;
+	use cryptochain::{amount::Amount, wallet::Wallet};
+	use pretty_assertions::assert_eq;
+	use std::collections::BTreeSet;
+
+	#[tokio::test(flavor = "multi_thread")]
+	async fn nodes_converge_on_mined_blocks_and_transactions() {
+		let nodes = start_nodes(3).await;
+		let all: Vec<&TestNode> = nodes.iter().collect();
+
+		nodes[0].mine().await;
+		converge_on_tip(&all, &nodes[0].tip().await).await;
+
+		let txn_id = nodes[0]
+			.transact(&nodes[1], Amount::new(10))
+			.await;
+		converge_on_pool(&all, &BTreeSet::from([txn_id])).await;
+
+		nodes[2].mine().await;
+		converge_on_tip(&all, &nodes[2].tip().await).await;
+		converge_on_pool(&all, &BTreeSet::new()).await;
+
+		let recipient = nodes[1].wallet.read().await.address();
+		for node in &nodes {
+			let blockchain = node.blockchain.read().await;
+			assert_eq!(
+				Wallet::calculate_balance(&blockchain.chain, &recipient),
+				Amount::new(10)
+			);
+		}
+	}
+
+	#[tokio::test(flavor = "multi_thread")]
+	async fn messages_are_forwarded_across_hops() {
+		let first = TestNode::start().await;
+		let middle = TestNode::start().await;
+		let last = TestNode::start().await;
+		first.connect(&middle).await;
+		middle.connect(&last).await;
+
+		first.mine().await;
+
+		converge_on_tip(&[&middle, &last], &first.tip().await).await;
+	}
+}
+
+mod partition {
+	use super::harness::*;
+	use cryptochain::amount::Amount;
+	use pretty_assertions::assert_ne;
+	use std::collections::BTreeSet;
+
+	#[tokio::test(flavor = "multi_thread")]
+	async fn partitioned_nodes_converge_after_heal() {
+		let nodes = start_nodes(3).await;
+		let (isolated, majority) = (&nodes[0], [&nodes[1], &nodes[2]]);
+		isolated.mine().await;
+		converge_on_tip(
+			&[isolated, majority[0], majority[1]],
+			&isolated.tip().await,
+		)
+		.await;
+
+		// both sides keep mining, the majority builds the longer chain
+		isolated.ban(majority[0]).await;
+		isolated.ban(majority[1]).await;
+		isolated.mine().await;
+		majority[0].mine().await;
+		converge_on_tip(&majority, &majority[0].tip().await).await;
+		majority[1].mine().await;
+		let majority_tip = majority[1].tip().await;
+		converge_on_tip(&majority, &majority_tip).await;
+		assert_ne!(isolated.tip().await, majority_tip);
+
+		let txn_id = majority[0]
+			.transact(majority[1], Amount::new(5))
+			.await;
+		converge_on_pool(&majority, &BTreeSet::from([txn_id])).await;
+		assert_ne!(isolated.pool().await, BTreeSet::from([txn_id]));
+
+		// reconnecting peers exchange chain and pool
+		isolated.unban(majority[0]).await;
+		isolated.unban(majority[1]).await;
+		isolated.connect(majority[0]).await;
+
+		converge_on_tip(&[isolated, majority[0], majority[1]], &majority_tip)
+			.await;
+		converge_on_pool(&[isolated], &BTreeSet::from([txn_id])).await;
+	}
+}
+
+mod api_errors {
+	use super::harness::*;
+	use axum::http::{Method, StatusCode};
+	use cryptochain::amount::Amount;
+	use pretty_assertions::assert_eq;
+	use serde_json::json;
+
+	#[tokio::test(flavor = "multi_thread")]
+	async fn transact_answers_errors_instead_of_panicking() {
+		let node = TestNode::start().await;
+		let recipient = node.wallet.read().await.address().to_string();
+		let mut typo = recipient.clone();
+		let last = if typo.ends_with('2') { '3' } else { '2' };
+		typo.pop();
+		typo.push(last);
+
+		let (missing, missing_body) = node
+			.request(
+				Method::POST,
+				"/api/transact",
+				Some(json!({ "recipient": recipient })),
+			)
+			.await;
+		let (mistyped, mistyped_body) = node
+			.request(
+				Method::POST,
+				"/api/transact",
+				Some(json!({ "recipient": typo, "amount": "1" })),
+			)
+			.await;
+		let (numeric, numeric_body) = node
+			.request(
+				Method::POST,
+				"/api/transact",
+				Some(json!({ "recipient": recipient, "amount": 1 })),
+			)
+			.await;
+
+		assert_eq!(missing, StatusCode::UNPROCESSABLE_ENTITY);
+		assert_eq!(missing_body["code"], "validation_failed");
+		assert_eq!(mistyped, StatusCode::BAD_REQUEST);
+		assert_eq!(mistyped_body["code"], "invalid_address");
+		assert_eq!(numeric, StatusCode::UNPROCESSABLE_ENTITY);
+		assert_eq!(numeric_body["code"], "invalid_json");
+	}
+
+	#[tokio::test(flavor = "multi_thread")]
+	async fn transact_rejects_fee_on_pending_transaction() {
+		let node = TestNode::start().await;
+		let recipient = TestNode::start().await;
+		node.mine().await;
+		let txn_id = node.transact(&recipient, Amount::new(10)).await;
+		let address = recipient
+			.wallet
+			.read()
+			.await
+			.address()
+			.to_string();
+
+		let (status, body) = node
+			.request(
+				Method::POST,
+				"/api/transact",
+				Some(json!({
+					"recipient": address,
+					"amount": "1",
+					"fee": "0.001",
+				})),
+			)
+			.await;
+
+		assert_eq!(status, StatusCode::BAD_REQUEST);
+		assert_eq!(body["code"], "invalid_transaction");
+		let transaction_pool = node.transaction_pool.read().await;
+		let pending = transaction_pool
+			.transaction_map()
+			.get(&txn_id)
+			.unwrap();
+		let paid = pending.output_map.get(&address.parse().unwrap());
+		assert_eq!(paid, Some(&Amount::new(10)));
+	}
+
+	#[tokio::test(flavor = "multi_thread")]
+	async fn routes_answer_json_errors() {
+		let node = TestNode::start().await;
+
+		let (balance, balance_body) = node
+			.request(Method::GET, "/api/balances/0a0b", None)
+			.await;
+		let (ban, ban_body) = node
+			.request(Method::POST, "/api/peers/bans", Some(json!({})))
+			.await;
+		let (pending, pending_body) = node
+			.request(
+				Method::GET,
+				"/api/multisig/transactions/00000000-0000-0000-0000-000000000000",
+				None,
+			)
+			.await;
+
+		assert_eq!(balance, StatusCode::BAD_REQUEST);
+		assert_eq!(balance_body["code"], "invalid_address");
+		assert_eq!(ban, StatusCode::UNPROCESSABLE_ENTITY);
+		assert_eq!(ban_body["code"], "validation_failed");
+		assert_eq!(pending, StatusCode::NOT_FOUND);
+		assert_eq!(pending_body["code"], "not_found");
+	}
+}
@@ -0,0 +1,256 @@
+/**
+ * Fixed signing vectors. Third party wallets have to reproduce these bytes
+ * exactly, so any change here is a consensus breaking change and has to bump
+ * `SIGNING_PAYLOAD_VERSION`. The vector of the replaced version then moves
+ * to `RETIRED_VECTORS` unchanged.
+ */
+mod signing_vectors {
+	use std::collections::BTreeMap;
+
+	use cryptochain::{
+		address::Address,
+		amount::Amount,
+		config::{ADDRESS_VERSION, SIGNING_PAYLOAD_VERSION},
+		lock_time::LockTime,
+		transaction::Transaction,
+		txn_input::TransactionInput,
+		wallet::Wallet,
+	};
+	use libp2p::identity::Keypair;
+	use pretty_assertions::assert_eq;
+	use uuid::Uuid;
+
+	const SENDER_SECRET: [u8; 32] = [7; 32];
+	const RECIPIENT_SECRET: [u8; 32] = [9; 32];
+	const TXN_ID: u128 = 0x0123_4567_89ab_cdef_0123_4567_89ab_cdef;
+	const TIMESTAMP: i64 = 1_700_000_000_000;
+	const INPUT_AMOUNT: Amount = Amount::new(1000);
+	const AMOUNT: Amount = Amount::new(50);
+	const FEE: Amount = Amount::new(3);
+	const LOCK_HEIGHT: u64 = 42;
+
+	const PAYLOAD_HEX: &str = concat!(
+		"051100000063727970746f636861696e2d6c6f63616c67452301ab89",
+		"efcd0123456789abcdef32000000000000000300000000000000012a",
+		"00000000000000e8030000000000000068e5cf8b0100001cd1982045",
+		"2343164cd868f4ef000fc0652c1741bfb7ca5febcdd9051929b085b8",
+		"2400000008011220ea4a6c63e29c520abef5507b132ec5f9954776ae",
+		"bebe7b92421eea691446d22c020000001c23ecaf131ee36cc94f2097",
+		"609e6e79d30b6671cb8aac6af94564a56975d6dcdc32000000000000",
+		"001cd19820452343164cd868f4ef000fc0652c1741bfb7ca5febcdd9",
+		"051929b085b8b303000000000000",
+	);
+	const SIGNATURE_HEX: &str = concat!(
+		"e3a2220164dacb73b3cb6078dd44e702e9d6a19a9cc7d27719e3a145",
+		"ef353a12b17579338758bc93c6fe72dcd4fa9c006df6a9b19301d5b1",
+		"8ad93dcd2fc23e03",
+	);
+
+	// Vectors of earlier payload versions, frozen when the version was
+	// bumped. Nodes only accept signatures over the current version, these
+	// stay so the old layouts remain documented and can not drift.
+	struct RetiredVector {
+		version: u8,
+		payload_hex: &'static str,
+		signature_hex: &'static str,
+	}
+
+	const RETIRED_VECTORS: [RetiredVector; 4] = [
+		RetiredVector {
+			version: 1,
+			payload_hex: concat!(
+				"011100000063727970746f636861696e2d6c6f63616c67452301ab89",
+				"efcd0123456789abcdef32000000e80300000068e5cf8b0100002400",
+				"000008011220ea4a6c63e29c520abef5507b132ec5f9954776aebebe",
+				"7b92421eea691446d22c020000002400000008011220ea4a6c63e29c",
+				"520abef5507b132ec5f9954776aebebe7b92421eea691446d22cb603",
+				"00002400000008011220fd1724385aa0c75b64fb78cd602fa1d991fd",
+				"ebf76b13c58ed702eac835e9f61832000000",
+			),
+			signature_hex: concat!(
+				"436b08e3260daca838d1af8cc96180c91f2d744d1af610b442ff4d46",
+				"5dbdb0953c52118021d4bdaf21e9bf5ad95f8268e528e054c5283dcd",
+				"fca4b316e0a33c05",
+			),
+		},
+		RetiredVector {
+			version: 2,
+			payload_hex: concat!(
+				"021100000063727970746f636861696e2d6c6f63616c67452301ab89",
+				"efcd0123456789abcdef3200000003000000e80300000068e5cf8b01",
+				"00002400000008011220ea4a6c63e29c520abef5507b132ec5f99547",
+				"76aebebe7b92421eea691446d22c020000002400000008011220ea4a",
+				"6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446",
+				"d22cb30300002400000008011220fd1724385aa0c75b64fb78cd602f",
+				"a1d991fdebf76b13c58ed702eac835e9f61832000000",
+			),
+			signature_hex: concat!(
+				"a5faabc1134e241df3c56f20dc021cd0137e56fcaaf58695ed316281",
+				"a7833ccd55f28183fd1e775b8f6abcc95659b1cb8f033707a6112208",
+				"0e1451f3a3394f02",
+			),
+		},
+		RetiredVector {
+			version: 3,
+			payload_hex: concat!(
+				"031100000063727970746f636861696e2d6c6f63616c67452301ab89",
+				"efcd0123456789abcdef3200000003000000012a00000000000000e8",
+				"0300000068e5cf8b0100002400000008011220ea4a6c63e29c520abe",
+				"f5507b132ec5f9954776aebebe7b92421eea691446d22c0200000024",
+				"00000008011220ea4a6c63e29c520abef5507b132ec5f9954776aebe",
+				"be7b92421eea691446d22cb30300002400000008011220fd1724385a",
+				"a0c75b64fb78cd602fa1d991fdebf76b13c58ed702eac835e9f61832",
+				"000000",
+			),
+			signature_hex: concat!(
+				"cce7a74b63f0ecfbe3348d377bd5fa7c1a08b3963e31f15da6050ae9",
+				"d01b152e298f73684c773e8ec17cc2b2426d35fc9a67b5f9db1064c3",
+				"38dad0f19a639005",
+			),
+		},
+		RetiredVector {
+			version: 4,
+			payload_hex: concat!(
+				"041100000063727970746f636861696e2d6c6f63616c67452301ab89",
+				"efcd0123456789abcdef32000000000000000300000000000000012a",
+				"00000000000000e8030000000000000068e5cf8b0100002400000008",
+				"011220ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b9242",
+				"1eea691446d22c020000002400000008011220ea4a6c63e29c520abe",
+				"f5507b132ec5f9954776aebebe7b92421eea691446d22cb303000000",
+				"0000002400000008011220fd1724385aa0c75b64fb78cd602fa1d991",
+				"fdebf76b13c58ed702eac835e9f6183200000000000000",
+			),
+			signature_hex: concat!(
+				"e4cd009cfcde919817b5790800fce0a886d2fd4d5fe6804929d19ec4",
+				"0e2275734b3571254660e2d03a9177bcedeadc9ca8ef9c0f4db3afca",
+				"a86be102ab20f80a",
+			),
+		},
+	];
+
+	fn vector_transaction() -> (Transaction, Wallet) {
+		let sender =
+			Wallet::new(&Keypair::ed25519_from_bytes(SENDER_SECRET).unwrap());
+		let recipient = Wallet::new(
+			&Keypair::ed25519_from_bytes(RECIPIENT_SECRET).unwrap(),
+		);
+
+		let id = Uuid::from_u128(TXN_ID);
+		let mut output_map: BTreeMap<Address, Amount> = BTreeMap::new();
+		output_map.insert(recipient.address(), AMOUNT);
+		let change = INPUT_AMOUNT.units() - AMOUNT.units() - FEE.units();
+		output_map.insert(sender.address(), Amount::new(change));
+
+		let mut transaction = Transaction {
+			id,
+			amount: AMOUNT,
+			fee: FEE,
+			lock_time: Some(LockTime::Height(LOCK_HEIGHT)),
+			input: TransactionInput {
+				timestamp: TIMESTAMP,
+				amount: INPUT_AMOUNT,
+				sender_address: sender.address(),
+				public_key: sender.public_key.clone(),
+				signature: Vec::new(),
+				multisig: None,
+			},
+			output_map,
+		};
+		transaction.input.signature = sender
+			.sign(&transaction.signing_payload())
+			.unwrap();
+
+		(transaction, sender)
+	}
+
+	#[test]
+	fn payload_matches_vector() {
+		let (transaction, _) = vector_transaction();
+		assert_eq!(hex::encode(transaction.signing_payload()), PAYLOAD_HEX);
+	}
+
+	#[test]
+	fn signature_matches_vector() {
+		let (transaction, _) = vector_transaction();
+		assert_eq!(hex::encode(&transaction.input.signature), SIGNATURE_HEX);
+	}
+
+	#[test]
+	fn vector_signature_verifies() {
+		let (transaction, sender) = vector_transaction();
+		let payload = hex::decode(PAYLOAD_HEX).unwrap();
+		let signature = hex::decode(SIGNATURE_HEX).unwrap();
+
+		assert_eq!(
+			Wallet::verify_signature(&sender.public_key, &payload, &signature),
+			true
+		);
+		assert_eq!(transaction.is_valid(), true);
+	}
+
+	#[test]
+	fn every_payload_version_has_a_vector() {
+		let payload = hex::decode(PAYLOAD_HEX).unwrap();
+		let retired: Vec<u8> = RETIRED_VECTORS
+			.iter()
+			.map(|vector| vector.version)
+			.collect();
+
+		assert_eq!(payload[0], SIGNING_PAYLOAD_VERSION);
+		assert_eq!(retired, (1..SIGNING_PAYLOAD_VERSION).collect::<Vec<u8>>());
+	}
+
+	#[test]
+	fn retired_vectors_stay_frozen() {
+		let (_, sender) = vector_transaction();
+		for vector in &RETIRED_VECTORS {
+			let payload = hex::decode(vector.payload_hex).unwrap();
+			let signature = hex::decode(vector.signature_hex).unwrap();
+
+			assert_eq!(payload[0], vector.version);
+			assert_eq!(
+				Wallet::verify_signature(
+					&sender.public_key,
+					&payload,
+					&signature
+				),
+				true
+			);
+		}
+	}
+
+	#[test]
+	fn retired_signatures_are_rejected() {
+		let (transaction, _) = vector_transaction();
+		for vector in &RETIRED_VECTORS {
+			let mut retired = transaction.clone();
+			retired.input.signature =
+				hex::decode(vector.signature_hex).unwrap();
+
+			assert_eq!(retired.is_valid(), false);
+		}
+	}
+
+	#[test]
+	fn payload_ignores_output_insertion_order() {
+		let (transaction, _) = vector_transaction();
+		let mut first = transaction.clone();
+		let mut second = transaction.clone();
+		first.output_map.clear();
+		second.output_map.clear();
+		for idx in 0..32u8 {
+			first.output_map.insert(
+				Address::new(ADDRESS_VERSION, [idx; 32]),
+				Amount::new(idx as u64),
+			);
+		}
+		for idx in (0..32u8).rev() {
+			second.output_map.insert(
+				Address::new(ADDRESS_VERSION, [idx; 32]),
+				Amount::new(idx as u64),
+			);
+		}
+
+		assert_eq!(first.signing_payload(), second.signing_payload());
+	}
+}
@@ -1,14 +1,16 @@
 mod transaction_tests {
 	use cryptochain::{
-		transaction::Transaction, utils::output_map_to_bytes, wallet::Wallet,
+		address::Address, amount::Amount, transaction::Transaction,
+		utils::output_map_to_bytes, wallet::Wallet,
 	};
 	use libp2p::identity::Keypair;
 	use pretty_assertions::assert_eq;
 
-	fn before_each() -> (Wallet, Wallet, u32) {
-		let sender_wallet = Wallet::new(&Keypair::generate_ed25519());
+	fn before_each() -> (Wallet, Wallet, Amount) {
+		let mut sender_wallet = Wallet::new(&Keypair::generate_ed25519());
+		sender_wallet.balance = Amount::new(1000);
 		let recipient_wallet = Wallet::new(&Keypair::generate_ed25519());
-		let amount: u32 = 50;
+		let amount = Amount::new(50);
 
 		(sender_wallet, recipient_wallet, amount)
 	}
@@ -26,9 +28,10 @@ mod transaction_tests {
 		let (sender_wallet, recipient_wallet, amount) = before_each();
 		let transaction = Transaction::new(
 			&sender_wallet,
-			&recipient_wallet.public_key,
+			&recipient_wallet.address(),
 			amount,
-		);
+		)
+		.unwrap();
 
 		let txn_id_bytes = &transaction.id.into_bytes();
 
@@ -40,15 +43,16 @@ mod transaction_tests {
 		let (sender_wallet, recipient_wallet, amount) = before_each();
 		let transaction = Transaction::new(
 			&sender_wallet,
-			&recipient_wallet.public_key,
+			&recipient_wallet.address(),
 			amount,
-		);
+		)
+		.unwrap();
 
 		// let recipient_amount_comparator = recipient_wallet.balance + amount;
 
 		let txn_value = transaction
 			.output_map
-			.get(&recipient_wallet.public_key)
+			.get(&recipient_wallet.address())
 			.unwrap();
 
 		assert_eq!(*txn_value, amount);
@@ -59,15 +63,17 @@ mod transaction_tests {
 		let (sender_wallet, recipient_wallet, amount) = before_each();
 		let transaction = Transaction::new(
 			&sender_wallet,
-			&recipient_wallet.public_key,
+			&recipient_wallet.address(),
 			amount,
-		);
+		)
+		.unwrap();
 
-		let sender_amount_comparator = sender_wallet.balance - amount;
+		let sender_amount_comparator =
+			sender_wallet.balance.checked_sub(amount).unwrap();
 
 		let txn_value = transaction
 			.output_map
-			.get(&sender_wallet.public_key)
+			.get(&sender_wallet.address())
 			.unwrap();
 
 		assert_eq!(*txn_value, sender_amount_comparator);
@@ -79,16 +85,17 @@ mod transaction_tests {
 		let sender_amount_pre = sender_wallet.balance;
 		let transaction = Transaction::new(
 			&sender_wallet,
-			&recipient_wallet.public_key,
+			&recipient_wallet.address(),
 			amount,
-		);
+		)
+		.unwrap();
 
 		let sender_amount = transaction
 			.output_map
-			.get(&sender_wallet.public_key)
+			.get(&sender_wallet.address())
 			.unwrap();
 
-		let sender_remaining = sender_amount_pre - amount;
+		let sender_remaining = sender_amount_pre.checked_sub(amount).unwrap();
 		assert_eq!(sender_remaining, *sender_amount)
 	}
 
@@ -97,10 +104,11 @@ mod transaction_tests {
 		let (sender_wallet, recipient_wallet, amount) = before_each();
 		let transaction = Transaction::new(
 			&sender_wallet,
-			&recipient_wallet.public_key,
+			&recipient_wallet.address(),
 			amount,
-		);
-		assert_eq!(transaction.input.sender_address, sender_wallet.public_key)
+		)
+		.unwrap();
+		assert_eq!(transaction.input.sender_address, sender_wallet.address())
 	}
 
 	#[test]
@@ -108,21 +116,16 @@ mod transaction_tests {
 		let (sender_wallet, recipient_wallet, amount) = before_each();
 		let transaction = Transaction::new(
 			&sender_wallet,
-			&recipient_wallet.public_key,
+			&recipient_wallet.address(),
 			amount,
-		);
-
-		let output_map = transaction.output_map;
-		let output_bytes = output_map_to_bytes(&output_map);
-		let signature = sender_wallet
-			.sign(&output_bytes)
-			.expect("Failed to generate signature.");
+		)
+		.unwrap();
 
 		assert_eq!(
 			Wallet::verify_signature(
 				&sender_wallet.public_key,
-				&output_bytes,
-				&signature
+				&transaction.signing_payload(),
+				&transaction.input.signature
 			),
 			true
 		)
@@ -137,9 +140,10 @@ mod transaction_tests {
 			let (sender_wallet, recipient_wallet, amount) = before_each();
 			let transaction = Transaction::new(
 				&sender_wallet,
-				&recipient_wallet.public_key,
+				&recipient_wallet.address(),
 				amount,
-			);
+			)
+			.unwrap();
 
 			assert_eq!(transaction.is_valid(), true);
 		}
@@ -150,13 +154,14 @@ mod transaction_tests {
 
 			let mut transaction = Transaction::new(
 				&sender_wallet,
-				&recipient_wallet.public_key,
+				&recipient_wallet.address(),
 				amount,
-			);
+			)
+			.unwrap();
 
 			transaction
 				.output_map
-				.insert(sender_wallet.public_key, 999999);
+				.insert(sender_wallet.address(), Amount::new(999999));
 
 			assert_eq!(transaction.is_valid(), false);
 		}
@@ -167,9 +172,10 @@ mod transaction_tests {
 
 			let mut transaction = Transaction::new(
 				&sender_wallet,
-				&recipient_wallet.public_key,
+				&recipient_wallet.address(),
 				amount,
-			);
+			)
+			.unwrap();
 
 			let wallet = Wallet::new(&Keypair::generate_ed25519());
 			let output_bytes = output_map_to_bytes(&transaction.output_map);
@@ -180,29 +186,97 @@ mod transaction_tests {
 		}
 	}
 
+	mod test_fee {
+		use super::*;
+		use pretty_assertions::assert_eq;
+
+		#[test]
+		fn subtracts_fee_from_sender_output() {
+			let (sender_wallet, recipient_wallet, amount) = before_each();
+			let transaction = Transaction::with_fee(
+				&sender_wallet,
+				&recipient_wallet.address(),
+				amount,
+				Amount::new(5),
+			)
+			.unwrap();
+
+			let sender_amount = transaction
+				.output_map
+				.get(&sender_wallet.address())
+				.unwrap();
+
+			let change = sender_wallet.balance.units() - amount.units() - 5;
+			assert_eq!(*sender_amount, Amount::new(change));
+			assert_eq!(transaction.is_valid(), true);
+		}
+
+		#[test]
+		fn tampered_fee_is_invalid() {
+			let (sender_wallet, recipient_wallet, amount) = before_each();
+			let mut transaction = Transaction::with_fee(
+				&sender_wallet,
+				&recipient_wallet.address(),
+				amount,
+				Amount::new(5),
+			)
+			.unwrap();
+
+			transaction.fee = Amount::new(6);
+			let change = Amount::new(1000 - amount.units() - 6);
+			transaction
+				.output_map
+				.insert(sender_wallet.address(), change);
+
+			assert_eq!(transaction.is_valid(), false);
+		}
+
+		#[test]
+		fn reward_transaction_is_not_valid_alone() {
+			let (_, recipient_wallet, _) = before_each();
+			let transaction = Transaction::reward_transaction(
+				&recipient_wallet.address(),
+				Amount::new(50),
+			);
+
+			assert_eq!(transaction.is_reward(), true);
+			assert_eq!(transaction.is_valid(), false);
+			assert_eq!(transaction.is_valid_reward(Amount::new(50)), true);
+		}
+	}
+
 	mod test_update_amount_valid {
 		use super::*;
 		use pretty_assertions::assert_eq;
 
-		fn before_each()
-		-> (Wallet, Wallet, Transaction, Vec<u8>, Vec<u8>, u32, u32, u32) {
+		fn before_each() -> (
+			Wallet,
+			Wallet,
+			Transaction,
+			Vec<u8>,
+			Address,
+			Amount,
+			Amount,
+			Amount,
+		) {
 			let (sender_wallet, recipient_wallet, amount) =
 				super::before_each();
 			let mut transaction = Transaction::new(
 				&sender_wallet,
-				&recipient_wallet.public_key,
+				&recipient_wallet.address(),
 				amount,
-			);
+			)
+			.unwrap();
 
 			let original_signature = transaction.input.signature.clone();
 			let original_sender_output = *transaction
 				.output_map
-				.get(&sender_wallet.public_key)
+				.get(&sender_wallet.address())
 				.unwrap();
 
 			let next_recipient =
-				Wallet::new(&Keypair::generate_ed25519()).public_key;
-			let next_amount: u32 = 80;
+				Wallet::new(&Keypair::generate_ed25519()).address();
+			let next_amount = Amount::new(80);
 
 			transaction
 				.update(&sender_wallet, &next_recipient, next_amount)
@@ -256,12 +330,14 @@ mod transaction_tests {
 
 			let next_recipient_amount = transaction
 				.output_map
-				.get(&sender_wallet.public_key)
+				.get(&sender_wallet.address())
 				.unwrap();
 
 			assert_eq!(
 				*next_recipient_amount,
-				original_sender_output - next_amount
+				original_sender_output
+					.checked_sub(next_amount)
+					.unwrap()
 			);
 		}
 
@@ -278,7 +354,9 @@ mod transaction_tests {
 				_next_amount,
 			) = before_each();
 
-			let amount_sum: u32 = transaction.output_map.values().sum();
+			let amount_sum =
+				Amount::checked_sum(transaction.output_map.values().copied())
+					.unwrap();
 
 			assert_eq!(amount_sum, transaction.input.amount);
 		}
@@ -300,6 +378,73 @@ mod transaction_tests {
 		}
 	}
 
+	mod test_update_balance_changed {
+		use super::*;
+		use pretty_assertions::assert_eq;
+
+		#[test]
+		fn recomputes_change_from_wallet_balance() {
+			let (mut sender_wallet, recipient_wallet, amount) =
+				super::before_each();
+			let mut transaction = Transaction::with_fee(
+				&sender_wallet,
+				&recipient_wallet.address(),
+				amount,
+				Amount::new(5),
+			)
+			.unwrap();
+			sender_wallet.balance = sender_wallet
+				.balance
+				.checked_add(Amount::new(200))
+				.unwrap();
+
+			transaction
+				.update(
+					&sender_wallet,
+					&recipient_wallet.address(),
+					Amount::new(10),
+				)
+				.unwrap();
+
+			let change = sender_wallet
+				.balance
+				.checked_sub(amount)
+				.and_then(|change| change.checked_sub(Amount::new(15)))
+				.unwrap();
+			assert_eq!(transaction.input.amount, sender_wallet.balance);
+			assert_eq!(
+				transaction
+					.output_map
+					.get(&sender_wallet.address()),
+				Some(&change)
+			);
+			assert_eq!(transaction.is_valid(), true);
+		}
+
+		#[test]
+		fn rejects_update_beyond_lowered_balance() {
+			let (mut sender_wallet, recipient_wallet, amount) =
+				super::before_each();
+			let mut transaction = Transaction::new(
+				&sender_wallet,
+				&recipient_wallet.address(),
+				amount,
+			)
+			.unwrap();
+			let pending = transaction.clone();
+			sender_wallet.balance = amount;
+
+			let res = transaction.update(
+				&sender_wallet,
+				&recipient_wallet.address(),
+				Amount::new(1),
+			);
+
+			assert_eq!(res, Err("Insufficient wallet balance."));
+			assert_eq!(transaction, pending);
+		}
+	}
+
 	mod test_update_amount_invalid {
 		use super::*;
 		use pretty_assertions::assert_eq;
@@ -310,13 +455,14 @@ mod transaction_tests {
 				super::before_each();
 			let mut transaction = Transaction::new(
 				&sender_wallet,
-				&recipient_wallet.public_key,
+				&recipient_wallet.address(),
 				amount,
-			);
+			)
+			.unwrap();
 
 			let next_recipient =
-				Wallet::new(&Keypair::generate_ed25519()).public_key;
-			let next_amount: u32 = 999999;
+				Wallet::new(&Keypair::generate_ed25519()).address();
+			let next_amount = Amount::new(999999);
 
 			let res = transaction.update(
 				&sender_wallet,
@@ -333,12 +479,13 @@ mod transaction_tests {
 				super::before_each();
 			let mut transaction = Transaction::new(
 				&sender_wallet,
-				&recipient_wallet.public_key,
+				&recipient_wallet.address(),
 				amount,
-			);
+			)
+			.unwrap();
 
-			let next_recipient = &recipient_wallet.public_key;
-			let next_amount: u32 = 100;
+			let next_recipient = &recipient_wallet.address();
+			let next_amount = Amount::new(100);
 
 			let first_recipient_amount = *transaction
 				.output_map
@@ -347,11 +494,11 @@ mod transaction_tests {
 
 			let first_sender_amount = *transaction
 				.output_map
-				.get(&sender_wallet.public_key)
+				.get(&sender_wallet.address())
 				.unwrap();
 
 			transaction
-				.update(&sender_wallet, &next_recipient, next_amount)
+				.update(&sender_wallet, next_recipient, next_amount)
 				.unwrap();
 
 			let total_recipient_amount = *transaction
@@ -361,15 +508,112 @@ mod transaction_tests {
 
 			let total_sender_amount = *transaction
 				.output_map
-				.get(&sender_wallet.public_key)
+				.get(&sender_wallet.address())
 				.unwrap();
 
 			assert_eq!(
 				total_recipient_amount,
-				first_recipient_amount + next_amount
+				first_recipient_amount
+					.checked_add(next_amount)
+					.unwrap()
+			);
+
+			assert_eq!(
+				total_sender_amount,
+				first_sender_amount
+					.checked_sub(next_amount)
+					.unwrap()
+			);
+		}
+
+		#[test]
+		fn rejects_zero_amount() {
+			let (sender_wallet, recipient_wallet, amount) =
+				super::before_each();
+			let mut transaction = Transaction::new(
+				&sender_wallet,
+				&recipient_wallet.address(),
+				amount,
+			)
+			.unwrap();
+
+			let res = transaction.update(
+				&sender_wallet,
+				&recipient_wallet.address(),
+				Amount::ZERO,
 			);
 
-			assert_eq!(total_sender_amount, first_sender_amount - next_amount);
+			assert_eq!(res, Err("Amount must be positive."));
+		}
+
+		#[test]
+		fn rejects_sending_to_sender() {
+			let (sender_wallet, recipient_wallet, amount) =
+				super::before_each();
+			let mut transaction = Transaction::new(
+				&sender_wallet,
+				&recipient_wallet.address(),
+				amount,
+			)
+			.unwrap();
+
+			let res = transaction.update(
+				&sender_wallet,
+				&sender_wallet.address(),
+				Amount::new(10),
+			);
+
+			assert_eq!(res, Err("Cannot send to the sender address."));
+		}
+	}
+
+	mod test_lock_time {
+		use super::*;
+		use cryptochain::lock_time::LockTime;
+		use pretty_assertions::assert_eq;
+
+		fn locked_transaction() -> Transaction {
+			let (sender_wallet, recipient_wallet, amount) =
+				super::before_each();
+			Transaction::new(
+				&sender_wallet,
+				&recipient_wallet.address(),
+				amount,
+			)
+			.unwrap()
+			.with_lock_time(&sender_wallet, LockTime::Height(10))
+		}
+
+		#[test]
+		fn signs_the_lock_time() {
+			let transaction = locked_transaction();
+
+			assert_eq!(transaction.lock_time, Some(LockTime::Height(10)));
+			assert_eq!(transaction.is_valid(), true);
+		}
+
+		#[test]
+		fn tampered_lock_time_is_invalid() {
+			let mut transaction = locked_transaction();
+			transaction.lock_time = Some(LockTime::Height(1));
+
+			assert_eq!(transaction.is_valid(), false);
+		}
+
+		#[test]
+		fn removed_lock_time_is_invalid() {
+			let mut transaction = locked_transaction();
+			transaction.lock_time = None;
+
+			assert_eq!(transaction.is_valid(), false);
+		}
+
+		#[test]
+		fn lock_expires_at_height() {
+			let transaction = locked_transaction();
+
+			assert_eq!(transaction.is_lock_expired(9, i64::MAX), false);
+			assert_eq!(transaction.is_lock_expired(10, 0), true);
 		}
 	}
 
@@ -384,8 +628,30 @@ mod transaction_tests {
 				super::before_each();
 			let transaction = Transaction::new(
 				&sender_wallet,
-				&recipient_wallet.public_key,
+				&recipient_wallet.address(),
+				amount,
+			)
+			.unwrap();
+
+			let bytes = transaction.to_bytes().unwrap();
+			let decoded = Transaction::from_bytes(&bytes).unwrap();
+
+			assert_eq!(transaction, decoded);
+		}
+
+		#[test]
+		fn test_encode_decode_with_lock_time() {
+			let (sender_wallet, recipient_wallet, amount) =
+				super::before_each();
+			let transaction = Transaction::new(
+				&sender_wallet,
+				&recipient_wallet.address(),
 				amount,
+			)
+			.unwrap()
+			.with_lock_time(
+				&sender_wallet,
+				cryptochain::lock_time::LockTime::Timestamp(1_700_000_000_000),
 			);
 
 			let bytes = transaction.to_bytes().unwrap();
@@ -394,4 +660,256 @@ mod transaction_tests {
 			assert_eq!(transaction, decoded);
 		}
 	}
+
+	mod test_multisig {
+		use super::*;
+		use cryptochain::{
+			blockchain::{Blockchain, BlockchainTr},
+			config::INITIAL_BLOCK_SUBSIDY,
+			multisig::MultisigInput,
+		};
+		use pretty_assertions::assert_eq;
+
+		fn before_each() -> (Vec<Wallet>, MultisigInput, Transaction) {
+			let cosigners: Vec<Wallet> = (0..3)
+				.map(|_| Wallet::new(&Keypair::generate_ed25519()))
+				.collect();
+			let public_keys: Vec<Vec<u8>> = cosigners
+				.iter()
+				.map(|wallet| wallet.public_key.clone())
+				.collect();
+			let multisig = MultisigInput::new(2, &public_keys).unwrap();
+			let recipient = Wallet::new(&Keypair::generate_ed25519());
+
+			let transaction = Transaction::new_multisig(
+				multisig.clone(),
+				Amount::new(1000),
+				&recipient.address(),
+				Amount::new(50),
+				Amount::new(2),
+			)
+			.unwrap();
+			(cosigners, multisig, transaction)
+		}
+
+		#[test]
+		fn spends_from_multisig_address() {
+			let (_, multisig, transaction) = before_each();
+
+			assert_eq!(transaction.input.sender_address, multisig.address());
+			assert_eq!(
+				*transaction
+					.output_map
+					.get(&multisig.address())
+					.unwrap(),
+				Amount::new(1000 - 50 - 2)
+			);
+		}
+
+		#[test]
+		fn invalid_below_threshold() {
+			let (cosigners, _, mut transaction) = before_each();
+			transaction.sign_multisig(&cosigners[0]).unwrap();
+
+			assert_eq!(transaction.is_valid(), false);
+		}
+
+		#[test]
+		fn valid_at_threshold() {
+			let (cosigners, _, mut transaction) = before_each();
+			transaction.sign_multisig(&cosigners[0]).unwrap();
+			transaction.sign_multisig(&cosigners[2]).unwrap();
+
+			assert_eq!(transaction.is_valid(), true);
+		}
+
+		#[test]
+		fn same_signer_counts_once() {
+			let (cosigners, _, mut transaction) = before_each();
+			transaction.sign_multisig(&cosigners[1]).unwrap();
+			let signature = cosigners[1]
+				.sign(&transaction.signing_payload())
+				.unwrap();
+			if let Some(multisig) = transaction.input.multisig.as_mut() {
+				multisig.signatures.push(
+					cryptochain::multisig::PartialSignature {
+						public_key: cosigners[1].public_key.clone(),
+						signature,
+					},
+				);
+			}
+
+			assert_eq!(transaction.is_valid(), false);
+		}
+
+		#[test]
+		fn zero_threshold_input_is_invalid() {
+			let (_, _, mut transaction) = before_each();
+			let multisig = transaction.input.multisig.as_mut().unwrap();
+			multisig.threshold = 0;
+			transaction.input.sender_address = multisig.address();
+
+			assert_eq!(transaction.is_valid(), false);
+		}
+
+		#[test]
+		fn rejects_partial_signature_over_other_data() {
+			let (cosigners, _, mut transaction) = before_each();
+			let signature = cosigners[0].sign(b"other data").unwrap();
+
+			let res = transaction
+				.add_partial_signature(&cosigners[0].public_key, signature);
+			assert_eq!(res.is_err(), true);
+		}
+
+		#[test]
+		fn tampered_outputs_invalidate_signatures() {
+			let (cosigners, multisig, mut transaction) = before_each();
+			transaction.sign_multisig(&cosigners[0]).unwrap();
+			transaction.sign_multisig(&cosigners[1]).unwrap();
+			transaction
+				.output_map
+				.insert(multisig.address(), Amount::new(900));
+			transaction.fee = Amount::new(1000 - 50 - 900);
+
+			assert_eq!(transaction.is_valid(), false);
+		}
+
+		#[test]
+		fn valid_in_chain_with_funded_address() {
+			let (cosigners, multisig, _) = before_each();
+			let mut blockchain = Blockchain::new();
+			let funding = Transaction::reward_transaction(
+				&multisig.address(),
+				INITIAL_BLOCK_SUBSIDY,
+			);
+			blockchain.add_block(vec![funding.to_block_data().unwrap()]);
+
+			let balance = Wallet::calculate_balance(
+				&blockchain.chain,
+				&multisig.address(),
+			);
+			let recipient = Wallet::new(&Keypair::generate_ed25519());
+			let mut transaction = Transaction::new_multisig(
+				multisig,
+				balance,
+				&recipient.address(),
+				Amount::new(20),
+				Amount::ZERO,
+			)
+			.unwrap();
+			transaction.sign_multisig(&cosigners[0]).unwrap();
+			transaction.sign_multisig(&cosigners[1]).unwrap();
+			blockchain.add_block(vec![transaction.to_block_data().unwrap()]);
+
+			assert_eq!(Blockchain::is_valid_chain(&blockchain.chain), true);
+		}
+	}
+
+	mod test_output_map_properties {
+		use std::collections::BTreeMap;
+
+		use cryptochain::{
+			config::ADDRESS_VERSION, traits::BinarySerializable,
+			txn_input::TransactionInput,
+		};
+		use libp2p::identity::Keypair;
+		use pretty_assertions::assert_eq;
+		use proptest::prelude::*;
+		use uuid::Uuid;
+
+		use super::*;
+
+		const SENDER_SECRET: [u8; 32] = [7; 32];
+
+		// same outputs inserted in the given order
+		fn transaction(
+			sender: &Wallet,
+			outputs: &[(Address, Amount)],
+			fee: Amount,
+		) -> Transaction {
+			let output_map: BTreeMap<Address, Amount> =
+				outputs.iter().cloned().collect();
+			let output_total =
+				Amount::checked_sum(output_map.values().copied()).unwrap();
+			Transaction {
+				id: Uuid::from_u128(1),
+				amount: output_total,
+				fee,
+				lock_time: None,
+				input: TransactionInput {
+					timestamp: 1_700_000_000_000,
+					amount: output_total.checked_add(fee).unwrap(),
+					sender_address: sender.address(),
+					public_key: sender.public_key.clone(),
+					signature: Vec::new(),
+					multisig: None,
+				},
+				output_map,
+			}
+		}
+
+		fn outputs() -> impl Strategy<Value = Vec<(Address, Amount)>> {
+			prop::collection::btree_map(
+				any::<[u8; 32]>()
+					.prop_map(|hash| Address::new(ADDRESS_VERSION, hash)),
+				(1..1_000_000u64).prop_map(Amount::new),
+				1..8,
+			)
+			.prop_map(|outputs| outputs.into_iter().collect())
+			.prop_shuffle()
+		}
+
+		proptest! {
+			#[test]
+			fn encoding_round_trips(outputs in outputs(), fee in (0..1000u64).prop_map(Amount::new)) {
+				let sender = Wallet::new(
+					&Keypair::ed25519_from_bytes(SENDER_SECRET).unwrap(),
+				);
+				let transaction = transaction(&sender, &outputs, fee);
+
+				let bytes = transaction.to_bytes().unwrap();
+
+				assert_eq!(Transaction::from_bytes(&bytes).unwrap(), transaction);
+			}
+
+			#[test]
+			fn insertion_order_does_not_change_bytes(
+				outputs in outputs(),
+				fee in (0..1000u64).prop_map(Amount::new),
+			) {
+				let sender = Wallet::new(
+					&Keypair::ed25519_from_bytes(SENDER_SECRET).unwrap(),
+				);
+				let reversed: Vec<(Address, Amount)> =
+					outputs.iter().rev().cloned().collect();
+
+				let forward = transaction(&sender, &outputs, fee);
+				let backward = transaction(&sender, &reversed, fee);
+
+				assert_eq!(forward.to_bytes().unwrap(), backward.to_bytes().unwrap());
+				assert_eq!(forward.signing_payload(), backward.signing_payload());
+			}
+
+			#[test]
+			fn signature_verifies_on_independent_map(
+				outputs in outputs(),
+				fee in (0..1000u64).prop_map(Amount::new),
+			) {
+				let sender = Wallet::new(
+					&Keypair::ed25519_from_bytes(SENDER_SECRET).unwrap(),
+				);
+				let reversed: Vec<(Address, Amount)> =
+					outputs.iter().rev().cloned().collect();
+				let mut signed = transaction(&sender, &outputs, fee);
+				signed.input.signature =
+					sender.sign(&signed.signing_payload()).unwrap();
+
+				let mut rebuilt = transaction(&sender, &reversed, fee);
+				rebuilt.input.signature = signed.input.signature.clone();
+
+				assert_eq!(rebuilt.is_valid(), true);
+			}
+		}
+	}
 }
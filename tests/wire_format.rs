@@ -0,0 +1,205 @@
+/**
+ * Golden encodings of every envelope format version. Nodes of different
+ * versions exchange these bytes, so a layout change has to bump the format
+ * version in `wire::MessageType` and add new golden files next to the old
+ * ones. `UPDATE_GOLDEN=1 cargo test --test wire_format` rewrites the files.
+ */
+mod wire_format {
+	use std::{
+		collections::{BTreeMap, HashMap},
+		fs,
+		path::PathBuf,
+	};
+
+	use cryptochain::{
+		address::Address,
+		amount::Amount,
+		block::{Block, BlockTr},
+		blockchain::{Blockchain, BlockchainTr},
+		traits::BinarySerializable,
+		transaction::Transaction,
+		transaction_pool::TransactionPool,
+		txn_input::TransactionInput,
+		wallet::Wallet,
+		wire::MessageType,
+	};
+	use libp2p::identity::Keypair;
+	use pretty_assertions::assert_eq;
+	use uuid::Uuid;
+
+	const SENDER_SECRET: [u8; 32] = [7; 32];
+	const RECIPIENT_SECRET: [u8; 32] = [9; 32];
+	const TXN_ID: u128 = 0x0123_4567_89ab_cdef_0123_4567_89ab_cdef;
+	const TIMESTAMP: i64 = 1_700_000_000_000;
+	const AMOUNT: Amount = Amount::new(50);
+
+	fn golden_transaction() -> Transaction {
+		let sender =
+			Wallet::new(&Keypair::ed25519_from_bytes(SENDER_SECRET).unwrap());
+		let recipient = Wallet::new(
+			&Keypair::ed25519_from_bytes(RECIPIENT_SECRET).unwrap(),
+		);
+		let mut output_map: BTreeMap<Address, Amount> = BTreeMap::new();
+		output_map.insert(recipient.address(), AMOUNT);
+
+		let mut transaction = Transaction {
+			id: Uuid::from_u128(TXN_ID),
+			amount: AMOUNT,
+			fee: Amount::ZERO,
+			lock_time: None,
+			input: TransactionInput {
+				timestamp: TIMESTAMP,
+				amount: AMOUNT,
+				sender_address: sender.address(),
+				public_key: sender.public_key.clone(),
+				signature: Vec::new(),
+				multisig: None,
+			},
+			output_map,
+		};
+		transaction.input.signature = sender
+			.sign(&transaction.signing_payload())
+			.unwrap();
+		transaction
+	}
+
+	fn golden_chain() -> Vec<Block> {
+		let genesis = Block::genesis();
+		let block = Block::new(
+			TIMESTAMP,
+			genesis.hash.clone(),
+			vec![0xab; 32],
+			vec![golden_transaction().to_block_data().unwrap()],
+			7,
+			4,
+		);
+		vec![genesis, block]
+	}
+
+	fn golden_path(name: &str) -> PathBuf {
+		PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+			.join("tests/golden")
+			.join(format!("{name}.hex"))
+	}
+
+	fn check_golden(name: &str, bytes: &[u8]) {
+		let path = golden_path(name);
+		if std::env::var_os("UPDATE_GOLDEN").is_some() {
+			fs::create_dir_all(path.parent().unwrap()).unwrap();
+			fs::write(&path, hex::encode(bytes) + "\n").unwrap();
+		}
+		let golden = fs::read_to_string(&path).unwrap();
+		assert_eq!(hex::encode(bytes), golden.trim());
+	}
+
+	fn golden_bytes(name: &str) -> Vec<u8> {
+		hex::decode(
+			fs::read_to_string(golden_path(name))
+				.unwrap()
+				.trim(),
+		)
+		.unwrap()
+	}
+
+	#[test]
+	fn transaction_v3() {
+		assert_eq!(MessageType::Transaction.format_version(), 3);
+		let transaction = golden_transaction();
+
+		check_golden("transaction_v3", &transaction.to_bytes().unwrap());
+		let decoded =
+			Transaction::from_bytes(&golden_bytes("transaction_v3")).unwrap();
+
+		assert_eq!(decoded, transaction);
+	}
+
+	#[test]
+	fn transaction_pool_v3() {
+		assert_eq!(MessageType::TransactionPool.format_version(), 3);
+		let transaction = golden_transaction();
+		let transaction_pool = TransactionPool::from_transaction_map(
+			HashMap::from([(transaction.id, transaction.clone())]),
+		);
+
+		check_golden(
+			"transaction_pool_v3",
+			&transaction_pool.to_bytes().unwrap(),
+		);
+		let decoded =
+			TransactionPool::from_bytes(&golden_bytes("transaction_pool_v3"))
+				.unwrap();
+
+		assert_eq!(
+			decoded.transaction_map().get(&transaction.id),
+			Some(&transaction)
+		);
+	}
+
+	#[test]
+	fn chain_v3() {
+		assert_eq!(MessageType::Chain.format_version(), 3);
+		let chain = golden_chain();
+
+		check_golden("chain_v3", &Blockchain::to_bytes(&chain).unwrap());
+		let decoded =
+			Blockchain::from_bytes(&golden_bytes("chain_v3")).unwrap();
+
+		assert_eq!(decoded, chain);
+	}
+
+	// v1 stored amounts as u32, v2 keyed outputs by raw public keys
+	#[test]
+	fn rejects_old_encodings() {
+		for version in 1..=2 {
+			let transaction = Transaction::from_bytes(&golden_bytes(&format!(
+				"transaction_v{version}"
+			)));
+			let pool = TransactionPool::from_bytes(&golden_bytes(&format!(
+				"transaction_pool_v{version}"
+			)));
+			let chain = Blockchain::from_bytes(&golden_bytes(&format!(
+				"chain_v{version}"
+			)));
+
+			assert_eq!(
+				transaction.unwrap_err().to_string(),
+				format!(
+					"Unsupported transaction format version {version}, expected 3."
+				)
+			);
+			assert_eq!(
+				pool.err().unwrap().to_string(),
+				format!(
+					"Unsupported transaction pool format version {version}, expected 3."
+				)
+			);
+			assert_eq!(
+				chain.unwrap_err().to_string(),
+				format!(
+					"Unsupported chain format version {version}, expected 3."
+				)
+			);
+		}
+	}
+
+	#[test]
+	fn rejects_future_versions() {
+		let mut bytes = golden_bytes("transaction_v3");
+		bytes[5] = 4;
+
+		let err = Transaction::from_bytes(&bytes).unwrap_err();
+
+		assert_eq!(
+			err.to_string(),
+			"Unsupported transaction format version 4, expected 3."
+		);
+	}
+
+	#[test]
+	fn rejects_payloads_without_envelope() {
+		let bytes = golden_bytes("chain_v3");
+
+		assert!(Blockchain::from_bytes(&bytes[14..]).is_err());
+		assert!(Transaction::from_bytes(&bytes).is_err());
+	}
+}